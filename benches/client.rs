@@ -0,0 +1,104 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    process::Command,
+    thread,
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Minimal sysbus endpoint answering login, logout and `NMC.getWANStatus`
+fn spawn_mock_livebox() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("unable to bind mock Livebox");
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || serve(stream));
+        }
+    });
+    base_url
+}
+
+fn serve(stream: TcpStream) {
+    stream.set_nodelay(true).ok();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let body = String::from_utf8_lossy(&body);
+        let (status, response) = if body.contains("createContext") {
+            (
+                "200 OK",
+                r#"{"status":0,"data":{"contextID":"bench","username":"admin","groups":"http,admin"}}"#,
+            )
+        } else if body.contains("releaseContext") {
+            ("401 Unauthorized", r#"{"status":1}"#)
+        } else {
+            (
+                "200 OK",
+                r#"{"status":true,"data":{"WanState":"up","LinkState":"up","ConnectionState":"Bound","IPAddress":"192.0.2.1"}}"#,
+            )
+        };
+        let written = write!(
+            writer,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response}",
+            response.len()
+        );
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+fn session(c: &mut Criterion) {
+    let base_url = spawn_mock_livebox();
+    let cache_dir = std::env::temp_dir().join("livebox-cli-bench");
+    let run = |args: &[&str]| {
+        let status = Command::new(env!("CARGO_BIN_EXE_livebox-cli"))
+            .env("XDG_CACHE_HOME", &cache_dir)
+            .args(["--base-url", &base_url, "--password", "bench"])
+            .args(args)
+            .output()
+            .expect("unable to run livebox-cli")
+            .status;
+        assert!(status.success());
+    };
+    c.bench_function("exec", |b| {
+        b.iter(|| run(&["exec", "--service", "NMC", "--method", "getWANStatus"]))
+    });
+    c.bench_function("exec --model", |b| {
+        b.iter(|| {
+            run(&[
+                "exec",
+                "--service",
+                "NMC",
+                "--method",
+                "getWANStatus",
+                "--model",
+                "WANStatus",
+            ])
+        })
+    });
+}
+
+criterion_group!(benches, session);
+criterion_main!(benches);
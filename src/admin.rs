@@ -0,0 +1,46 @@
+use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
+use secrecy::SecretString;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    i18n::Message,
+    livebox::{Client, ClientBuilder},
+};
+
+#[derive(Serialize, JsonSchema)]
+pub struct PasswordChangeReport {
+    username: String,
+    /// A new session was opened with the new password
+    verified: bool,
+}
+
+/// Changes the password of the authenticated user and logs in again with the new one.
+pub async fn change_password(
+    client: &Client,
+    builder: &ClientBuilder,
+    new_password: SecretString,
+) -> Result<Value> {
+    let username = builder
+        .username()
+        .ok_or_else(|| anyhow!(Message::MissingCredentials.to_string()))?;
+    client
+        .change_password(
+            username.to_string(),
+            builder.password()?,
+            new_password.clone(),
+        )
+        .await?;
+    let verification = builder
+        .clone()
+        .with_credentials(username.to_string(), new_password)
+        .build()
+        .await
+        .context(Message::PasswordNotVerified.to_string())?;
+    verification.logout().await?;
+    Ok(serde_json::to_value(PasswordChangeReport {
+        username: username.to_string(),
+        verified: true,
+    })?)
+}
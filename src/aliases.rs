@@ -0,0 +1,167 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, net::IpAddr, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    config, i18n::Message, livebox::DeviceView, Commands, DiagActions, DmzActions, FirewallActions,
+    Session,
+};
+
+/// Device names given by the user, kept in `aliases.toml` of the configuration directory
+pub struct Aliases {
+    path: PathBuf,
+    /// MAC addresses by alias
+    aliases: BTreeMap<String, String>,
+}
+
+/// A device with its alias
+#[derive(Serialize, JsonSchema)]
+pub struct AliasedDevice {
+    #[serde(rename = "Alias", skip_serializing_if = "Option::is_none")]
+    alias: Option<String>,
+
+    #[serde(flatten)]
+    device: DeviceView,
+}
+
+impl Aliases {
+    pub fn load() -> Result<Self> {
+        let path = config::config_dir()
+            .map(|dir| dir.join("aliases.toml"))
+            .ok_or_else(|| anyhow!(Message::NoConfigDirectory.to_string()))?;
+        let aliases = match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| Message::InvalidAliases(&path).to_string())?,
+            Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err).with_context(|| Message::UnableToRead(&path).to_string()),
+        };
+        Ok(Self { path, aliases })
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, toml::to_string(&self.aliases)?)
+            .with_context(|| Message::UnableToWrite(&self.path).to_string())
+    }
+
+    pub fn list(&self) -> &BTreeMap<String, String> {
+        &self.aliases
+    }
+
+    /// Names the device, replacing any previous alias of the device. MAC addresses are stored in
+    /// lowercase.
+    pub fn set(&mut self, mac: &str, name: String) -> Result<()> {
+        let mac = mac.to_ascii_lowercase();
+        self.aliases
+            .retain(|_, aliased| !aliased.eq_ignore_ascii_case(&mac));
+        self.aliases.insert(name, mac);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        self.aliases
+            .remove(name)
+            .ok_or_else(|| anyhow!(Message::UnknownAlias(name).to_string()))?;
+        self.save()
+    }
+
+    pub fn alias_of(&self, mac: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(_, aliased)| aliased.eq_ignore_ascii_case(mac))
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn annotate(&self, devices: Vec<DeviceView>) -> Vec<AliasedDevice> {
+        devices
+            .into_iter()
+            .map(|device| AliasedDevice {
+                alias: self.alias_of(&device.phys_address).map(str::to_string),
+                device,
+            })
+            .collect()
+    }
+}
+
+/// Replaces an alias or a device name (ex: `nas` or `nas.lan`) given as host by the current IP
+/// address of the device.
+pub async fn resolve_hosts(session: &Session, command: &mut Commands) -> Result<()> {
+    let host = match command {
+        Commands::Nat {
+            action: FirewallActions::Add(rule) | FirewallActions::AddTemp { rule, .. },
+        } => &mut rule.destination_host,
+        Commands::Nat {
+            action:
+                FirewallActions::FromCompose {
+                    host: Some(host), ..
+                },
+        } => host,
+        Commands::Dmz {
+            action: DmzActions::Set { destination },
+        } => destination,
+        Commands::Diag {
+            action: DiagActions::Ping { host, .. } | DiagActions::Traceroute { host, .. },
+        } => host,
+        _ => return Ok(()),
+    };
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let aliases = Aliases::load()?;
+    let mac = aliases.aliases.get(host.as_str());
+    let name = host.strip_suffix(".lan").unwrap_or(host);
+    let device = session
+        .client()
+        .await?
+        .list_devices()
+        .await?
+        .into_iter()
+        .find(|device| match mac {
+            Some(mac) => device.has_mac_address(mac),
+            None => device.name.eq_ignore_ascii_case(name),
+        });
+    match device {
+        Some(device) if !device.ip_address.is_empty() => *host = device.ip_address,
+        // not a device, left to the Livebox
+        None if mac.is_none() => {}
+        _ => return Err(anyhow!(Message::NoDeviceAddress(host).to_string())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn replaces_the_alias_of_a_device_whatever_the_mac_case() {
+        let dir = env::temp_dir().join(format!("livebox-cli-aliases-{}", process::id()));
+        let mut aliases = Aliases {
+            path: dir.join("aliases.toml"),
+            aliases: BTreeMap::new(),
+        };
+
+        aliases.set("AA:BB:CC:DD:EE:FF", "nas".to_string()).unwrap();
+        aliases
+            .set("aa:bb:cc:dd:ee:ff", "storage".to_string())
+            .unwrap();
+        aliases.set("00:11:22:33:44:55", "tv".to_string()).unwrap();
+
+        let expected = BTreeMap::from([
+            ("storage".to_string(), "aa:bb:cc:dd:ee:ff".to_string()),
+            ("tv".to_string(), "00:11:22:33:44:55".to_string()),
+        ]);
+        assert_eq!(aliases.list(), &expected);
+        assert_eq!(aliases.alias_of("AA:BB:CC:DD:EE:FF"), Some("storage"));
+        let saved: BTreeMap<String, String> =
+            toml::from_str(&fs::read_to_string(&aliases.path).unwrap()).unwrap();
+        assert_eq!(saved, expected);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
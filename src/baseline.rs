@@ -0,0 +1,211 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i18n::Message;
+
+/// JSON patch (RFC 6902) operation turning the baseline into the current response
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// Differences between a response and the baseline stored in a JSON file, none when it didn't
+/// drift.
+pub fn compare(path: &Path, current: &Value) -> Result<Vec<Operation>> {
+    let file = File::open(path).with_context(|| Message::UnableToOpen(path).to_string())?;
+    let baseline: Value = serde_json::from_reader(file)
+        .with_context(|| Message::InvalidBaseline(path).to_string())?;
+    let mut patch = Vec::new();
+    diff("", &baseline, current, &mut patch);
+    Ok(patch)
+}
+
+/// Object members are compared by name, array items by index, other values replaced as a whole.
+fn diff(path: &str, baseline: &Value, current: &Value, patch: &mut Vec<Operation>) {
+    match (baseline, current) {
+        (Value::Object(baseline), Value::Object(current)) => {
+            for (name, value) in baseline {
+                let path = format!("{path}/{}", escape(name));
+                match current.get(name) {
+                    Some(current) => diff(&path, value, current, patch),
+                    None => patch.push(Operation::Remove { path }),
+                }
+            }
+            for (name, value) in current {
+                if !baseline.contains_key(name) {
+                    patch.push(Operation::Add {
+                        path: format!("{path}/{}", escape(name)),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(baseline), Value::Array(current)) => {
+            for (index, (baseline, current)) in baseline.iter().zip(current).enumerate() {
+                diff(&format!("{path}/{index}"), baseline, current, patch);
+            }
+            for value in current.iter().skip(baseline.len()) {
+                patch.push(Operation::Add {
+                    path: format!("{path}/-"),
+                    value: value.clone(),
+                });
+            }
+            // last items first so the indexes stay valid
+            for index in (current.len()..baseline.len()).rev() {
+                patch.push(Operation::Remove {
+                    path: format!("{path}/{index}"),
+                });
+            }
+        }
+        (baseline, current) if baseline != current => patch.push(Operation::Replace {
+            path: path.to_string(),
+            value: current.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// JSON pointer (RFC 6901) reference token of a member name
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn patch(baseline: Value, current: Value) -> Vec<Operation> {
+        let mut patch = Vec::new();
+        diff("", &baseline, &current, &mut patch);
+        patch
+    }
+
+    #[test]
+    fn adds_removes_and_replaces_members() {
+        assert_eq!(
+            patch(
+                json!({"kept": 1, "changed": "a", "removed": true}),
+                json!({"kept": 1, "changed": "b", "added": null}),
+            ),
+            vec![
+                Operation::Replace {
+                    path: "/changed".to_string(),
+                    value: json!("b"),
+                },
+                Operation::Remove {
+                    path: "/removed".to_string(),
+                },
+                Operation::Add {
+                    path: "/added".to_string(),
+                    value: json!(null),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_nested_objects_by_member() {
+        assert_eq!(
+            patch(
+                json!({"status": {"wan": {"state": "up", "ip": "1.2.3.4"}}}),
+                json!({"status": {"wan": {"state": "down", "ip": "1.2.3.4"}}}),
+            ),
+            vec![Operation::Replace {
+                path: "/status/wan/state".to_string(),
+                value: json!("down"),
+            }]
+        );
+    }
+
+    #[test]
+    fn compares_arrays_by_index() {
+        assert_eq!(
+            patch(json!([1, {"id": "a"}, 3]), json!([1, {"id": "b"}, 3, 4, 5])),
+            vec![
+                Operation::Replace {
+                    path: "/1/id".to_string(),
+                    value: json!("b"),
+                },
+                Operation::Add {
+                    path: "/-".to_string(),
+                    value: json!(4),
+                },
+                Operation::Add {
+                    path: "/-".to_string(),
+                    value: json!(5),
+                },
+            ]
+        );
+        assert_eq!(
+            patch(json!({"rules": [1, 2, 3, 4]}), json!({"rules": [1, 2]})),
+            vec![
+                Operation::Remove {
+                    path: "/rules/3".to_string(),
+                },
+                Operation::Remove {
+                    path: "/rules/2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn replaces_values_of_another_type() {
+        assert_eq!(
+            patch(json!({"data": [1]}), json!({"data": {"0": 1}})),
+            vec![Operation::Replace {
+                path: "/data".to_string(),
+                value: json!({"0": 1}),
+            }]
+        );
+        assert_eq!(
+            patch(json!(1), json!("1")),
+            vec![Operation::Replace {
+                path: String::new(),
+                value: json!("1"),
+            }]
+        );
+    }
+
+    #[test]
+    fn escapes_member_names() {
+        assert_eq!(
+            patch(
+                json!({"a/b": 1, "m~n": {"~/": 1}}),
+                json!({"a/b": 2, "m~n": {"~/": 2}}),
+            ),
+            vec![
+                Operation::Replace {
+                    path: "/a~1b".to_string(),
+                    value: json!(2),
+                },
+                Operation::Replace {
+                    path: "/m~0n/~0~1".to_string(),
+                    value: json!(2),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_no_drift_against_an_identical_baseline() {
+        let path = env::temp_dir().join(format!("livebox-cli-baseline-{}.json", process::id()));
+        let response = json!({"status": true, "data": {"rules": [{"id": "ssh"}]}});
+        fs::write(&path, response.to_string()).unwrap();
+
+        let patch = compare(&path, &response);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(patch.unwrap(), vec![]);
+    }
+}
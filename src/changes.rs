@@ -0,0 +1,192 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::{self, Write},
+    io::{self, IsTerminal},
+};
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    livebox::Client, redact, Commands, FirewallActions, OutputArgs, RadioActions, WifiActions,
+};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Objects a mutating command may change, fetched before and after it runs
+#[derive(Clone, Copy)]
+pub enum Tracked {
+    NatRules,
+    Radios,
+}
+
+type Objects = BTreeMap<String, BTreeMap<String, Value>>;
+
+impl Tracked {
+    pub fn of(command: &Commands) -> Option<Self> {
+        match command {
+            Commands::Nat {
+                action: FirewallActions::List,
+            } => None,
+            Commands::Nat { .. } => Some(Tracked::NatRules),
+            Commands::Wifi {
+                action:
+                    WifiActions::Radio {
+                        action: RadioActions::Set(_),
+                    },
+            } => Some(Tracked::Radios),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Tracked::NatRules => "nat",
+            Tracked::Radios => "radio",
+        }
+    }
+
+    pub async fn fetch(&self, client: &Client) -> Result<Objects> {
+        let objects: BTreeMap<String, Value> = match self {
+            Tracked::NatRules => client
+                .structured_nat_rules()
+                .await?
+                .into_iter()
+                .map(|rule| Ok((rule.id.clone(), serde_json::to_value(rule)?)))
+                .collect::<Result<_>>()?,
+            Tracked::Radios => client
+                .list_radios()
+                .await?
+                .into_iter()
+                .map(|(name, radio)| Ok((name, serde_json::to_value(radio)?)))
+                .collect::<Result<_>>()?,
+        };
+        Ok(objects
+            .into_iter()
+            .map(|(id, object)| {
+                let mut fields = BTreeMap::new();
+                flatten("", object, &mut fields);
+                (id, fields)
+            })
+            .collect())
+    }
+
+    /// Prints the added, removed and changed objects to stderr, colored unless disabled, `NO_COLOR`
+    /// is set or stderr isn't a terminal, and masking personal data with `--redact`.
+    pub fn report(&self, before: &Objects, after: &Objects, args: &OutputArgs) -> Result<()> {
+        let (before, after) = match args.redact {
+            true => (redacted(before)?, redacted(after)?),
+            false => (before.clone(), after.clone()),
+        };
+        let color =
+            !args.no_color && env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal();
+        let mut output = Output {
+            text: String::new(),
+            color,
+        };
+        let ids: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+        for id in ids {
+            match (before.get(id), after.get(id)) {
+                (Some(fields), None) => {
+                    output.line(RED, format_args!("- {} {id}", self.kind()));
+                    for (name, value) in fields {
+                        output.line(RED, format_args!("  - {name}: {value}"));
+                    }
+                }
+                (None, Some(fields)) => {
+                    output.line(GREEN, format_args!("+ {} {id}", self.kind()));
+                    for (name, value) in fields {
+                        output.line(GREEN, format_args!("  + {name}: {value}"));
+                    }
+                }
+                (Some(old), Some(new)) if old != new => {
+                    output.line(YELLOW, format_args!("~ {} {id}", self.kind()));
+                    let names: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+                    for name in names {
+                        if old.get(name) == new.get(name) {
+                            continue;
+                        }
+                        if let Some(value) = old.get(name) {
+                            output.line(RED, format_args!("  - {name}: {value}"));
+                        }
+                        if let Some(value) = new.get(name) {
+                            output.line(GREEN, format_args!("  + {name}: {value}"));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        eprint!("{}", output.text);
+        Ok(())
+    }
+}
+
+/// Objects with their personal data masked, pseudonyms stay consistent between both fetches
+fn redacted(objects: &Objects) -> Result<Objects> {
+    Ok(serde_json::from_value(redact::redact(
+        serde_json::to_value(objects)?,
+    ))?)
+}
+
+struct Output {
+    text: String,
+    color: bool,
+}
+
+impl Output {
+    fn line(&mut self, color: &str, line: fmt::Arguments) {
+        // writing to a String never fails
+        let _ = match self.color {
+            true => writeln!(self.text, "{color}{line}{RESET}"),
+            false => writeln!(self.text, "{line}"),
+        };
+    }
+}
+
+fn flatten(prefix: &str, value: Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(object) => {
+            for (name, value) in object {
+                let name = match prefix {
+                    "" => name,
+                    _ => format!("{prefix}.{name}"),
+                };
+                flatten(&name, value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn redacts_objects_with_the_same_pseudonyms() {
+        let objects = |destination: &str| -> Objects {
+            let mut fields = BTreeMap::new();
+            fields.insert("DestinationIPAddress".to_string(), json!(destination));
+            fields.insert("SourcePrefix".to_string(), json!("55.27.2.115"));
+            BTreeMap::from([("webui_ssh".to_string(), fields)])
+        };
+        let before = redacted(&objects("192.168.1.10")).unwrap();
+        let after = redacted(&objects("192.168.1.11")).unwrap();
+        let field = |objects: &Objects, name: &str| objects["webui_ssh"][name].clone();
+        assert_eq!(field(&before, "DestinationIPAddress"), "192.168.1.10");
+        assert_ne!(field(&before, "SourcePrefix"), "55.27.2.115");
+        assert_eq!(
+            field(&before, "SourcePrefix"),
+            field(&after, "SourcePrefix")
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::BTreeMap, fs::File, net::IpAddr, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+use crate::{declarative::NatRuleState, i18n::Message, Protocol};
+
+#[derive(Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    services: BTreeMap<String, Service>,
+}
+
+#[derive(Deserialize)]
+struct Service {
+    #[serde(default)]
+    ports: Vec<PortMapping>,
+}
+
+/// Port of a compose service, in short (`"127.0.0.1:8080:80/udp"`) or long syntax
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortMapping {
+    #[allow(dead_code)]
+    ContainerOnly(u16),
+    Short(String),
+    Long {
+        #[serde(default)]
+        published: Option<PublishedPort>,
+        #[serde(default)]
+        host_ip: Option<String>,
+        #[serde(default)]
+        protocol: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PublishedPort {
+    Number(u16),
+    String(String),
+}
+
+/// Port published on the Docker host
+struct PublishedMapping {
+    host_ip: Option<String>,
+    port: String,
+    protocol: String,
+}
+
+impl PortMapping {
+    /// The published port, `None` when the port is only exposed to other containers
+    fn published(self) -> Option<PublishedMapping> {
+        match self {
+            PortMapping::ContainerOnly(_) => None,
+            PortMapping::Short(mapping) => {
+                let (mapping, protocol) = mapping
+                    .split_once('/')
+                    .map_or((mapping.as_str(), "tcp"), |(mapping, protocol)| {
+                        (mapping, protocol)
+                    });
+                let (host, _container) = mapping.rsplit_once(':')?;
+                let (host_ip, port) = match host.rsplit_once(':') {
+                    Some((host_ip, port)) => (Some(host_ip.to_string()), port),
+                    None => (None, host),
+                };
+                Some(PublishedMapping {
+                    host_ip,
+                    port: port.to_string(),
+                    protocol: protocol.to_string(),
+                })
+            }
+            PortMapping::Long {
+                published,
+                host_ip,
+                protocol,
+            } => Some(PublishedMapping {
+                host_ip,
+                port: match published? {
+                    PublishedPort::Number(port) => port.to_string(),
+                    PublishedPort::String(port) => port,
+                },
+                protocol: protocol.unwrap_or_else(|| "tcp".to_string()),
+            }),
+        }
+        .filter(|mapping| !mapping.port.is_empty())
+    }
+}
+
+impl PublishedMapping {
+    fn is_loopback(&self) -> bool {
+        self.host_ip.as_deref().is_some_and(|host_ip| {
+            host_ip
+                .trim_matches(['[', ']'])
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+        })
+    }
+}
+
+/// NAT rules forwarding the ports published by the services of a compose file to the Docker host.
+///
+/// Ports bound to a loopback address are skipped, as they aren't reachable from the Livebox.
+/// Variables (ex: `${PORT}:80`) are rejected as the compose file isn't interpolated.
+pub fn nat_rules(path: &Path, destination: &str) -> Result<Vec<NatRuleState>> {
+    let file = File::open(path).with_context(|| Message::UnableToOpen(path).to_string())?;
+    let compose: ComposeFile = serde_yaml::from_reader(file)
+        .with_context(|| Message::InvalidConfiguration(path).to_string())?;
+    compose.nat_rules(destination)
+}
+
+impl ComposeFile {
+    fn nat_rules(self, destination: &str) -> Result<Vec<NatRuleState>> {
+        let mut rules = Vec::new();
+        for (name, service) in self.services {
+            for mapping in service.ports.into_iter().filter_map(PortMapping::published) {
+                let published = match &mapping.host_ip {
+                    Some(host_ip) => format!("{host_ip}:{}", mapping.port),
+                    None => mapping.port.clone(),
+                };
+                if published.contains('$') {
+                    return Err(anyhow!(
+                        Message::ComposeVariable(&name, &published).to_string()
+                    ));
+                }
+                if mapping.is_loopback() {
+                    continue;
+                }
+                let protocol = match mapping.protocol.as_str() {
+                    "tcp" => Protocol::Tcp,
+                    "udp" => Protocol::Udp,
+                    protocol => {
+                        warn!(
+                            "{name}: {protocol} port {} can't be forwarded",
+                            mapping.port
+                        );
+                        continue;
+                    }
+                };
+                rules.push(NatRuleState {
+                    id: format!("{name}-{}-{}", mapping.port, mapping.protocol),
+                    description: format!("{name} (compose)"),
+                    protocol,
+                    external_port: mapping.port.clone(),
+                    internal_port: mapping.port,
+                    destination: destination.to_string(),
+                    enable: true,
+                });
+            }
+        }
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(compose: &str) -> Result<Vec<(String, String)>> {
+        let compose: ComposeFile = serde_yaml::from_str(compose).unwrap();
+        Ok(compose
+            .nat_rules("192.168.1.50")?
+            .into_iter()
+            .map(|rule| (rule.id, rule.external_port))
+            .collect())
+    }
+
+    fn rule(id: &str, port: &str) -> (String, String) {
+        (id.to_string(), port.to_string())
+    }
+
+    #[test]
+    fn forwards_published_ports_of_the_short_syntax() {
+        let compose = r#"
+services:
+  web:
+    ports:
+      - "8080:80"
+      - "127.0.0.1:8081:80"
+      - "[::1]:8082:80"
+      - "0.0.0.0:8443:443"
+      - "80"
+      - 9000
+      - "8000-8010:8000-8010"
+      - "53:53/udp"
+      - "5000:5000/sctp"
+"#;
+        assert_eq!(
+            rules(compose).unwrap(),
+            vec![
+                rule("web-8080-tcp", "8080"),
+                rule("web-8443-tcp", "8443"),
+                rule("web-8000-8010-tcp", "8000-8010"),
+                rule("web-53-udp", "53"),
+            ]
+        );
+    }
+
+    #[test]
+    fn forwards_published_ports_of_the_long_syntax() {
+        let compose = r#"
+services:
+  dns:
+    ports:
+      - target: 53
+        published: 53
+        protocol: udp
+      - target: 80
+        published: "8080"
+      - target: 81
+        published: 8081
+        host_ip: 127.0.0.1
+      - target: 82
+"#;
+        assert_eq!(
+            rules(compose).unwrap(),
+            vec![rule("dns-53-udp", "53"), rule("dns-8080-tcp", "8080")]
+        );
+    }
+
+    #[test]
+    fn rejects_variables() {
+        for port in ["${WEB_PORT}:80", "${HOST_IP}:8080:80", "$WEB_PORT:80"] {
+            let compose = format!("services:\n  web:\n    ports:\n      - {port}\n");
+            let err = rules(&compose).unwrap_err();
+            let published = port.strip_suffix(":80").unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "web: the port {published} uses a variable, compose files aren't interpolated"
+                )
+            );
+        }
+        let compose = "services:\n  web:\n    ports:\n      - published: ${WEB_PORT}\n";
+        assert!(rules(compose).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    i18n::Message,
+    livebox::{Pool, Profile, RateLimit},
+};
+
+/// Compatibility profiles shipped for known rebrands
+const PROFILES: &[(&str, &str)] = &[("funbox", include_str!("../profiles/funbox.toml"))];
+
+/// Directory holding livebox-cli configuration and local state
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("livebox-cli"))
+}
+
+/// Directory holding livebox-cli disposable state
+pub fn cache_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("livebox-cli"))
+}
+
+/// Directory holding livebox-cli state worth keeping, like the audit log
+pub fn state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("livebox-cli"))
+}
+
+/// State kept in a TOML file of the state directory, the default one until saved
+pub fn load_state<T: DeserializeOwned + Default>(file_name: &str) -> Result<(PathBuf, T)> {
+    let path = state_dir()
+        .map(|dir| dir.join(file_name))
+        .ok_or_else(|| anyhow!(Message::NoStateDirectory.to_string()))?;
+    let state = match fs::read_to_string(&path) {
+        Ok(content) => {
+            toml::from_str(&content).with_context(|| Message::InvalidState(&path).to_string())?
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => T::default(),
+        Err(err) => return Err(err).with_context(|| Message::UnableToRead(&path).to_string()),
+    };
+    Ok((path, state))
+}
+
+pub fn save_state<T: Serialize>(path: &Path, state: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(state)?)
+        .with_context(|| Message::UnableToWrite(path).to_string())
+}
+
+/// Creates or truncates a file only its owner can read, for files holding secrets.
+pub fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(path)?;
+    // the mode only applies to new files
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Settings read from `config.toml`, command line flags take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Compatibility profile, by name or path
+    pub profile: Option<String>,
+    /// Prompt before destructive operations when interactive, enabled unless set to `false`
+    pub always_confirm: Option<bool>,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub pool: PoolConfig,
+    /// JSON lines file recording the configuration changes, `audit.jsonl` of the state directory
+    /// by default
+    pub audit_log: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Minimum milliseconds between two requests
+    pub min_interval_ms: Option<u64>,
+    /// Maximum number of concurrent requests, at least 1
+    pub max_concurrent: Option<NonZeroUsize>,
+    /// Maximum random milliseconds added to the minimum interval
+    pub jitter_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolConfig {
+    /// Seconds after which an unused connection is closed, 0 keeps it forever
+    pub idle_timeout_s: Option<u64>,
+    /// Maximum number of unused connections kept open
+    pub max_idle: Option<usize>,
+    /// Seconds between TCP keep-alive probes, 0 disables them
+    pub tcp_keepalive_s: Option<u64>,
+}
+
+impl Config {
+    /// Loads the given configuration file, or the default one when it exists.
+    pub fn load(path: Option<&PathBuf>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.clone(), true),
+            None => match config_dir() {
+                Some(dir) => (dir.join("config.toml"), false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound && !required => {
+                return Ok(Config::default())
+            }
+            Err(err) => return Err(err).with_context(|| Message::UnableToRead(&path).to_string()),
+        };
+        toml::from_str(&content).with_context(|| Message::InvalidConfiguration(&path).to_string())
+    }
+
+    /// Path of the audit log, unless no directory can hold it
+    pub fn audit_log_path(&self) -> Option<PathBuf> {
+        self.audit_log
+            .clone()
+            .or_else(|| state_dir().map(|dir| dir.join("audit.jsonl")))
+    }
+}
+
+/// Loads a compatibility profile: a shipped one, one from the `profiles` configuration directory
+/// or a TOML file.
+pub fn load_profile(name: &str) -> Result<Profile> {
+    if let Some((_, content)) = PROFILES.iter().find(|(shipped, _)| *shipped == name) {
+        return Ok(toml::from_str(content)?);
+    }
+    let path = config_dir()
+        .map(|dir| dir.join("profiles").join(format!("{name}.toml")))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name));
+    let content =
+        fs::read_to_string(&path).with_context(|| Message::UnableToRead(&path).to_string())?;
+    toml::from_str(&content).with_context(|| Message::InvalidConfiguration(&path).to_string())
+}
+
+impl RateLimitConfig {
+    pub fn to_rate_limit(&self) -> RateLimit {
+        RateLimit {
+            min_interval: Duration::from_millis(self.min_interval_ms.unwrap_or_default()),
+            max_concurrent: self.max_concurrent,
+            jitter: Duration::from_millis(self.jitter_ms.unwrap_or_default()),
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn to_pool(&self) -> Pool {
+        let default = Pool::default();
+        let seconds = |value: u64| (value > 0).then(|| Duration::from_secs(value));
+        Pool {
+            idle_timeout: self.idle_timeout_s.map_or(default.idle_timeout, seconds),
+            max_idle: self.max_idle.unwrap_or(default.max_idle),
+            tcp_keepalive: self.tcp_keepalive_s.map_or(default.tcp_keepalive, seconds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use livebox_cli::livebox::ApiFlavor;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn funbox_profile_remaps_the_device_listing() {
+        let profile = load_profile("funbox").unwrap();
+        assert!(matches!(profile.api_flavor, Some(ApiFlavor::Legacy)));
+
+        let request = json!({
+            "service": "Devices",
+            "method": "get",
+            "parameters": {"expression": "lan and not self"},
+        });
+        assert_eq!(
+            profile.apply(request),
+            json!({
+                "service": "Hosts",
+                "method": "getDevices",
+                "parameters": {"expression": "lan and not self"},
+            })
+        );
+
+        let request = json!({"service": "NMC", "method": "getWANStatus", "parameters": {}});
+        assert_eq!(profile.apply(request.clone()), request);
+    }
+
+    #[test]
+    fn rejects_no_concurrent_requests() {
+        let config: Config = toml::from_str("[rate_limit]\nmax_concurrent = 2").unwrap();
+        assert_eq!(config.rate_limit.max_concurrent, NonZeroUsize::new(2));
+        assert!(toml::from_str::<Config>("[rate_limit]\nmax_concurrent = 0").is_err());
+    }
+}
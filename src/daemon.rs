@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{livebox::Client, nat, redact, rules::Rules, systemd};
+
+/// Change of the Livebox state noticed between two polls
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    DeviceJoined { device: Value },
+    DeviceLeft { device: Value },
+    WanDown { wan: Value },
+    WanUp { wan: Value },
+    WifiToggled { enable: bool },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::DeviceJoined { .. } => "device-joined",
+            Event::DeviceLeft { .. } => "device-left",
+            Event::WanDown { .. } => "wan-down",
+            Event::WanUp { .. } => "wan-up",
+            Event::WifiToggled { .. } => "wifi-toggled",
+        }
+    }
+}
+
+struct State {
+    /// active devices by key
+    devices: BTreeMap<String, Value>,
+    wan_up: bool,
+    wan: Value,
+    wifi_enable: bool,
+}
+
+impl State {
+    async fn fetch(client: &Client) -> Result<Self> {
+        let devices = client
+            .list_devices()
+            .await?
+            .into_iter()
+            .filter(|device| device.active)
+            .map(|device| Ok((device.key.clone(), serde_json::to_value(device)?)))
+            .collect::<Result<_>>()?;
+        let wan = client.get_wan_status().await?;
+        Ok(Self {
+            devices,
+            wan_up: wan.is_up(),
+            wan: serde_json::to_value(wan)?,
+            wifi_enable: client.get_wifi_status().await?.enable,
+        })
+    }
+
+    fn events_since(&self, previous: &State) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (key, device) in &self.devices {
+            if !previous.devices.contains_key(key) {
+                events.push(Event::DeviceJoined {
+                    device: device.clone(),
+                });
+            }
+        }
+        for (key, device) in &previous.devices {
+            if !self.devices.contains_key(key) {
+                events.push(Event::DeviceLeft {
+                    device: device.clone(),
+                });
+            }
+        }
+        match (previous.wan_up, self.wan_up) {
+            (true, false) => events.push(Event::WanDown {
+                wan: self.wan.clone(),
+            }),
+            (false, true) => events.push(Event::WanUp {
+                wan: self.wan.clone(),
+            }),
+            _ => {}
+        }
+        if previous.wifi_enable != self.wifi_enable {
+            events.push(Event::WifiToggled {
+                enable: self.wifi_enable,
+            });
+        }
+        events
+    }
+}
+
+/// Polls the Livebox until interrupted, printing each event as a JSON line, masking personal
+/// data with `redact`, and running the matching rules.
+///
+/// Expired temporary NAT rules are removed and tracked NAT rules follow their device at each
+/// poll. Under systemd, readiness is notified after the first poll and the watchdog is kept alive
+/// between polls.
+pub async fn run(
+    client: &Client,
+    rules: Option<&Rules>,
+    interval: Duration,
+    redact: bool,
+) -> Result<Value> {
+    let mut previous = State::fetch(client).await?;
+    systemd::notify("READY=1");
+    let watchdog = systemd::watchdog_interval();
+    loop {
+        let next_poll = Instant::now() + interval;
+        loop {
+            let remaining = next_poll.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            tokio::time::sleep(watchdog.map_or(remaining, |watchdog| watchdog.min(remaining)))
+                .await;
+            if watchdog.is_some() {
+                systemd::notify("WATCHDOG=1");
+            }
+        }
+        match nat::remove_expired(client).await {
+            Ok(removed) => {
+                for rule in removed {
+                    info!("Removed the expired NAT rule {}", rule.id());
+                }
+            }
+            Err(err) => warn!("Unable to remove the expired NAT rules: {err:#}"),
+        }
+        match nat::follow_tracked(client).await {
+            Ok(updated) => {
+                for (id, destination) in updated {
+                    info!("Forwarded the NAT rule {id} to {destination}");
+                }
+            }
+            Err(err) => warn!("Unable to update the tracked NAT rules: {err:#}"),
+        }
+        let state = match State::fetch(client).await {
+            Ok(state) => state,
+            Err(err) => {
+                warn!("Unable to poll the Livebox: {err:#}");
+                continue;
+            }
+        };
+        for event in state.events_since(&previous) {
+            let line = serde_json::to_value(&event)?;
+            let line = match redact {
+                true => redact::redact(line),
+                false => line,
+            };
+            println!("{line}");
+            if let Some(rules) = rules {
+                rules.react(&event).await;
+            }
+        }
+        previous = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn state(devices: &[&str], wan_up: bool, wifi_enable: bool) -> State {
+        State {
+            devices: devices
+                .iter()
+                .map(|key| (key.to_string(), json!({"Key": key, "Active": true})))
+                .collect(),
+            wan_up,
+            wan: json!({"WanState": if wan_up { "up" } else { "down" }}),
+            wifi_enable,
+        }
+    }
+
+    fn events(state: &State, previous: &State) -> Value {
+        serde_json::to_value(state.events_since(previous)).unwrap()
+    }
+
+    #[test]
+    fn reports_nothing_without_changes() {
+        let state = state(&["nas"], true, true);
+        assert_eq!(events(&state, &state), json!([]));
+    }
+
+    #[test]
+    fn reports_devices_joining_and_leaving() {
+        let previous = state(&["nas", "phone"], true, true);
+        let state = state(&["laptop", "nas"], true, true);
+        assert_eq!(
+            events(&state, &previous),
+            json!([
+                {"event": "device-joined", "device": {"Key": "laptop", "Active": true}},
+                {"event": "device-left", "device": {"Key": "phone", "Active": true}},
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_the_wan_going_down_and_up() {
+        let up = state(&[], true, true);
+        let down = state(&[], false, true);
+        assert_eq!(
+            events(&down, &up),
+            json!([{"event": "wan-down", "wan": {"WanState": "down"}}])
+        );
+        assert_eq!(
+            events(&up, &down),
+            json!([{"event": "wan-up", "wan": {"WanState": "up"}}])
+        );
+    }
+
+    #[test]
+    fn reports_wifi_toggles() {
+        let enabled = state(&[], true, true);
+        let disabled = state(&[], true, false);
+        assert_eq!(
+            events(&disabled, &enabled),
+            json!([{"event": "wifi-toggled", "enable": false}])
+        );
+        assert_eq!(
+            events(&enabled, &disabled),
+            json!([{"event": "wifi-toggled", "enable": true}])
+        );
+    }
+}
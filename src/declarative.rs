@@ -0,0 +1,877 @@
+use std::{collections::BTreeMap, fs::File, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use schemars::JsonSchema;
+use secrecy::SecretString;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config,
+    i18n::Message,
+    livebox::{self, FirewallLevel, NatRuleView, SetPortFowardingParams},
+    OnError, Protocol,
+};
+
+/// Desired Livebox configuration.
+///
+/// Each section is optional: sections absent from the document are left untouched.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct State {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wifi: Option<WifiState>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nat: Option<Vec<NatRuleState>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub static_leases: Option<Vec<StaticLeaseState>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dmz: Option<DmzState>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ddns: Option<Vec<DdnsHostState>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firewall: Option<FirewallState>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WifiState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<bool>,
+
+    /// Access points settings indexed by interface name (ex: `wl0`)
+    #[serde(default)]
+    pub access_points: BTreeMap<String, AccessPointState>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AccessPointState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NatRuleState {
+    pub id: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    pub protocol: Protocol,
+
+    #[serde(deserialize_with = "string_or_number")]
+    pub external_port: String,
+
+    #[serde(deserialize_with = "string_or_number")]
+    pub internal_port: String,
+
+    pub destination: String,
+
+    #[serde(default = "enabled")]
+    pub enable: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StaticLeaseState {
+    pub mac: String,
+    pub ip: String,
+}
+
+/// DMZ host, no destination means the DMZ must be disabled
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DmzState {
+    #[serde(default)]
+    pub destination: Option<String>,
+}
+
+/// Dynamic DNS host, only service and username drift is detected because the box never returns passwords
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DdnsHostState {
+    pub service: String,
+    pub hostname: String,
+    pub username: String,
+
+    #[serde(default, skip_serializing)]
+    #[schemars(with = "String")]
+    pub password: SecretString,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FirewallState {
+    pub level: FirewallLevel,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u16),
+    }
+    Ok(match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(value) => value,
+        StringOrNumber::Number(value) => value.to_string(),
+    })
+}
+
+impl From<NatRuleView> for NatRuleState {
+    fn from(rule: NatRuleView) -> Self {
+        NatRuleState {
+            id: rule
+                .id
+                .strip_prefix(&format!("{}_", rule.origin))
+                .map(str::to_string)
+                .unwrap_or(rule.id),
+            description: rule.description,
+            protocol: rule.protocol.into(),
+            external_port: rule.external_port,
+            internal_port: rule.internal_port,
+            destination: rule.destination_ip_address,
+            enable: rule.enable,
+        }
+    }
+}
+
+impl State {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| Message::UnableToOpen(path).to_string())?;
+        serde_yaml::from_reader(file)
+            .with_context(|| Message::InvalidConfiguration(path).to_string())
+    }
+
+    /// Writes the state, WiFi passphrases included, to a file only readable by its owner.
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let file = config::create_private(path)
+            .with_context(|| Message::UnableToCreate(path).to_string())?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| Message::UnableToWrite(path).to_string())
+    }
+
+    /// The state with WiFi passphrases masked, to be displayed
+    pub fn masked(&self) -> Result<Value> {
+        let mut state = serde_json::to_value(self)?;
+        if let Some(access_points) = state["wifi"]["access_points"].as_object_mut() {
+            for access_point in access_points.values_mut() {
+                if let Some(passphrase) = access_point.get_mut("passphrase") {
+                    *passphrase = Value::String("********".to_string());
+                }
+            }
+        }
+        Ok(state)
+    }
+}
+
+/// Exports the actual Livebox configuration using the declarative schema.
+///
+/// DynDNS passwords are never returned by the box and are therefore missing from the export.
+pub async fn snapshot(client: &livebox::Client) -> Result<State> {
+    let access_points = client
+        .list_access_points()
+        .await?
+        .into_iter()
+        .map(|(name, access_point)| {
+            let state = AccessPointState {
+                ssid: Some(access_point.ssid),
+                passphrase: Some(access_point.security.key_pass_phrase),
+            };
+            (name, state)
+        })
+        .collect();
+    let wifi = WifiState {
+        enable: Some(client.get_wifi_status().await?.enable),
+        access_points,
+    };
+
+    let mut nat: Vec<NatRuleState> = client
+        .structured_nat_rules()
+        .await?
+        .into_iter()
+        .filter(|rule| rule.origin == "webui")
+        .map(NatRuleState::from)
+        .collect();
+    nat.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let static_leases = client
+        .list_static_leases()
+        .await?
+        .into_iter()
+        .map(|lease| StaticLeaseState {
+            mac: lease.mac_address,
+            ip: lease.ip_address,
+        })
+        .collect();
+
+    let dmz = DmzState {
+        destination: client
+            .get_dmz()
+            .await?
+            .filter(|dmz| dmz.enable)
+            .map(|dmz| dmz.destination_ip_address),
+    };
+
+    let ddns = client
+        .list_ddns_hosts()
+        .await?
+        .into_iter()
+        .map(|host| DdnsHostState {
+            service: host.service,
+            hostname: host.hostname,
+            username: host.username,
+            password: SecretString::default(),
+        })
+        .collect();
+
+    let firewall = FirewallState {
+        level: client.get_firewall_level().await?,
+    };
+
+    Ok(State {
+        wifi: Some(wifi),
+        nat: Some(nat),
+        static_leases: Some(static_leases),
+        dmz: Some(dmz),
+        ddns: Some(ddns),
+        firewall: Some(firewall),
+    })
+}
+
+/// A single mutation required to converge the Livebox to the desired state.
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Change {
+    SetWifiEnable {
+        enable: bool,
+    },
+    ConfigureAccessPoint {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ssid: Option<String>,
+        #[serde(skip_serializing)]
+        passphrase: Option<String>,
+        passphrase_changed: bool,
+    },
+    RemoveNatRule {
+        id: String,
+    },
+    SetNatRule {
+        rule: NatRuleState,
+    },
+    RemoveStaticLease {
+        mac: String,
+    },
+    AddStaticLease {
+        lease: StaticLeaseState,
+    },
+    SetDmz {
+        destination: String,
+    },
+    RemoveDmz,
+    RemoveDdnsHost {
+        hostname: String,
+    },
+    AddDdnsHost {
+        host: DdnsHostState,
+    },
+    SetFirewallLevel {
+        level: FirewallLevel,
+    },
+}
+
+/// Livebox NAT rule ids are prefixed with their origin once created
+pub fn nat_rule_matches(rule: &NatRuleView, id: &str) -> bool {
+    rule.id == id || rule.id == format!("{}_{id}", rule.origin)
+}
+
+pub fn nat_rule_differs(actual: &NatRuleView, desired: &NatRuleState) -> bool {
+    actual.description != desired.description
+        || actual.protocol != desired.protocol.clone().into()
+        || actual.external_port != desired.external_port
+        || actual.internal_port != desired.internal_port
+        || actual.destination_ip_address != desired.destination
+        || actual.enable != desired.enable
+}
+
+/// Compares the desired state with the actual Livebox configuration and lists required changes.
+pub async fn plan(client: &livebox::Client, desired: &State) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+
+    if let Some(rules) = &desired.nat {
+        let actual_rules = client.structured_nat_rules().await?;
+        for actual in actual_rules.iter().filter(|rule| rule.origin == "webui") {
+            if !rules.iter().any(|rule| nat_rule_matches(actual, &rule.id)) {
+                changes.push(Change::RemoveNatRule {
+                    id: actual.id.clone(),
+                });
+            }
+        }
+        for rule in rules {
+            let actual = actual_rules
+                .iter()
+                .find(|actual| nat_rule_matches(actual, &rule.id));
+            if actual.is_none_or(|actual| nat_rule_differs(actual, rule)) {
+                changes.push(Change::SetNatRule { rule: rule.clone() });
+            }
+        }
+    }
+
+    if let Some(leases) = &desired.static_leases {
+        let actual_leases = client.list_static_leases().await?;
+        for actual in &actual_leases {
+            let still_desired = leases.iter().any(|lease| {
+                lease.mac.eq_ignore_ascii_case(&actual.mac_address) && lease.ip == actual.ip_address
+            });
+            if !still_desired {
+                changes.push(Change::RemoveStaticLease {
+                    mac: actual.mac_address.clone(),
+                });
+            }
+        }
+        for lease in leases {
+            let exists = actual_leases.iter().any(|actual| {
+                lease.mac.eq_ignore_ascii_case(&actual.mac_address) && lease.ip == actual.ip_address
+            });
+            if !exists {
+                changes.push(Change::AddStaticLease {
+                    lease: lease.clone(),
+                });
+            }
+        }
+    }
+
+    if let Some(dmz) = &desired.dmz {
+        let actual = client
+            .get_dmz()
+            .await?
+            .filter(|dmz| dmz.enable)
+            .map(|dmz| dmz.destination_ip_address);
+        match (&dmz.destination, actual) {
+            (Some(destination), Some(actual)) if *destination == actual => {}
+            (Some(destination), _) => changes.push(Change::SetDmz {
+                destination: destination.clone(),
+            }),
+            (None, Some(_)) => changes.push(Change::RemoveDmz),
+            (None, None) => {}
+        }
+    }
+
+    if let Some(hosts) = &desired.ddns {
+        let actual_hosts = client.list_ddns_hosts().await?;
+        for actual in &actual_hosts {
+            let still_desired = hosts.iter().any(|host| {
+                host.hostname == actual.hostname
+                    && host.service == actual.service
+                    && host.username == actual.username
+            });
+            if !still_desired {
+                changes.push(Change::RemoveDdnsHost {
+                    hostname: actual.hostname.clone(),
+                });
+            }
+        }
+        for host in hosts {
+            let exists = actual_hosts.iter().any(|actual| {
+                host.hostname == actual.hostname
+                    && host.service == actual.service
+                    && host.username == actual.username
+            });
+            if !exists {
+                changes.push(Change::AddDdnsHost { host: host.clone() });
+            }
+        }
+    }
+
+    if let Some(firewall) = &desired.firewall {
+        if client.get_firewall_level().await? != firewall.level {
+            changes.push(Change::SetFirewallLevel {
+                level: firewall.level,
+            });
+        }
+    }
+
+    // last, like the setup wizard: the WiFi changes may drop the connection of the user applying
+    // the state
+    if let Some(wifi) = &desired.wifi {
+        if let Some(enable) = wifi.enable {
+            if client.get_wifi_status().await?.enable != enable {
+                changes.push(Change::SetWifiEnable { enable });
+            }
+        }
+        if !wifi.access_points.is_empty() {
+            let actual_access_points = client.list_access_points().await?;
+            for (name, access_point) in &wifi.access_points {
+                let actual = actual_access_points
+                    .get(name)
+                    .with_context(|| Message::NoAccessPoint(name).to_string())?;
+                let ssid = access_point
+                    .ssid
+                    .clone()
+                    .filter(|ssid| *ssid != actual.ssid);
+                let passphrase = access_point
+                    .passphrase
+                    .clone()
+                    .filter(|passphrase| *passphrase != actual.security.key_pass_phrase);
+                if ssid.is_some() || passphrase.is_some() {
+                    changes.push(Change::ConfigureAccessPoint {
+                        name: name.clone(),
+                        ssid,
+                        passphrase_changed: passphrase.is_some(),
+                        passphrase,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+impl Change {
+    async fn apply(&self, client: &livebox::Client) -> Result<Value> {
+        match self {
+            Change::SetWifiEnable { enable } => client.set_wifi_enable(*enable).await,
+            Change::ConfigureAccessPoint {
+                name,
+                ssid,
+                passphrase,
+                ..
+            } => {
+                client
+                    .configure_access_point(name.clone(), ssid.clone(), passphrase.clone())
+                    .await
+            }
+            Change::RemoveNatRule { id } => client.remove_nat_rule(id.clone()).await,
+            Change::SetNatRule { rule } => {
+                let params = SetPortFowardingParams::new(
+                    rule.id.clone(),
+                    rule.description.clone(),
+                    rule.protocol.clone().into(),
+                    rule.external_port.clone(),
+                    rule.internal_port.clone(),
+                    rule.destination.clone(),
+                )
+                .with_enable(rule.enable);
+                let result = client.add_nat_rule(params).await?;
+                client.commit_firewall().await?;
+                Ok(result)
+            }
+            Change::RemoveStaticLease { mac } => client.remove_static_lease(mac.clone()).await,
+            Change::AddStaticLease { lease } => {
+                client
+                    .add_static_lease(lease.mac.clone(), lease.ip.clone())
+                    .await
+            }
+            Change::SetDmz { destination } => client.set_dmz(destination.clone()).await,
+            Change::RemoveDmz => client.remove_dmz().await,
+            Change::RemoveDdnsHost { hostname } => client.remove_ddns_host(hostname.clone()).await,
+            Change::AddDdnsHost { host } => {
+                client
+                    .add_ddns_host(
+                        host.service.clone(),
+                        host.hostname.clone(),
+                        host.username.clone(),
+                        host.password.clone(),
+                    )
+                    .await
+            }
+            Change::SetFirewallLevel { level } => client.set_firewall_level(*level).await,
+        }
+    }
+
+    /// The change restoring the actual configuration once this one is applied, `None` when the
+    /// Livebox doesn't return what it would need (ex: DynDNS passwords).
+    async fn inverse(&self, client: &livebox::Client) -> Result<Option<Change>> {
+        Ok(match self {
+            Change::SetWifiEnable { enable } => Some(Change::SetWifiEnable { enable: !enable }),
+            Change::ConfigureAccessPoint { name, .. } => {
+                let mut access_points = client.list_access_points().await?;
+                let actual = access_points
+                    .remove(name)
+                    .with_context(|| Message::NoAccessPoint(name).to_string())?;
+                Some(Change::ConfigureAccessPoint {
+                    name: name.clone(),
+                    ssid: Some(actual.ssid),
+                    passphrase: Some(actual.security.key_pass_phrase),
+                    passphrase_changed: true,
+                })
+            }
+            Change::RemoveNatRule { id } => client
+                .structured_nat_rules()
+                .await?
+                .into_iter()
+                .find(|rule| rule.id == *id)
+                .map(|rule| Change::SetNatRule { rule: rule.into() }),
+            Change::SetNatRule { rule } => Some(
+                match client
+                    .structured_nat_rules()
+                    .await?
+                    .into_iter()
+                    .find(|actual| nat_rule_matches(actual, &rule.id))
+                {
+                    Some(actual) => Change::SetNatRule {
+                        rule: actual.into(),
+                    },
+                    None => Change::RemoveNatRule {
+                        id: format!("webui_{}", rule.id),
+                    },
+                },
+            ),
+            Change::RemoveStaticLease { mac } => client
+                .list_static_leases()
+                .await?
+                .into_iter()
+                .find(|lease| lease.mac_address.eq_ignore_ascii_case(mac))
+                .map(|lease| Change::AddStaticLease {
+                    lease: StaticLeaseState {
+                        mac: lease.mac_address,
+                        ip: lease.ip_address,
+                    },
+                }),
+            Change::AddStaticLease { lease } => Some(Change::RemoveStaticLease {
+                mac: lease.mac.clone(),
+            }),
+            Change::SetDmz { .. } | Change::RemoveDmz => {
+                Some(match client.get_dmz().await?.filter(|dmz| dmz.enable) {
+                    Some(dmz) => Change::SetDmz {
+                        destination: dmz.destination_ip_address,
+                    },
+                    None => Change::RemoveDmz,
+                })
+            }
+            Change::RemoveDdnsHost { .. } => None,
+            Change::AddDdnsHost { host } => Some(Change::RemoveDdnsHost {
+                hostname: host.hostname.clone(),
+            }),
+            Change::SetFirewallLevel { .. } => Some(Change::SetFirewallLevel {
+                level: client.get_firewall_level().await?,
+            }),
+        })
+    }
+}
+
+/// Reverts the applied changes, latest first, and flags them in the report. Failures are logged.
+async fn rollback(
+    client: &livebox::Client,
+    reports: &mut [ChangeOutcome],
+    applied: Vec<(usize, Option<Change>)>,
+) {
+    for (index, inverse) in applied.into_iter().rev() {
+        let report = &mut reports[index];
+        let change = serde_json::to_string(&report.change).unwrap_or_default();
+        let Some(inverse) = inverse else {
+            warn!("{}", Message::Irreversible(&change));
+            continue;
+        };
+        match inverse.apply(client).await {
+            Ok(_) => {
+                warn!("{}", Message::RolledBack(&change));
+                report.rolled_back = true;
+            }
+            Err(err) => warn!("{}: {err:#}", Message::UnableToRollBack(&change)),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ApplyReport {
+    changed: bool,
+    dry_run: bool,
+    /// Number of changes which failed
+    failed: usize,
+    /// Changes applied or attempted, up to the failed one unless continuing on error
+    changes: Vec<ChangeOutcome>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ChangeOutcome {
+    #[serde(flatten)]
+    change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Reverted after a later change failed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    rolled_back: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DiffReport {
+    drift: bool,
+    changes: Vec<Change>,
+}
+
+/// Reports the changes required to converge the Livebox to the desired state, without applying them.
+pub async fn diff(client: &livebox::Client, desired: &State) -> Result<Value> {
+    let changes = plan(client, desired).await?;
+    Ok(serde_json::to_value(DiffReport {
+        drift: !changes.is_empty(),
+        changes,
+    })?)
+}
+
+/// Converges the Livebox to the desired state, or only reports the plan when `dry_run` is set.
+pub async fn apply(
+    client: &livebox::Client,
+    desired: &State,
+    dry_run: bool,
+    on_error: OnError,
+) -> Result<Value> {
+    let changes = plan(client, desired).await?;
+    apply_changes(client, changes, dry_run, on_error).await
+}
+
+/// Applies the changes in order, or only reports them when `dry_run` is set.
+///
+/// A failed change stops the others, unless `on_error` continues with the next ones or rolls back
+/// the changes already applied, in reverse order. Failed changes are flagged with their error in
+/// the report, see [`check_failures`].
+pub async fn apply_changes(
+    client: &livebox::Client,
+    changes: Vec<Change>,
+    dry_run: bool,
+    on_error: OnError,
+) -> Result<Value> {
+    let mut reports = Vec::new();
+    let mut failed = 0;
+    let mut applied = Vec::new();
+    for change in changes {
+        let mut error = None;
+        let mut stop = false;
+        if !dry_run {
+            let result = async {
+                let inverse = match on_error {
+                    OnError::Rollback => change.inverse(client).await?,
+                    OnError::Continue | OnError::Stop => None,
+                };
+                change.apply(client).await?;
+                anyhow::Ok(inverse)
+            }
+            .await
+            .with_context(|| {
+                let change = serde_json::to_string(&change).unwrap_or_default();
+                Message::UnableToApply(&change).to_string()
+            });
+            match result {
+                Ok(inverse) => applied.push((reports.len(), inverse)),
+                Err(err) => {
+                    warn!("{err:#}");
+                    error = Some(format!("{err:#}"));
+                    failed += 1;
+                    stop = !matches!(on_error, OnError::Continue);
+                }
+            }
+        }
+        reports.push(ChangeOutcome {
+            change,
+            error,
+            rolled_back: false,
+        });
+        if stop {
+            if matches!(on_error, OnError::Rollback) {
+                rollback(client, &mut reports, applied).await;
+            }
+            break;
+        }
+    }
+    Ok(serde_json::to_value(ApplyReport {
+        changed: reports
+            .iter()
+            .any(|report| report.error.is_none() && !report.rolled_back),
+        dry_run,
+        failed,
+        changes: reports,
+    })?)
+}
+
+/// Fails when some changes of the apply report failed, once the report is printed
+pub fn check_failures(report: &Value) -> Result<()> {
+    let failed = report["failed"].as_u64().unwrap_or_default() as usize;
+    let count = report["changes"].as_array().map_or(0, Vec::len);
+    match failed {
+        0 => Ok(()),
+        _ => Err(anyhow!(Message::ChangesFailed(failed, count).to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use livebox_cli::livebox::test_support::MockLiveboxBuilder;
+
+    use super::*;
+
+    fn state() -> State {
+        let access_point = AccessPointState {
+            ssid: Some("Livebox-1234".to_string()),
+            passphrase: Some("secret".to_string()),
+        };
+        State {
+            wifi: Some(WifiState {
+                enable: Some(true),
+                access_points: BTreeMap::from([("wl0".to_string(), access_point)]),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn snapshot_file_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            env::temp_dir().join(format!("livebox-cli-snapshot-{}.json", std::process::id()));
+        // an existing file keeps its mode unless changed
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        state().to_file(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn masks_passphrases() {
+        let masked = state().masked().unwrap();
+        let access_point = &masked["wifi"]["access_points"]["wl0"];
+        assert_eq!(access_point["ssid"], "Livebox-1234");
+        assert_eq!(access_point["passphrase"], "********");
+    }
+
+    fn changes() -> Vec<Change> {
+        let rule = serde_json::json!({
+            "id": "ssh",
+            "protocol": "tcp",
+            "external_port": 2222,
+            "internal_port": 22,
+            "destination": "192.168.1.10",
+        });
+        vec![
+            Change::RemoveNatRule {
+                id: "missing".to_string(),
+            },
+            Change::SetNatRule {
+                rule: serde_json::from_value(rule).unwrap(),
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn plans_wifi_changes_last() {
+        let wifi = serde_json::json!({"status": {"Enable": false, "Status": false}});
+        let livebox = MockLiveboxBuilder::default()
+            .with_response("NMC.Wifi", "get", wifi)
+            .start()
+            .unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+        let desired = State {
+            wifi: Some(WifiState {
+                enable: Some(true),
+                access_points: BTreeMap::new(),
+            }),
+            nat: Some(vec![serde_json::from_value(serde_json::json!({
+                "id": "ssh",
+                "protocol": "tcp",
+                "external_port": 2222,
+                "internal_port": 22,
+                "destination": "192.168.1.10",
+            }))
+            .unwrap()]),
+            ..Default::default()
+        };
+
+        let changes = plan(&client, &desired).await.unwrap();
+        assert!(matches!(
+            changes[..],
+            [
+                Change::SetNatRule { .. },
+                Change::SetWifiEnable { enable: true }
+            ]
+        ));
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_failed_changes_when_continuing() {
+        let livebox = MockLiveboxBuilder::default().start().unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+
+        let report = apply_changes(&client, changes(), false, OnError::Continue)
+            .await
+            .unwrap();
+        assert_eq!(report["failed"], 1);
+        assert!(report["changes"][0]["error"].is_string());
+        assert!(report["changes"][1].get("error").is_none());
+        assert!(livebox
+            .nat_rules()
+            .iter()
+            .any(|rule| rule.id.ends_with("_ssh")));
+        assert!(livebox
+            .requests()
+            .iter()
+            .any(|request| request["method"] == "commit"));
+        let err = check_failures(&report).unwrap_err();
+        assert_eq!(err.to_string(), Message::ChangesFailed(1, 2).to_string());
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_failed_change() {
+        let livebox = MockLiveboxBuilder::default().start().unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+
+        let report = apply_changes(&client, changes(), false, OnError::Stop)
+            .await
+            .unwrap();
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["changes"].as_array().unwrap().len(), 1);
+        assert!(report["changes"][0]["error"].is_string());
+        assert_eq!(report["changed"], false);
+        assert!(check_failures(&report).is_err());
+        assert!(livebox.nat_rules().is_empty());
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rolls_back_the_applied_changes() {
+        let livebox = MockLiveboxBuilder::default().start().unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+        let changes = changes().into_iter().rev().collect();
+
+        let report = apply_changes(&client, changes, false, OnError::Rollback)
+            .await
+            .unwrap();
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["changed"], false);
+        assert_eq!(report["changes"][0]["rolled_back"], true);
+        assert!(report["changes"][1]["error"].is_string());
+        let requests = livebox.requests();
+        let removed = requests
+            .iter()
+            .filter(|request| request["method"] == "deletePortForwarding")
+            .map(|request| request["parameters"]["id"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(removed, ["webui_ssh"]);
+        assert!(livebox.nat_rules().is_empty());
+        client.logout().await.unwrap();
+    }
+}
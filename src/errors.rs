@@ -0,0 +1,109 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use crate::{
+    i18n::Message,
+    livebox::{
+        InvalidCredentials, PermissionDenied, ResponseBody, TooManySessions, UnsupportedApi,
+    },
+    prompt::NotInteractive,
+};
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// A failure explained with what to try next
+struct Explanation {
+    summary: String,
+    hint: Message<'static>,
+}
+
+/// Common failures, the other errors are left to their causes
+fn explain(err: &anyhow::Error, base_url: &str) -> Option<Explanation> {
+    let (summary, hint) = if err.is::<InvalidCredentials>() {
+        (
+            Message::InvalidCredentials.to_string(),
+            Message::HintInvalidCredentials,
+        )
+    } else if err.is::<TooManySessions>() {
+        (
+            Message::TooManySessions.to_string(),
+            Message::HintTooManySessions,
+        )
+    } else if err.is::<UnsupportedApi>() {
+        (
+            Message::UnsupportedFirmware.to_string(),
+            Message::HintUnsupportedFirmware,
+        )
+    } else if err.is::<NotInteractive>() {
+        (Message::Aborted.to_string(), Message::HintNotInteractive)
+    } else if err.is::<PermissionDenied>() {
+        // the command and missing group are given as context
+        (causes(err).next()?, Message::HintPermissionDenied)
+    } else {
+        let http_error = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<reqwest::Error>())?;
+        if http_error.is_timeout() {
+            (
+                Message::LiveboxTimeout.to_string(),
+                Message::HintLiveboxTimeout,
+            )
+        } else if http_error.is_connect() {
+            (
+                Message::LiveboxUnreachable(base_url).to_string(),
+                Message::HintLiveboxUnreachable,
+            )
+        } else {
+            return None;
+        }
+    };
+    Some(Explanation { summary, hint })
+}
+
+/// Messages of the error and its causes, without the raw response bodies logged at debug level
+fn causes(err: &anyhow::Error) -> impl Iterator<Item = String> + '_ {
+    let body = err.downcast_ref::<ResponseBody>().map(ToString::to_string);
+    err.chain()
+        .map(ToString::to_string)
+        .filter(move |cause| Some(cause) != body.as_ref())
+}
+
+/// One-line description of an error, the explanation of a common failure or the causes
+pub fn summary(err: &anyhow::Error, base_url: &str) -> String {
+    match explain(err, base_url) {
+        Some(explanation) => explanation.summary,
+        None => causes(err).collect::<Vec<_>>().join(": "),
+    }
+}
+
+/// Renders an error for the user: common failures get a short explanation and a hint, other
+/// errors their causes. Colored on a terminal, unless disabled or `NO_COLOR` is set.
+pub fn render(err: &anyhow::Error, base_url: &str, no_color: bool) -> String {
+    let color = !no_color && env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal();
+    let paint = |style: &str, text: String| match color {
+        true => format!("{style}{text}{RESET}"),
+        false => text,
+    };
+    let error = paint(&format!("{BOLD}{RED}"), Message::ErrorLabel.to_string());
+    match explain(err, base_url) {
+        Some(explanation) => format!(
+            "{error} {}\n{} {}",
+            explanation.summary,
+            paint(YELLOW, Message::HintLabel.to_string()),
+            explanation.hint
+        ),
+        None => {
+            let mut causes = causes(err);
+            let mut rendered = format!("{error} {}", causes.next().unwrap_or_default());
+            for cause in causes {
+                rendered.push_str(&format!("\n  {} {cause}", Message::CausedByLabel));
+            }
+            rendered
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{fmt, time::Duration};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::net::UdpSocket;
+
+use crate::{
+    errors,
+    i18n::Message,
+    livebox::{Client, ClientBuilder},
+};
+
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+const DNS_QUERY_ID: u16 = 0x6c62;
+
+/// Checks run by `healthcheck`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Probe {
+    /// the credentials open a session
+    Login,
+    /// WAN link up
+    Wan,
+    /// the Livebox DNS resolver answers
+    Dns,
+    /// VoIP lines registered
+    Voip,
+}
+
+/// Monitoring verdict, ordered by the Nagios plugins convention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Status {
+    Ok,
+    Unknown,
+    Warning,
+    Critical,
+}
+
+impl Status {
+    /// Nagios plugins exit code
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+            Status::Unknown => 3,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Unknown => "UNKNOWN",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ProbeReport {
+    probe: Probe,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HealthReport {
+    status: Status,
+    probes: Vec<ProbeReport>,
+}
+
+impl HealthReport {
+    pub fn status(&self) -> Status {
+        self.status
+    }
+}
+
+/// One-line summary (ex: `CRITICAL - login OK, wan CRITICAL (WAN down)`)
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -", self.status.as_str())?;
+        for (index, probe) in self.probes.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            let name = probe.probe.to_possible_value().expect("no skipped probe");
+            write!(
+                f,
+                "{separator} {} {}",
+                name.get_name(),
+                probe.status.as_str()
+            )?;
+            if let Some(message) = &probe.message {
+                write!(f, " ({message})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the probes, a failed login makes the probes requiring a session unknown.
+pub async fn check(
+    client_builder: ClientBuilder,
+    base_url: &str,
+    probes: &[Probe],
+    dns_name: &str,
+) -> HealthReport {
+    let client = client_builder.build().await;
+    let mut reports = Vec::new();
+    for probe in probes {
+        let result = match (probe, &client) {
+            (Probe::Login, Ok(_)) => Ok(None),
+            (Probe::Login, Err(err)) => {
+                Ok(Some((Status::Critical, errors::summary(err, base_url))))
+            }
+            (Probe::Dns, _) => probe_dns(base_url, dns_name).await,
+            (_, Err(_)) => Err(anyhow!(Message::LoginFailed.to_string())),
+            (Probe::Wan, Ok(client)) => probe_wan(client).await,
+            (Probe::Voip, Ok(client)) => probe_voip(client).await,
+        };
+        let (status, message) = match result {
+            Ok(None) => (Status::Ok, None),
+            Ok(Some((status, message))) => (status, Some(message)),
+            Err(err) => (Status::Unknown, Some(format!("{err:#}"))),
+        };
+        reports.push(ProbeReport {
+            probe: *probe,
+            status,
+            message,
+        });
+    }
+    if let Ok(client) = client {
+        // the verdict doesn't depend on the session release
+        let _ = client.logout().await;
+    }
+    HealthReport {
+        status: reports
+            .iter()
+            .map(|report| report.status)
+            .max()
+            .unwrap_or(Status::Ok),
+        probes: reports,
+    }
+}
+
+/// A probe outcome: `None` when healthy, the failure status and message otherwise
+type Outcome = Result<Option<(Status, String)>>;
+
+async fn probe_wan(client: &Client) -> Outcome {
+    let wan = client.get_wan_status().await?;
+    Ok((!wan.is_up()).then(|| (Status::Critical, Message::WanDown.to_string())))
+}
+
+async fn probe_voip(client: &Client) -> Outcome {
+    let registered = client
+        .list_voip_trunks()
+        .await?
+        .iter()
+        .all(|trunk| trunk.is_registered());
+    Ok((!registered).then(|| (Status::Warning, Message::VoipNotRegistered.to_string())))
+}
+
+/// Sends an `A` query to the Livebox resolver and expects at least one answer.
+async fn probe_dns(base_url: &str, name: &str) -> Outcome {
+    let url = Url::parse(base_url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!(Message::NoHost(url.as_str()).to_string()))?;
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect((host, 53)).await?;
+
+    let mut query = Vec::new();
+    query.extend(DNS_QUERY_ID.to_be_bytes());
+    // recursion desired, one question
+    query.extend([0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        query.push(label.len() as u8);
+        query.extend(label.as_bytes());
+    }
+    // root label, type A, class IN
+    query.extend([0, 0, 1, 0, 1]);
+    socket.send(&query).await?;
+
+    let mut response = [0; 512];
+    let length = match tokio::time::timeout(DNS_TIMEOUT, socket.recv(&mut response)).await {
+        Ok(length) => length?,
+        Err(_) => return Ok(Some((Status::Critical, Message::DnsTimeout.to_string()))),
+    };
+    let answers = match &response[..length] {
+        [id_high, id_low, _, flags, _, _, answers_high, answers_low, ..]
+            if u16::from_be_bytes([*id_high, *id_low]) == DNS_QUERY_ID && flags & 0x0f == 0 =>
+        {
+            u16::from_be_bytes([*answers_high, *answers_low])
+        }
+        _ => 0,
+    };
+    Ok((answers == 0).then(|| (Status::Critical, Message::DnsNoAnswer(name).to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use livebox_cli::livebox::test_support::{MockLivebox, MockLiveboxBuilder};
+    use serde_json::json;
+
+    use super::*;
+
+    fn livebox(wan_state: &str) -> MockLivebox {
+        let wan = json!({"status": true, "data": {
+            "WanState": wan_state,
+            "LinkState": wan_state,
+            "ConnectionState": "Bound",
+            "IPAddress": "55.27.2.115",
+        }});
+        MockLiveboxBuilder::default()
+            .with_response("NMC", "getWANStatus", wan)
+            .start()
+            .unwrap()
+    }
+
+    async fn check(
+        livebox: &MockLivebox,
+        builder: ClientBuilder,
+        probes: &[Probe],
+    ) -> HealthReport {
+        super::check(builder, &livebox.base_url(), probes, "orange.fr").await
+    }
+
+    #[tokio::test]
+    async fn reports_a_healthy_livebox() {
+        let livebox = livebox("up");
+        let report = check(
+            &livebox,
+            livebox.client_builder(),
+            &[Probe::Login, Probe::Wan],
+        )
+        .await;
+        assert_eq!(report.status(), Status::Ok);
+        assert_eq!(report.status().exit_code(), 0);
+        assert_eq!(report.to_string(), "OK - login OK, wan OK");
+        assert_eq!(livebox.open_contexts(), 0);
+    }
+
+    #[tokio::test]
+    async fn reports_a_wan_down_as_critical() {
+        let livebox = livebox("down");
+        let report = check(
+            &livebox,
+            livebox.client_builder(),
+            &[Probe::Login, Probe::Wan],
+        )
+        .await;
+        assert_eq!(report.status(), Status::Critical);
+        assert_eq!(report.status().exit_code(), 2);
+        assert_eq!(
+            report.to_string(),
+            format!("CRITICAL - login OK, wan CRITICAL ({})", Message::WanDown)
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_probes_needing_a_session_as_unknown_on_bad_credentials() {
+        let livebox = livebox("up");
+        let builder = livebox
+            .client_builder()
+            .with_credentials("admin".to_string(), "wrong".to_string().into());
+
+        let report = check(&livebox, builder.clone(), &[Probe::Wan]).await;
+        assert_eq!(report.status(), Status::Unknown);
+        assert_eq!(report.status().exit_code(), 3);
+        assert_eq!(
+            report.to_string(),
+            format!("UNKNOWN - wan UNKNOWN ({})", Message::LoginFailed)
+        );
+
+        let report = check(&livebox, builder, &[Probe::Login, Probe::Wan]).await;
+        assert_eq!(report.status(), Status::Critical);
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "CRITICAL - login CRITICAL ({}), wan UNKNOWN ({})",
+                Message::InvalidCredentials,
+                Message::LoginFailed
+            )
+        );
+    }
+}
@@ -0,0 +1,688 @@
+use std::{env, fmt, net::SocketAddr, path::Path, sync::OnceLock};
+
+use clap::ValueEnum;
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Language of user-facing messages, JSON output is never translated
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+/// Selects the messages language, falling back to the `LANG` environment variable.
+pub fn init(lang: Option<Lang>) {
+    let lang = lang.unwrap_or_else(|| match env::var("LANG") {
+        Ok(locale) if locale.starts_with("fr") => Lang::Fr,
+        _ => Lang::En,
+    });
+    LANG.set(lang).ok();
+}
+
+fn lang() -> Lang {
+    *LANG.get().unwrap_or(&Lang::En)
+}
+
+/// User-facing messages
+pub enum Message<'a> {
+    MissingCredentials,
+    MissingPassword,
+    AuthenticationFailed(&'a dyn fmt::Display),
+    ExecutionFailed(&'a dyn fmt::Display),
+    ResponseBody(&'a str),
+    NoRuleWithId(&'a str),
+    NoRadio(&'a str),
+    NoMatchingAccessPoint,
+    QrCodeTooLong(usize),
+    UnsupportedImageFormat(&'a Path),
+    NoAlg(&'a str),
+    UnsupportedWanAccess(&'a str),
+    LoginFailed,
+    WanDown,
+    VoipNotRegistered,
+    DnsTimeout,
+    DnsNoAnswer(&'a str),
+    NoAccessPoint(&'a str),
+    UnableToOpen(&'a Path),
+    UnableToCreate(&'a Path),
+    UnableToWrite(&'a Path),
+    UnableToLock(&'a Path),
+    InvalidConfiguration(&'a Path),
+    InvalidBaseline(&'a Path),
+    UnableToApply(&'a str),
+    ChangesFailed(usize, usize),
+    CallsFailed(usize, usize),
+    InvalidCall(&'a str),
+    NoRequestField(&'a str, &'a str),
+    UnableToListen(SocketAddr),
+    InvalidCronExpression(&'a str),
+    NoScheduledTask(u32),
+    RolledBack(&'a str),
+    UnableToRollBack(&'a str),
+    Irreversible(&'a str),
+    NotReadyAfter(&'a str, u64),
+    NoJsonPathMatch,
+    InvalidParameter(&'a str),
+    InvalidDuration(&'a str),
+    InvalidPipelineStep(usize),
+    PipelineStepFailed(usize),
+    UntypedOutput,
+    TextOutput,
+    Interrupted,
+    ErrorLabel,
+    HintLabel,
+    CausedByLabel,
+    InvalidCredentials,
+    HintInvalidCredentials,
+    TooManySessions,
+    HintTooManySessions,
+    UnsupportedFirmware,
+    HintUnsupportedFirmware,
+    PermissionDenied,
+    HintPermissionDenied,
+    HintNotInteractive,
+    LiveboxTimeout,
+    HintLiveboxTimeout,
+    LiveboxUnreachable(&'a str),
+    HintLiveboxUnreachable,
+    NoCacheDirectory,
+    NoStateDirectory,
+    UnknownModel(&'a str, &'a str),
+    NewPassword,
+    ConfirmPassword,
+    PasswordsMismatch,
+    SetupAdminPassword,
+    SetupSsid(&'a str),
+    SetupPassphrase(&'a str),
+    SetupDhcpMin,
+    SetupDhcpMax,
+    SetupDisableWps,
+    PasswordNotVerified,
+    InsufficientPermissions(&'a str, &'a str),
+    ConfirmPrune(usize),
+    ConfirmRemoveRule(&'a str),
+    ConfirmSetDmz(&'a str),
+    ConfirmSetWanMode(&'a str),
+    ConfirmReboot,
+    ConfirmFactoryReset,
+    Aborted,
+    CheckUnsupported(&'a str),
+    NoConfigDirectory,
+    UnknownAlias(&'a str),
+    NoDeviceAddress(&'a str),
+    NoDeviceAtAddress(&'a str),
+    InvalidMac(&'a str),
+    InvalidHexValue(&'a str),
+    UnableToRead(&'a Path),
+    InvalidState(&'a Path),
+    InvalidAliases(&'a Path),
+    InvalidAuditEntry(&'a str),
+    MissingPluginName,
+    NoPlugin(&'a str, &'a str),
+    UnableToRun(&'a str),
+    CommandFailed(&'a str, &'a dyn fmt::Display),
+    BrokerRefused(&'a str, u8),
+    NotABroker(&'a str),
+    IncompleteRequestHead,
+    InvalidRequestLine(&'a str),
+    ChunkedBodyUnsupported,
+    RequestBodyTooLarge(usize),
+    NoHost(&'a str),
+    NoAddress(&'a str),
+    ComposeVariable(&'a str, &'a str),
+    #[cfg(feature = "self-update")]
+    MissingReleaseAsset(&'a str),
+    #[cfg(feature = "self-update")]
+    MissingChecksum(&'a str),
+    #[cfg(feature = "self-update")]
+    ChecksumMismatch(&'a str),
+    #[cfg(feature = "self-update")]
+    MissingReleaseKey,
+    #[cfg(feature = "self-update")]
+    InvalidSignature(&'a str),
+}
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match lang() {
+            Lang::En => match self {
+                Message::MissingCredentials => write!(f, "missing credentials"),
+                Message::MissingPassword => write!(
+                    f,
+                    "the following required arguments were not provided:\n  --password <PASSWORD>"
+                ),
+                Message::AuthenticationFailed(status) => {
+                    write!(f, "Authentication failed: {status}")
+                }
+                Message::ExecutionFailed(status) => write!(f, "Execution failed: {status}"),
+                Message::ResponseBody(body) => write!(f, "Response body: {body}"),
+                Message::NoRuleWithId(id) => write!(f, "No rule with id {id}"),
+                Message::NoRadio(band) => write!(f, "No {band} radio"),
+                Message::NoMatchingAccessPoint => write!(f, "No matching WiFi access point"),
+                Message::QrCodeTooLong(length) => {
+                    write!(f, "{length} bytes don't fit in a QR code")
+                }
+                Message::UnsupportedImageFormat(path) => {
+                    write!(
+                        f,
+                        "Unsupported image format {path:?}, expected .png or .svg"
+                    )
+                }
+                Message::NoAlg(alg) => write!(f, "No {alg} application level gateway"),
+                Message::UnsupportedWanAccess(access) => {
+                    write!(f, "The Livebox supports no {access} WAN mode")
+                }
+                Message::LoginFailed => write!(f, "Login failed"),
+                Message::WanDown => write!(f, "WAN down"),
+                Message::VoipNotRegistered => write!(f, "VoIP lines not registered"),
+                Message::DnsTimeout => write!(f, "No DNS response"),
+                Message::DnsNoAnswer(name) => write!(f, "{name} doesn't resolve"),
+                Message::NoAccessPoint(name) => write!(f, "No WiFi access point named {name}"),
+                Message::UnableToOpen(path) => write!(f, "Unable to open {path:?}"),
+                Message::UnableToCreate(path) => write!(f, "Unable to create {path:?}"),
+                Message::UnableToWrite(path) => write!(f, "Unable to write {path:?}"),
+                Message::UnableToLock(path) => write!(
+                    f,
+                    "Unable to lock {path:?}, held by another livebox-cli process"
+                ),
+                Message::InvalidConfiguration(path) => {
+                    write!(f, "Invalid configuration in {path:?}")
+                }
+                Message::InvalidBaseline(path) => write!(f, "Invalid JSON baseline {path:?}"),
+                Message::UnableToApply(change) => write!(f, "Unable to apply {change}"),
+                Message::ChangesFailed(failures, count) => {
+                    write!(f, "{failures} of {count} changes failed")
+                }
+                Message::CallsFailed(failures, count) => {
+                    write!(f, "{failures} of {count} calls failed")
+                }
+                Message::InvalidCall(call) => {
+                    write!(f, "Invalid call {call}, expected service:method")
+                }
+                Message::NoRequestField(field, request) => {
+                    write!(f, "No {field} in request {request}")
+                }
+                Message::UnableToListen(address) => write!(f, "Unable to listen on {address}"),
+                Message::InvalidCronExpression(expression) => write!(
+                    f,
+                    "Invalid cron expression {expression:?}, expected minute hour day month weekday"
+                ),
+                Message::NoScheduledTask(id) => write!(f, "No scheduled task {id}"),
+                Message::RolledBack(change) => write!(f, "Rolled back {change}"),
+                Message::UnableToRollBack(change) => write!(f, "Unable to roll back {change}"),
+                Message::Irreversible(change) => {
+                    write!(
+                        f,
+                        "{change} can't be rolled back, the Livebox hides the previous value"
+                    )
+                }
+                Message::NotReadyAfter(stage, seconds) => {
+                    write!(f, "{stage} not ready after {seconds} seconds")
+                }
+                Message::NoJsonPathMatch => write!(f, "No match for given JsonPath"),
+                Message::InvalidParameter(parameter) => {
+                    write!(f, "Invalid parameter {parameter}, expected name=value")
+                }
+                Message::InvalidDuration(duration) => {
+                    write!(
+                        f,
+                        "Invalid duration {duration}, expected ex: 90s, 30m, 2h or 1d"
+                    )
+                }
+                Message::InvalidPipelineStep(step) => write!(f, "Invalid pipeline step {step}"),
+                Message::PipelineStepFailed(step) => write!(f, "Pipeline step {step} failed"),
+                Message::UntypedOutput => write!(
+                    f,
+                    "This command outputs the raw Livebox response, no schema available"
+                ),
+                Message::TextOutput => {
+                    write!(
+                        f,
+                        "This command outputs text, not JSON, no schema available"
+                    )
+                }
+                Message::Interrupted => write!(f, "Interrupted"),
+                Message::ErrorLabel => write!(f, "error:"),
+                Message::HintLabel => write!(f, "hint:"),
+                Message::CausedByLabel => write!(f, "caused by:"),
+                Message::InvalidCredentials => {
+                    write!(f, "The Livebox rejected the username or password")
+                }
+                Message::HintInvalidCredentials => write!(
+                    f,
+                    "check --username and --password (or LIVEBOX_PASSWORD), the Livebox delays \
+                     logins after several failures"
+                ),
+                Message::TooManySessions => {
+                    write!(f, "The Livebox reached its maximum number of sessions")
+                }
+                Message::HintTooManySessions => write!(
+                    f,
+                    "log out of the Livebox web interface or wait a few minutes for idle sessions \
+                     to expire"
+                ),
+                Message::UnsupportedFirmware => {
+                    write!(f, "This Livebox firmware doesn't expose the /ws API")
+                }
+                Message::HintUnsupportedFirmware => write!(
+                    f,
+                    "retry with --api-flavor legacy, or a compatibility profile with --profile"
+                ),
+                Message::PermissionDenied => write!(f, "Permission denied"),
+                Message::HintPermissionDenied => write!(
+                    f,
+                    "`livebox-cli whoami` lists the commands allowed to this user"
+                ),
+                Message::HintNotInteractive => write!(
+                    f,
+                    "stdin isn't a terminal to confirm on, pass --yes to confirm beforehand"
+                ),
+                Message::LiveboxTimeout => write!(f, "The Livebox didn't answer in time"),
+                Message::HintLiveboxTimeout => write!(
+                    f,
+                    "it may be rebooting or overloaded, retry later or space requests out with \
+                     --min-request-interval"
+                ),
+                Message::LiveboxUnreachable(url) => {
+                    write!(f, "The Livebox is unreachable at {url}")
+                }
+                Message::HintLiveboxUnreachable => write!(
+                    f,
+                    "check --base-url and that this host is connected to the Livebox network"
+                ),
+                Message::NoCacheDirectory => write!(f, "Unable to locate the cache directory"),
+                Message::NoStateDirectory => write!(f, "Unable to locate the state directory"),
+                Message::UnknownModel(name, known) => {
+                    write!(f, "Unknown model {name}, expected one of: {known}")
+                }
+                Message::NewPassword => write!(f, "New password: "),
+                Message::ConfirmPassword => write!(f, "Confirm the new password: "),
+                Message::PasswordsMismatch => write!(f, "Passwords don't match"),
+                Message::SetupAdminPassword => write!(f, "Change the administration password?"),
+                Message::SetupSsid(name) => write!(f, "SSID of {name}"),
+                Message::SetupPassphrase(name) => {
+                    write!(f, "Passphrase of {name} (empty keeps the current one): ")
+                }
+                Message::SetupDhcpMin => write!(f, "First address leased by the DHCP server"),
+                Message::SetupDhcpMax => write!(f, "Last address leased by the DHCP server"),
+                Message::SetupDisableWps => write!(f, "Disable WPS?"),
+                Message::PasswordNotVerified => write!(
+                    f,
+                    "Password changed but logging in with the new password failed"
+                ),
+                Message::InsufficientPermissions(command, group) => write!(
+                    f,
+                    "Insufficient permissions: `{command}` requires the {group} group"
+                ),
+                Message::ConfirmPrune(count) => write!(f, "Remove these {count} NAT rules?"),
+                Message::ConfirmRemoveRule(id) => write!(f, "Remove the NAT rule {id}?"),
+                Message::ConfirmSetDmz(destination) => {
+                    write!(f, "Expose every port of {destination} to the Internet?")
+                }
+                Message::ConfirmSetWanMode(mode) => write!(
+                    f,
+                    "Switch the WAN to {mode}? The Internet connection will be interrupted"
+                ),
+                Message::ConfirmReboot => write!(f, "Reboot the Livebox?"),
+                Message::ConfirmFactoryReset => write!(
+                    f,
+                    "Restore the factory settings? The whole configuration will be lost"
+                ),
+                Message::Aborted => write!(f, "Aborted"),
+                Message::CheckUnsupported(command) => {
+                    write!(f, "`{command}` doesn't support --check")
+                }
+                Message::NoConfigDirectory => {
+                    write!(f, "Unable to locate the configuration directory")
+                }
+                Message::UnknownAlias(name) => write!(f, "Unknown alias {name}"),
+                Message::InvalidMac(mac) => write!(f, "Invalid MAC address {mac}"),
+                Message::InvalidHexValue(value) => write!(f, "Invalid hexBinary value {value}"),
+                Message::NoDeviceAddress(name) => {
+                    write!(f, "The Livebox knows no IP address of {name}")
+                }
+                Message::NoDeviceAtAddress(address) => {
+                    write!(f, "The Livebox knows no device at {address} to track")
+                }
+                Message::UnableToRead(path) => write!(f, "Unable to read {path:?}"),
+                Message::InvalidState(path) => write!(f, "Invalid state in {path:?}"),
+                Message::InvalidAliases(path) => write!(f, "Invalid aliases in {path:?}"),
+                Message::InvalidAuditEntry(line) => write!(f, "Invalid audit entry {line}"),
+                Message::MissingPluginName => write!(f, "missing plugin name"),
+                Message::NoPlugin(name, program) => write!(
+                    f,
+                    "unrecognized subcommand '{name}', no {program} plugin on PATH"
+                ),
+                Message::UnableToRun(program) => write!(f, "Unable to run {program}"),
+                Message::CommandFailed(command, status) => {
+                    write!(f, "{command} exited with {status}")
+                }
+                Message::BrokerRefused(broker, code) => {
+                    write!(f, "{broker} refused the connection: {code}")
+                }
+                Message::NotABroker(broker) => write!(f, "{broker} isn't an MQTT broker"),
+                Message::IncompleteRequestHead => write!(f, "Incomplete request head"),
+                Message::InvalidRequestLine(line) => write!(f, "Invalid request line {line}"),
+                Message::ChunkedBodyUnsupported => {
+                    write!(f, "Chunked request bodies are not supported")
+                }
+                Message::RequestBodyTooLarge(max) => {
+                    write!(f, "Request bodies are limited to {max} bytes")
+                }
+                Message::NoHost(url) => write!(f, "No host in {url}"),
+                Message::NoAddress(host) => write!(f, "No address for {host}"),
+                Message::ComposeVariable(service, port) => write!(
+                    f,
+                    "{service}: the port {port} uses a variable, compose files aren't interpolated"
+                ),
+                #[cfg(feature = "self-update")]
+                Message::MissingReleaseAsset(name) => {
+                    write!(f, "The latest release has no {name} asset")
+                }
+                #[cfg(feature = "self-update")]
+                Message::MissingChecksum(name) => write!(f, "No published checksum for {name}"),
+                #[cfg(feature = "self-update")]
+                Message::ChecksumMismatch(name) => {
+                    write!(
+                        f,
+                        "Checksum mismatch for {name}, the binary was not replaced"
+                    )
+                }
+                #[cfg(feature = "self-update")]
+                Message::MissingReleaseKey => write!(
+                    f,
+                    "This build embeds no release signing key, releases can't be verified"
+                ),
+                #[cfg(feature = "self-update")]
+                Message::InvalidSignature(name) => {
+                    write!(f, "Invalid signature of {name}, the binary was not replaced")
+                }
+            },
+            Lang::Fr => match self {
+                Message::MissingCredentials => write!(f, "identifiants manquants"),
+                Message::MissingPassword => write!(
+                    f,
+                    "les arguments obligatoires suivants sont manquants :\n  --password <PASSWORD>"
+                ),
+                Message::AuthenticationFailed(status) => {
+                    write!(f, "Échec de l'authentification : {status}")
+                }
+                Message::ExecutionFailed(status) => write!(f, "Échec de l'exécution : {status}"),
+                Message::ResponseBody(body) => write!(f, "Corps de la réponse : {body}"),
+                Message::NoRuleWithId(id) => write!(f, "Aucune règle avec l'identifiant {id}"),
+                Message::NoRadio(band) => write!(f, "Aucune radio {band}"),
+                Message::NoMatchingAccessPoint => {
+                    write!(f, "Aucun point d'accès WiFi correspondant")
+                }
+                Message::QrCodeTooLong(length) => {
+                    write!(f, "{length} octets ne tiennent pas dans un QR code")
+                }
+                Message::UnsupportedImageFormat(path) => {
+                    write!(
+                        f,
+                        "Format d'image {path:?} non supporté, .png ou .svg attendu"
+                    )
+                }
+                Message::NoAlg(alg) => write!(f, "Aucune passerelle applicative {alg}"),
+                Message::UnsupportedWanAccess(access) => {
+                    write!(f, "La Livebox ne prend en charge aucun mode WAN {access}")
+                }
+                Message::LoginFailed => write!(f, "Échec de la connexion"),
+                Message::WanDown => write!(f, "WAN coupé"),
+                Message::VoipNotRegistered => write!(f, "Lignes VoIP non enregistrées"),
+                Message::DnsTimeout => write!(f, "Aucune réponse DNS"),
+                Message::DnsNoAnswer(name) => write!(f, "{name} n'est pas résolu"),
+                Message::NoAccessPoint(name) => write!(f, "Aucun point d'accès WiFi nommé {name}"),
+                Message::UnableToOpen(path) => write!(f, "Impossible d'ouvrir {path:?}"),
+                Message::UnableToCreate(path) => write!(f, "Impossible de créer {path:?}"),
+                Message::UnableToWrite(path) => write!(f, "Impossible d'écrire {path:?}"),
+                Message::UnableToLock(path) => write!(
+                    f,
+                    "Impossible de verrouiller {path:?}, détenu par un autre processus livebox-cli"
+                ),
+                Message::InvalidConfiguration(path) => {
+                    write!(f, "Configuration invalide dans {path:?}")
+                }
+                Message::InvalidBaseline(path) => write!(f, "Référence JSON {path:?} invalide"),
+                Message::UnableToApply(change) => write!(f, "Impossible d'appliquer {change}"),
+                Message::ChangesFailed(failures, count) => {
+                    write!(f, "{failures} modifications sur {count} ont échoué")
+                }
+                Message::CallsFailed(failures, count) => {
+                    write!(f, "{failures} appels sur {count} ont échoué")
+                }
+                Message::InvalidCall(call) => {
+                    write!(f, "Appel {call} invalide, service:méthode attendu")
+                }
+                Message::NoRequestField(field, request) => {
+                    write!(f, "Pas de {field} dans la requête {request}")
+                }
+                Message::UnableToListen(address) => {
+                    write!(f, "Impossible d'écouter sur {address}")
+                }
+                Message::InvalidCronExpression(expression) => write!(
+                    f,
+                    "Expression cron {expression:?} invalide, minute heure jour mois jour-de-semaine attendus"
+                ),
+                Message::NoScheduledTask(id) => write!(f, "Aucune tâche planifiée {id}"),
+                Message::RolledBack(change) => write!(f, "{change} annulé"),
+                Message::UnableToRollBack(change) => write!(f, "Impossible d'annuler {change}"),
+                Message::Irreversible(change) => write!(
+                    f,
+                    "{change} ne peut pas être annulé, la Livebox masque la valeur précédente"
+                ),
+                Message::NotReadyAfter(stage, seconds) => {
+                    write!(f, "{stage} toujours indisponible après {seconds} secondes")
+                }
+                Message::NoJsonPathMatch => write!(f, "Aucune correspondance pour ce JsonPath"),
+                Message::InvalidParameter(parameter) => {
+                    write!(f, "Paramètre {parameter} invalide, nom=valeur attendu")
+                }
+                Message::InvalidDuration(duration) => {
+                    write!(
+                        f,
+                        "Durée {duration} invalide, ex : 90s, 30m, 2h ou 1d attendu"
+                    )
+                }
+                Message::InvalidPipelineStep(step) => {
+                    write!(f, "Étape {step} du pipeline invalide")
+                }
+                Message::PipelineStepFailed(step) => {
+                    write!(f, "Échec de l'étape {step} du pipeline")
+                }
+                Message::UntypedOutput => write!(
+                    f,
+                    "Cette commande affiche la réponse brute de la Livebox, aucun schéma disponible"
+                ),
+                Message::TextOutput => write!(
+                    f,
+                    "Cette commande affiche du texte, pas du JSON, aucun schéma disponible"
+                ),
+                Message::Interrupted => write!(f, "Interrompu"),
+                Message::ErrorLabel => write!(f, "erreur :"),
+                Message::HintLabel => write!(f, "conseil :"),
+                Message::CausedByLabel => write!(f, "cause :"),
+                Message::InvalidCredentials => {
+                    write!(f, "La Livebox a refusé l'utilisateur ou le mot de passe")
+                }
+                Message::HintInvalidCredentials => write!(
+                    f,
+                    "vérifiez --username et --password (ou LIVEBOX_PASSWORD), la Livebox retarde \
+                     les connexions après plusieurs échecs"
+                ),
+                Message::TooManySessions => {
+                    write!(f, "La Livebox a atteint son nombre maximum de sessions")
+                }
+                Message::HintTooManySessions => write!(
+                    f,
+                    "déconnectez-vous de l'interface web de la Livebox ou attendez quelques \
+                     minutes l'expiration des sessions inactives"
+                ),
+                Message::UnsupportedFirmware => {
+                    write!(f, "Ce firmware de Livebox n'expose pas l'API /ws")
+                }
+                Message::HintUnsupportedFirmware => write!(
+                    f,
+                    "réessayez avec --api-flavor legacy, ou un profil de compatibilité avec \
+                     --profile"
+                ),
+                Message::PermissionDenied => write!(f, "Permission refusée"),
+                Message::HintPermissionDenied => write!(
+                    f,
+                    "`livebox-cli whoami` liste les commandes autorisées pour cet utilisateur"
+                ),
+                Message::HintNotInteractive => write!(
+                    f,
+                    "l'entrée standard n'est pas un terminal où confirmer, passez --yes pour \
+                     confirmer d'avance"
+                ),
+                Message::LiveboxTimeout => write!(f, "La Livebox n'a pas répondu à temps"),
+                Message::HintLiveboxTimeout => write!(
+                    f,
+                    "elle redémarre peut-être ou est surchargée, réessayez plus tard ou espacez \
+                     les requêtes avec --min-request-interval"
+                ),
+                Message::LiveboxUnreachable(url) => {
+                    write!(f, "La Livebox est injoignable à l'adresse {url}")
+                }
+                Message::HintLiveboxUnreachable => write!(
+                    f,
+                    "vérifiez --base-url et que cet hôte est connecté au réseau de la Livebox"
+                ),
+                Message::NoCacheDirectory => {
+                    write!(f, "Impossible de localiser le répertoire de cache")
+                }
+                Message::NoStateDirectory => {
+                    write!(f, "Impossible de localiser le répertoire d'état")
+                }
+                Message::UnknownModel(name, known) => {
+                    write!(f, "Modèle {name} inconnu, valeurs possibles : {known}")
+                }
+                Message::NewPassword => write!(f, "Nouveau mot de passe : "),
+                Message::ConfirmPassword => write!(f, "Confirmez le nouveau mot de passe : "),
+                Message::PasswordsMismatch => {
+                    write!(f, "Les mots de passe ne correspondent pas")
+                }
+                Message::SetupAdminPassword => {
+                    write!(f, "Changer le mot de passe d'administration ?")
+                }
+                Message::SetupSsid(name) => write!(f, "SSID de {name}"),
+                Message::SetupPassphrase(name) => {
+                    write!(f, "Clé de sécurité de {name} (vide pour la conserver) : ")
+                }
+                Message::SetupDhcpMin => {
+                    write!(f, "Première adresse attribuée par le serveur DHCP")
+                }
+                Message::SetupDhcpMax => {
+                    write!(f, "Dernière adresse attribuée par le serveur DHCP")
+                }
+                Message::SetupDisableWps => write!(f, "Désactiver le WPS ?"),
+                Message::PasswordNotVerified => write!(
+                    f,
+                    "Mot de passe changé mais la connexion avec le nouveau mot de passe a échoué"
+                ),
+                Message::InsufficientPermissions(command, group) => write!(
+                    f,
+                    "Permissions insuffisantes : `{command}` nécessite le groupe {group}"
+                ),
+                Message::ConfirmPrune(count) => {
+                    write!(f, "Supprimer ces {count} règles NAT ?")
+                }
+                Message::ConfirmRemoveRule(id) => write!(f, "Supprimer la règle NAT {id} ?"),
+                Message::ConfirmSetDmz(destination) => {
+                    write!(f, "Exposer tous les ports de {destination} sur Internet ?")
+                }
+                Message::ConfirmSetWanMode(mode) => write!(
+                    f,
+                    "Basculer le WAN en {mode} ? La connexion Internet sera interrompue"
+                ),
+                Message::ConfirmReboot => write!(f, "Redémarrer la Livebox ?"),
+                Message::ConfirmFactoryReset => write!(
+                    f,
+                    "Restaurer les paramètres d'usine ? Toute la configuration sera perdue"
+                ),
+                Message::Aborted => write!(f, "Abandon"),
+                Message::CheckUnsupported(command) => {
+                    write!(f, "`{command}` ne prend pas en charge --check")
+                }
+                Message::NoConfigDirectory => {
+                    write!(f, "Impossible de localiser le répertoire de configuration")
+                }
+                Message::UnknownAlias(name) => write!(f, "Alias inconnu {name}"),
+                Message::InvalidMac(mac) => write!(f, "Adresse MAC invalide {mac}"),
+                Message::InvalidHexValue(value) => {
+                    write!(f, "Valeur hexBinary invalide {value}")
+                }
+                Message::NoDeviceAddress(name) => {
+                    write!(f, "La Livebox ne connaît aucune adresse IP de {name}")
+                }
+                Message::NoDeviceAtAddress(address) => {
+                    write!(
+                        f,
+                        "La Livebox ne connaît aucun appareil à suivre en {address}"
+                    )
+                }
+                Message::UnableToRead(path) => write!(f, "Impossible de lire {path:?}"),
+                Message::InvalidState(path) => write!(f, "État invalide dans {path:?}"),
+                Message::InvalidAliases(path) => write!(f, "Alias invalides dans {path:?}"),
+                Message::InvalidAuditEntry(line) => {
+                    write!(f, "Entrée du journal d'audit invalide {line}")
+                }
+                Message::MissingPluginName => write!(f, "nom de plugin manquant"),
+                Message::NoPlugin(name, program) => write!(
+                    f,
+                    "sous-commande '{name}' inconnue, aucun plugin {program} dans le PATH"
+                ),
+                Message::UnableToRun(program) => write!(f, "Impossible d'exécuter {program}"),
+                Message::CommandFailed(command, status) => {
+                    write!(f, "{command} s'est terminé avec {status}")
+                }
+                Message::BrokerRefused(broker, code) => {
+                    write!(f, "{broker} a refusé la connexion : {code}")
+                }
+                Message::NotABroker(broker) => write!(f, "{broker} n'est pas un broker MQTT"),
+                Message::IncompleteRequestHead => write!(f, "En-tête de requête incomplet"),
+                Message::InvalidRequestLine(line) => {
+                    write!(f, "Ligne de requête invalide {line}")
+                }
+                Message::ChunkedBodyUnsupported => {
+                    write!(f, "Les corps de requête découpés (chunked) ne sont pas pris en charge")
+                }
+                Message::RequestBodyTooLarge(max) => {
+                    write!(f, "Les corps de requête sont limités à {max} octets")
+                }
+                Message::NoHost(url) => write!(f, "Aucun hôte dans {url}"),
+                Message::NoAddress(host) => write!(f, "Aucune adresse pour {host}"),
+                Message::ComposeVariable(service, port) => write!(
+                    f,
+                    "{service} : le port {port} utilise une variable, les fichiers compose ne sont pas interpolés"
+                ),
+                #[cfg(feature = "self-update")]
+                Message::MissingReleaseAsset(name) => {
+                    write!(f, "La dernière version ne contient pas de fichier {name}")
+                }
+                #[cfg(feature = "self-update")]
+                Message::MissingChecksum(name) => {
+                    write!(f, "Aucune somme de contrôle publiée pour {name}")
+                }
+                #[cfg(feature = "self-update")]
+                Message::ChecksumMismatch(name) => write!(
+                    f,
+                    "Somme de contrôle invalide pour {name}, le binaire n'a pas été remplacé"
+                ),
+                #[cfg(feature = "self-update")]
+                Message::MissingReleaseKey => write!(
+                    f,
+                    "Ce binaire n'embarque pas de clé de signature, les versions ne peuvent pas \
+                     être vérifiées"
+                ),
+                #[cfg(feature = "self-update")]
+                Message::InvalidSignature(name) => write!(
+                    f,
+                    "Signature invalide pour {name}, le binaire n'a pas été remplacé"
+                ),
+            },
+        }
+    }
+}
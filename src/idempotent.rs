@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    declarative,
+    i18n::Message,
+    livebox::{self, Client},
+    prompt::Confirmation,
+    AlgActions, Commands, DmzActions, FirewallActions, FirewallSettingsActions, MulticastActions,
+    RadioActions, WanActions, WanModeActions, WifiActions,
+};
+
+/// Outcome of an idempotent command: whether the Livebox configuration changed, or would change
+/// in check mode
+#[derive(Serialize, JsonSchema)]
+pub struct ChangeReport {
+    changed: bool,
+    check: bool,
+}
+
+/// Whether the command compares the actual configuration before changing it, and can only report
+/// the changes with `--check`.
+pub fn supports_check(command: &Commands) -> bool {
+    // only reports the latest version
+    #[cfg(feature = "self-update")]
+    if matches!(command, Commands::SelfUpdate) {
+        return true;
+    }
+    matches!(
+        command,
+        Commands::Apply { .. }
+            | Commands::Nat {
+                action: FirewallActions::Enable(_)
+                    | FirewallActions::Disable(_)
+                    | FirewallActions::FromCompose { .. },
+            }
+            | Commands::Firewall {
+                action: FirewallSettingsActions::Alg {
+                    action: AlgActions::Enable { .. } | AlgActions::Disable { .. },
+                },
+            }
+            | Commands::Multicast {
+                action: MulticastActions::Enable | MulticastActions::Disable,
+            }
+            | Commands::Wan {
+                action: WanActions::Mode {
+                    action: WanModeActions::Set { .. },
+                },
+            }
+            | Commands::Dmz {
+                action: DmzActions::Set { .. } | DmzActions::Remove,
+            }
+            | Commands::Wifi {
+                action: WifiActions::Radio {
+                    action: RadioActions::Set(_),
+                },
+            }
+    )
+}
+
+fn report(changed: bool, check: bool) -> Result<Value> {
+    Ok(serde_json::to_value(ChangeReport { changed, check })?)
+}
+
+pub async fn set_nat_rule_enable(
+    client: &Client,
+    id: &str,
+    enable: bool,
+    check: bool,
+) -> Result<Value> {
+    let rule = client
+        .structured_nat_rules()
+        .await?
+        .into_iter()
+        .find(|rule| declarative::nat_rule_matches(rule, id))
+        .ok_or_else(|| anyhow!(Message::NoRuleWithId(id).to_string()))?;
+    let changed = rule.enable != enable;
+    if changed && !check {
+        match enable {
+            true => client.enable_nat_rule(rule.id).await?,
+            false => client.disable_nat_rule(rule.id).await?,
+        };
+    }
+    report(changed, check)
+}
+
+pub async fn set_alg_enable(
+    client: &Client,
+    alg: livebox::Alg,
+    enable: bool,
+    check: bool,
+) -> Result<Value> {
+    let changed = client
+        .list_algs()
+        .await?
+        .get(alg.as_str())
+        .ok_or_else(|| anyhow!(Message::NoAlg(alg.as_str()).to_string()))?
+        .enable
+        != enable;
+    if changed && !check {
+        client.set_alg_enable(alg, enable).await?;
+    }
+    report(changed, check)
+}
+
+pub async fn set_igmp_snooping(client: &Client, enable: bool, check: bool) -> Result<Value> {
+    let changed = client.get_multicast_status().await?.snooping.enable != enable;
+    if changed && !check {
+        client.set_igmp_snooping(enable).await?;
+    }
+    report(changed, check)
+}
+
+/// Switches to a WAN mode of the access, keeping the connection type of the current mode when
+/// supported (ex: `GPON_DHCP` from `VDSL_DHCP`)
+pub async fn set_wan_access(
+    client: &Client,
+    access: livebox::WanAccess,
+    check: bool,
+    confirmation: Confirmation,
+) -> Result<Value> {
+    let current = client.get_wan_status().await?.wan_mode;
+    let changed = livebox::WanAccess::of(&current) != Some(access);
+    if changed && !check {
+        let connection = current.split_once('_').map(|(_, connection)| connection);
+        let modes: Vec<String> = client
+            .list_wan_modes()
+            .await?
+            .into_iter()
+            .filter(|mode| livebox::WanAccess::of(mode) == Some(access))
+            .collect();
+        let mode = modes
+            .iter()
+            .find(|mode| mode.split_once('_').map(|(_, connection)| connection) == connection)
+            .or(modes.first())
+            .ok_or_else(|| anyhow!(Message::UnsupportedWanAccess(access.as_str()).to_string()))?;
+        confirmation
+            .require(Message::ConfirmSetWanMode(mode))
+            .await?;
+        client.set_wan_mode(mode.clone()).await?;
+    }
+    report(changed, check)
+}
+
+pub async fn set_dmz(
+    client: &Client,
+    destination: &str,
+    check: bool,
+    confirmation: Confirmation,
+) -> Result<Value> {
+    let changed = client
+        .get_dmz()
+        .await?
+        .is_none_or(|dmz| !dmz.enable || dmz.destination_ip_address != destination);
+    if changed && !check {
+        confirmation
+            .require(Message::ConfirmSetDmz(destination))
+            .await?;
+        client.set_dmz(destination.to_string()).await?;
+    }
+    report(changed, check)
+}
+
+pub async fn remove_dmz(client: &Client, check: bool) -> Result<Value> {
+    let changed = client.get_dmz().await?.is_some();
+    if changed && !check {
+        client.remove_dmz().await?;
+    }
+    report(changed, check)
+}
+
+pub async fn configure_radio(
+    client: &Client,
+    band: livebox::FrequencyBand,
+    config: livebox::RadioConfig,
+    check: bool,
+) -> Result<Value> {
+    let changed = client
+        .list_radios()
+        .await?
+        .values()
+        .find(|radio| radio.operating_frequency_band == band)
+        .ok_or_else(|| anyhow!(Message::NoRadio(band.as_str()).to_string()))?
+        .is_changed_by(&config);
+    if changed && !check {
+        client.configure_radio(band, config).await?;
+    }
+    report(changed, check)
+}
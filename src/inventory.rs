@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    net::Ipv4Addr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{aliases::Aliases, livebox::Client, redact};
+
+/// Inventory formats of `devices export`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// NetBox IP addresses bulk import
+    NetboxCsv,
+    /// nmap XML output, as written by `nmap -oX`
+    NmapXml,
+    /// `/etc/hosts` fragment
+    Hosts,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Host {
+    address: String,
+    mac: String,
+    /// Device name as given by the Livebox
+    name: String,
+    /// Host names derived from the device name and alias
+    hostnames: Vec<String>,
+    active: bool,
+}
+
+/// Device list of the Livebox in an inventory format, devices without an address are skipped.
+/// Personal data is masked before formatting with `redact`. A host name shared by several devices
+/// is only given to the first one.
+pub async fn export(client: &Client, format: ExportFormat, redact: bool) -> Result<String> {
+    let aliases = Aliases::load()?;
+    let hosts: Vec<_> = client
+        .list_devices()
+        .await?
+        .into_iter()
+        .filter(|device| !device.ip_address.is_empty())
+        .map(|device| {
+            let alias = aliases.alias_of(&device.phys_address);
+            let mut hostnames = Vec::new();
+            for name in [Some(device.name.as_str()), alias].into_iter().flatten() {
+                let hostname = hostname(name);
+                if !hostname.is_empty() && !hostnames.contains(&hostname) {
+                    hostnames.push(hostname);
+                }
+            }
+            Host {
+                address: device.ip_address,
+                mac: device.phys_address,
+                name: device.name,
+                hostnames,
+                active: device.active,
+            }
+        })
+        .collect();
+    let mut hosts: Vec<Host> = match redact {
+        true => serde_json::from_value(redact::redact(serde_json::to_value(hosts)?))?,
+        false => hosts,
+    };
+    deduplicate(&mut hosts);
+    match format {
+        ExportFormat::NetboxCsv => {
+            let netmask: Ipv4Addr = client.get_lan_ip().await?.netmask.parse()?;
+            netbox_csv(&hosts, netmask.to_bits().count_ones())
+        }
+        ExportFormat::NmapXml => nmap_xml(&hosts),
+        ExportFormat::Hosts => etc_hosts(&hosts),
+    }
+}
+
+/// Lowercase letters, digits and hyphens of a device name (ex: `NAS de Jo` is `nas-de-jo`)
+fn hostname(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Removes the host names already given to a previous host, with a warning.
+fn deduplicate(hosts: &mut [Host]) {
+    let mut owners = HashMap::new();
+    for host in hosts {
+        host.hostnames
+            .retain(|hostname| match owners.get(hostname) {
+                Some(owner) => {
+                    warn!(
+                        "{} is also named {hostname}, keeping it for {owner}",
+                        host.mac
+                    );
+                    false
+                }
+                None => {
+                    owners.insert(hostname.clone(), host.mac.clone());
+                    true
+                }
+            });
+    }
+}
+
+fn netbox_csv(hosts: &[Host], prefix_length: u32) -> Result<String> {
+    let mut csv = String::from("address,status,dns_name,description\n");
+    for host in hosts {
+        let status = match host.active {
+            true => "active",
+            false => "deprecated",
+        };
+        let dns_name = host
+            .hostnames
+            .first()
+            .map(|hostname| format!("{hostname}.lan"))
+            .unwrap_or_default();
+        let description = format!("{} ({})", host.name, host.mac);
+        writeln!(
+            csv,
+            "{}/{prefix_length},{status},{dns_name},{}",
+            host.address,
+            csv_field(&description)
+        )?;
+    }
+    Ok(csv)
+}
+
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+fn nmap_xml(hosts: &[Host]) -> Result<String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(xml, "<!DOCTYPE nmaprun>")?;
+    writeln!(
+        xml,
+        r#"<nmaprun scanner="livebox-cli" args="livebox-cli devices export --format nmap-xml" start="{now}" version="{}" xmloutputversion="1.05">"#,
+        env!("CARGO_PKG_VERSION")
+    )?;
+    for host in hosts {
+        let state = match host.active {
+            true => "up",
+            false => "down",
+        };
+        writeln!(xml, "<host>")?;
+        writeln!(xml, r#"<status state="{state}" reason="user-set"/>"#)?;
+        let address_type = match host.address.contains(':') {
+            true => "ipv6",
+            false => "ipv4",
+        };
+        writeln!(
+            xml,
+            r#"<address addr="{}" addrtype="{address_type}"/>"#,
+            xml_escape(&host.address)
+        )?;
+        if !host.mac.is_empty() {
+            writeln!(
+                xml,
+                r#"<address addr="{}" addrtype="mac"/>"#,
+                xml_escape(&host.mac.to_uppercase())
+            )?;
+        }
+        writeln!(xml, "<hostnames>")?;
+        for hostname in &host.hostnames {
+            writeln!(xml, r#"<hostname name="{hostname}" type="user"/>"#)?;
+        }
+        writeln!(xml, "</hostnames>")?;
+        writeln!(xml, "</host>")?;
+    }
+    let up = hosts.iter().filter(|host| host.active).count();
+    writeln!(xml, "<runstats>")?;
+    writeln!(xml, r#"<finished time="{now}" exit="success"/>"#)?;
+    writeln!(
+        xml,
+        r#"<hosts up="{up}" down="{}" total="{}"/>"#,
+        hosts.len() - up,
+        hosts.len()
+    )?;
+    writeln!(xml, "</runstats>")?;
+    writeln!(xml, "</nmaprun>")?;
+    Ok(xml)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn etc_hosts(hosts: &[Host]) -> Result<String> {
+    let mut fragment = String::from("# generated by livebox-cli devices export\n");
+    for host in hosts.iter().filter(|host| !host.hostnames.is_empty()) {
+        let names: Vec<_> = host
+            .hostnames
+            .iter()
+            .flat_map(|hostname| [format!("{hostname}.lan"), hostname.clone()])
+            .collect();
+        writeln!(fragment, "{}\t{}", host.address, names.join(" "))?;
+    }
+    Ok(fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(address: &str, mac: &str, name: &str, active: bool) -> Host {
+        Host {
+            address: address.to_string(),
+            mac: mac.to_string(),
+            name: name.to_string(),
+            hostnames: Some(hostname(name))
+                .filter(|hostname| !hostname.is_empty())
+                .into_iter()
+                .collect(),
+            active,
+        }
+    }
+
+    fn hosts() -> Vec<Host> {
+        vec![
+            host("192.168.1.10", "aa:bb:cc:dd:ee:01", "NAS de Jo", true),
+            host(
+                "192.168.1.11",
+                "aa:bb:cc:dd:ee:02",
+                "TV \"salon\", <4K> & co",
+                false,
+            ),
+        ]
+    }
+
+    #[test]
+    fn derives_host_names_from_device_names() {
+        assert_eq!(hostname("NAS de Jo"), "nas-de-jo");
+        assert_eq!(hostname("  Galaxy_S23 (Jo)  "), "galaxy-s23-jo");
+        assert_eq!(hostname("Télé"), "t-l");
+        assert_eq!(hostname("***"), "");
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        assert_eq!(csv_field("NAS (aa:bb)"), "NAS (aa:bb)");
+        assert_eq!(csv_field("TV, salon"), "\"TV, salon\"");
+        assert_eq!(csv_field("TV \"salon\""), "\"TV \"\"salon\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn writes_netbox_csv() {
+        assert_eq!(
+            netbox_csv(&hosts(), 24).unwrap(),
+            "address,status,dns_name,description\n\
+             192.168.1.10/24,active,nas-de-jo.lan,NAS de Jo (aa:bb:cc:dd:ee:01)\n\
+             192.168.1.11/24,deprecated,tv-salon-4k-co.lan,\
+             \"TV \"\"salon\"\", <4K> & co (aa:bb:cc:dd:ee:02)\"\n"
+        );
+    }
+
+    #[test]
+    fn escapes_xml_attributes() {
+        assert_eq!(
+            xml_escape("TV \"salon\", <4K> & co"),
+            "TV &quot;salon&quot;, &lt;4K&gt; &amp; co"
+        );
+        let xml = nmap_xml(&hosts()).unwrap();
+        assert!(xml.contains(r#"<address addr="AA:BB:CC:DD:EE:01" addrtype="mac"/>"#));
+        assert!(xml.contains(r#"<hosts up="1" down="1" total="2"/>"#));
+    }
+
+    #[test]
+    fn writes_an_etc_hosts_fragment() {
+        let mut hosts = hosts();
+        hosts[1].hostnames.push("tv".to_string());
+        hosts.push(host("192.168.1.12", "aa:bb:cc:dd:ee:03", "***", true));
+        assert_eq!(
+            etc_hosts(&hosts).unwrap(),
+            "# generated by livebox-cli devices export\n\
+             192.168.1.10\tnas-de-jo.lan nas-de-jo\n\
+             192.168.1.11\ttv-salon-4k-co.lan tv-salon-4k-co tv.lan tv\n"
+        );
+    }
+
+    #[test]
+    fn gives_shared_host_names_to_the_first_device() {
+        let mut hosts = vec![
+            host("192.168.1.10", "aa:bb:cc:dd:ee:01", "Phone", true),
+            host("192.168.1.11", "aa:bb:cc:dd:ee:02", "phone", true),
+        ];
+        hosts[1].hostnames.push("phone-jo".to_string());
+        deduplicate(&mut hosts);
+        assert_eq!(hosts[0].hostnames, ["phone"]);
+        assert_eq!(hosts[1].hostnames, ["phone-jo"]);
+    }
+}
@@ -0,0 +1,5 @@
+//! Livebox client of the `livebox-cli` binary, usable by other tools. A blocking facade is
+//! available with the `blocking` feature.
+
+pub mod i18n;
+pub mod livebox;
@@ -1,17 +1,74 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use anyhow::{anyhow, Ok, Result};
 
 use log::{debug, warn};
 use reqwest::{
-    cookie::Jar,
-    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE},
-    Client as ReqwestClient, ClientBuilder as ReqwestClientBuilder,
+    cookie::{CookieStore, Jar},
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    Client as ReqwestClient, ClientBuilder as ReqwestClientBuilder, RequestBuilder,
 };
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
 use serde_json::Value;
 
-pub(super) const LIVEBOX_BASE_URL: &str = "http://livebox.home";
+use crate::i18n::Message;
+use rate_limit::RateLimiter;
+use timing::timed;
+use tokio::sync::OnceCell;
+
+pub use alg::{Alg, AlgView};
+pub use audit::{AuditEntry, AuditLog};
+pub use compat::Profile;
+pub use devices::DeviceView;
+pub use dhcp::DhcpOptionView;
+pub use dmz::DmzView;
+pub use legacy::{ApiFlavor, UnsupportedApi};
+pub use models::{find_model, Model, MODELS};
+pub use multicast::MulticastStatus;
+pub use neighbours::Neighbour;
+pub use pool::Pool;
+pub use rate_limit::RateLimit;
+pub use sessions::{CachedContext, ContextCache, TooManySessions};
+pub use timing::Timings;
+pub use users::{InvalidCredentials, PermissionDenied};
+pub use wan::{WanAccess, WanModeView};
+pub use wifi::{
+    AccessPointView, AssociatedStation, FrequencyBand, NeighborAccessPoint, RadioConfig, RadioView,
+};
+
+mod alg;
+mod audit;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod compat;
+mod ddns;
+mod devices;
+mod dhcp;
+mod diagnostics;
+mod dmz;
+mod legacy;
+mod models;
+mod multicast;
+mod neighbours;
+mod pool;
+mod rate_limit;
+mod sessions;
+mod system;
+#[cfg(feature = "test-util")]
+pub mod test_support;
+mod timing;
+mod users;
+mod wan;
+mod wifi;
+
+pub const LIVEBOX_BASE_URL: &str = "http://livebox.home";
 const APPLICATION_NAME: &str = "livebox-cli";
 
 const APPLICATION_SAH_WS_CALL: &str = "application/x-sah-ws-4-call+json";
@@ -19,9 +76,30 @@ const X_SAH_LOGIN: &str = "X-Sah-Login";
 const X_SAH_LOGOUT: &str = "X-Sah-Logout";
 const X_CONTEXT: &str = "x-context";
 
-pub(super) struct ClientBuilder {
+/// Request fields masked in logs
+const SECRET_FIELDS: &[&str] = &["password", "newPassword", "KeyPassPhrase"];
+
+/// Password of the builder credentials
+#[derive(Clone)]
+enum Password {
+    /// Taken by the first successful login
+    Given(Arc<Mutex<Option<SecretString>>>),
+    /// Read again at each login
+    Source(Arc<dyn Fn() -> Result<SecretString> + Send + Sync>),
+}
+
+#[derive(Clone)]
+pub struct ClientBuilder {
     base_url_ws: String,
-    credentials: Option<(String, String)>,
+    credentials: Option<(String, Password)>,
+    rate_limit: RateLimit,
+    pool: Pool,
+    api_flavor: ApiFlavor,
+    profile: Profile,
+    context_cache: Option<ContextCache>,
+    timings: Option<Arc<Timings>>,
+    audit_log: Option<AuditLog>,
+    change_group: Option<String>,
 }
 
 impl Default for ClientBuilder {
@@ -29,6 +107,14 @@ impl Default for ClientBuilder {
         Self {
             base_url_ws: LIVEBOX_BASE_URL.to_string(),
             credentials: None,
+            rate_limit: RateLimit::default(),
+            pool: Pool::default(),
+            api_flavor: ApiFlavor::default(),
+            profile: Profile::default(),
+            context_cache: None,
+            timings: None,
+            audit_log: None,
+            change_group: None,
         }
     }
 }
@@ -40,33 +126,275 @@ impl ClientBuilder {
         self
     }
 
-    pub fn with_credentials(mut self, username: String, password: String) -> Self {
+    /// The password is only kept until the first successful login, use a password source to open
+    /// more sessions.
+    pub fn with_credentials(mut self, username: String, password: SecretString) -> Self {
+        let password = Password::Given(Arc::new(Mutex::new(Some(password))));
         self.credentials = Some((username, password));
         self
     }
 
+    /// The password is read from the source at each login instead of being kept in memory
+    /// (ex: from the environment or a prompt).
+    pub fn with_password_source<F>(mut self, username: String, source: F) -> Self
+    where
+        F: Fn() -> Result<SecretString> + Send + Sync + 'static,
+    {
+        self.credentials = Some((username, Password::Source(Arc::new(source))));
+        self
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.credentials
+            .as_ref()
+            .map(|(username, _)| username.as_str())
+    }
+
+    /// Reads the password, a given one is missing once a session has been opened with it.
+    pub fn password(&self) -> Result<SecretString> {
+        let password = match self.credentials.as_ref().map(|(_, password)| password) {
+            Some(Password::Given(password)) => password.lock().unwrap().clone(),
+            Some(Password::Source(source)) => Some(source()?),
+            None => None,
+        };
+        password.ok_or_else(|| anyhow!(Message::MissingCredentials.to_string()))
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    pub fn with_api_flavor(mut self, api_flavor: ApiFlavor) -> Self {
+        self.api_flavor = api_flavor;
+        self
+    }
+
+    /// Remaps requests for rebranded firmware, its API flavor applies unless one is given
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_pool(mut self, pool: Pool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Tracks opened contexts, allowing to release leaked ones when the Livebox refuses new sessions
+    pub fn with_context_cache(mut self, context_cache: ContextCache) -> Self {
+        self.context_cache = Some(context_cache);
+        self
+    }
+
+    /// Collects the duration of each phase of the session
+    pub fn with_timings(mut self, timings: Arc<Timings>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// Records the requests changing the configuration
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Calls changing the configuration fail before being sent when the authenticated user lacks
+    /// the group (ex: `admin`).
+    pub fn with_change_group(mut self, group: &str) -> Self {
+        self.change_group = Some(group.to_string());
+        self
+    }
+
+    /// Checks the Livebox answers HTTP requests, whatever the response status.
+    pub async fn probe(&self, timeout: Duration) -> Result<()> {
+        ReqwestClientBuilder::default()
+            .timeout(timeout)
+            .build()?
+            .get(&self.base_url_ws)
+            .send()
+            .await?;
+        Ok(())
+    }
+
     pub async fn build(self) -> Result<Client> {
-        let (username, password) = self.credentials.ok_or(anyhow!("missing credentials"))?;
-        Client::login(self.base_url_ws, username, password).await
+        let username = self
+            .username()
+            .ok_or(anyhow!(Message::MissingCredentials.to_string()))?
+            .to_string();
+        let password = self.password()?;
+        let client = self.open_session(username, &password).await?;
+        if let Some((_, Password::Given(password))) = &self.credentials {
+            password.lock().unwrap().take();
+        }
+        Ok(client)
+    }
+
+    async fn open_session(&self, username: String, password: &SecretString) -> Result<Client> {
+        if let Some(timings) = &self.timings {
+            timings.measure_network(&self.base_url_ws).await?;
+        }
+        let api_flavor = match (self.api_flavor, self.profile.api_flavor) {
+            (ApiFlavor::Auto, Some(api_flavor)) => api_flavor,
+            (api_flavor, _) => api_flavor,
+        };
+        loop {
+            let login = timed(
+                self.timings.as_deref(),
+                "login",
+                Client::login(
+                    self.base_url_ws.clone(),
+                    username.clone(),
+                    password,
+                    self.rate_limit.clone(),
+                    &self.pool,
+                    api_flavor,
+                ),
+            )
+            .await
+            .map(|mut client| {
+                client.timings = self.timings.clone();
+                client.profile = self.profile.clone();
+                client.audit_log = self.audit_log.clone();
+                client.change_group = self.change_group.clone();
+                client
+            });
+            // legacy sessions are bound to cookies and aren't tracked
+            let Some(cache) = self
+                .context_cache
+                .as_ref()
+                .filter(|_| api_flavor != ApiFlavor::Legacy)
+            else {
+                return login;
+            };
+            let mut client = match login {
+                Err(err) if err.is::<TooManySessions>() => {
+                    let Some(oldest) = cache
+                        .load()?
+                        .into_iter()
+                        .find(|context| context.base_url_ws == self.base_url_ws)
+                    else {
+                        return Err(err);
+                    };
+                    warn!("Releasing leaked context {}", oldest.context_id);
+                    if let Err(err) = oldest.release().await {
+                        warn!("Unable to release context {}: {err:#}", oldest.context_id);
+                    }
+                    cache.remove(&oldest.context_id)?;
+                    continue;
+                }
+                login => login?,
+            };
+            if client.api_flavor == ApiFlavor::Legacy {
+                return Ok(client);
+            }
+            cache.add(CachedContext::new(
+                client.context_id.clone(),
+                client.base_url_ws.clone(),
+                client.cookie_header(),
+            ))?;
+            client.context_cache = Some(cache.clone());
+            return Ok(client);
+        }
     }
 }
 
-pub(super) struct Client {
+pub struct Client {
     http_client: ReqwestClient,
+    cookie_store: Arc<Jar>,
+    rate_limiter: RateLimiter,
+    context_cache: Option<ContextCache>,
+    timings: Option<Arc<Timings>>,
     base_url_ws: String,
+    api_flavor: ApiFlavor,
+    profile: Profile,
     context_id: String,
+    username: String,
+    groups: Vec<String>,
+    audit_log: Option<AuditLog>,
+    change_group: Option<String>,
+    /// Groups asked to the Livebox when the login response has none
+    queried_groups: OnceCell<Vec<String>>,
 }
 
 impl Client {
-    async fn login(base_url: String, username: String, password: String) -> Result<Self> {
+    async fn login(
+        base_url: String,
+        username: String,
+        password: &SecretString,
+        rate_limit: RateLimit,
+        pool: &Pool,
+        api_flavor: ApiFlavor,
+    ) -> Result<Self> {
         let cookie_store = Arc::new(Jar::default());
-        let http_client = ReqwestClientBuilder::default()
+        // the connection opened to log in is reused by the whole session
+        let http_client = pool
+            .configure(ReqwestClientBuilder::default())
             .cookie_provider(cookie_store.clone())
             .build()
             .expect("error building HTTP client");
 
+        let legacy_base_url = base_url.strip_suffix("/ws").unwrap_or(&base_url);
+        let (context, api_flavor) = match api_flavor {
+            ApiFlavor::Legacy => (
+                legacy::authenticate(&http_client, legacy_base_url, &username, password).await?,
+                ApiFlavor::Legacy,
+            ),
+            ApiFlavor::Ws => (
+                Self::create_context(&http_client, &base_url, username, password).await?,
+                ApiFlavor::Ws,
+            ),
+            ApiFlavor::Auto => {
+                match Self::create_context(&http_client, &base_url, username.clone(), password)
+                    .await
+                {
+                    Err(err) if err.is::<UnsupportedApi>() => {
+                        debug!("Falling back to the legacy API: {err:#}");
+                        let context = legacy::authenticate(
+                            &http_client,
+                            legacy_base_url,
+                            &username,
+                            password,
+                        )
+                        .await?;
+                        (context, ApiFlavor::Legacy)
+                    }
+                    context => (context?, ApiFlavor::Ws),
+                }
+            }
+        };
+
+        Ok(Client {
+            http_client,
+            cookie_store,
+            rate_limiter: RateLimiter::new(rate_limit),
+            context_cache: None,
+            timings: None,
+            base_url_ws: base_url,
+            api_flavor,
+            profile: Profile::default(),
+            context_id: context.context_id,
+            username: context.username,
+            groups: context
+                .groups
+                .split(',')
+                .filter(|group| !group.is_empty())
+                .map(str::to_string)
+                .collect(),
+            audit_log: None,
+            change_group: None,
+            queried_groups: OnceCell::new(),
+        })
+    }
+
+    async fn create_context(
+        http_client: &ReqwestClient,
+        base_url: &str,
+        username: String,
+        password: &SecretString,
+    ) -> Result<LoginContext> {
         let response = http_client
-            .post(&base_url)
+            .post(base_url)
             .header(CONTENT_TYPE, APPLICATION_SAH_WS_CALL)
             .header(AUTHORIZATION, X_SAH_LOGIN)
             .json(&SysbusRequest::SahDeviceInformation(
@@ -74,42 +402,51 @@ impl Client {
                     parameters: LoginParameters {
                         application_name: APPLICATION_NAME.to_string(),
                         username,
-                        password,
+                        password: password.clone(),
                     },
                 },
             ))
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await?;
-            return Err(anyhow!("Authentication failed: {status}")
-                .context(format!("Response body: {body}")));
+        let status = response.status();
+        let body = response.text().await?;
+        if sessions::is_too_many_sessions(status.as_u16(), &body) {
+            return Err(anyhow!(TooManySessions).context(ResponseBody(body)));
+        }
+        if legacy::is_unsupported_api(status.as_u16()) {
+            return Err(anyhow!(UnsupportedApi).context(ResponseBody(body)));
+        }
+        if users::is_invalid_credentials(status.as_u16()) {
+            return Err(anyhow!(InvalidCredentials).context(ResponseBody(body)));
+        }
+        if !status.is_success() {
+            return Err(anyhow!(Message::AuthenticationFailed(&status).to_string())
+                .context(ResponseBody(body)));
         }
 
-        let context_id = response.json::<LoginResponse>().await?.data.context_id;
+        Ok(serde_json::from_str::<LoginResponse>(&body)?.data)
+    }
 
-        let context_headers = HeaderMap::from_iter(vec![
-            (ACCEPT, HeaderValue::from_static(APPLICATION_SAH_WS_CALL)),
-            (
-                HeaderName::from_static(X_CONTEXT),
-                HeaderValue::from_str(&context_id).unwrap(),
-            ),
-        ]);
+    pub fn base_url_ws(&self) -> &str {
+        &self.base_url_ws
+    }
 
-        Ok(Client {
-            http_client: ReqwestClientBuilder::default()
-                .cookie_provider(cookie_store)
-                .default_headers(context_headers)
-                .build()
-                .expect("error building HTTP client"),
-            base_url_ws: base_url,
-            context_id,
-        })
+    pub fn context_id(&self) -> &str {
+        &self.context_id
+    }
+
+    /// Session cookies as a `Cookie` header value
+    pub fn cookie_header(&self) -> Option<String> {
+        let url = self.base_url_ws.parse().ok()?;
+        let cookies = self.cookie_store.cookies(&url)?;
+        cookies.to_str().ok().map(str::to_string)
     }
 
     pub async fn logout(&self) -> Result<()> {
+        if self.api_flavor == ApiFlavor::Legacy {
+            return self.legacy_logout().await;
+        }
         let req = SysbusRequest::SahDeviceInformation(SahMethod::ReleaseContext {
             parameters: LogoutParameters::default(),
         });
@@ -118,19 +455,22 @@ impl Client {
             &self.base_url_ws,
             serde_json::to_string_pretty(&req)?
         );
-        let response = self
-            .http_client
-            .post(&self.base_url_ws)
-            .json(&req)
-            .header(AUTHORIZATION, format!("{X_SAH_LOGOUT} {}", self.context_id))
-            .send()
-            .await?;
-        let status = response.status();
-        let body = response.text().await?;
+        let _permit = self.rate_limiter.acquire().await;
+        let (status, body) = timed(self.timings.as_deref(), "logout", async {
+            let response = self
+                .post()
+                .json(&req)
+                .header(AUTHORIZATION, format!("{X_SAH_LOGOUT} {}", self.context_id))
+                .send()
+                .await?;
+            Ok((response.status(), response.text().await?))
+        })
+        .await?;
         debug!("<<< {status}\n{body}");
         if status.as_u16() != 401 {
             warn!("Logout error: {status}\n{body}")
         }
+        self.forget()?;
         let response = serde_json::from_str::<LogoutResponse>(&body)?;
         if response.status != 1 {
             warn!("Logout error: {body}")
@@ -138,30 +478,137 @@ impl Client {
         Ok(())
     }
 
+    async fn legacy_logout(&self) -> Result<()> {
+        let url = legacy::logout_url(self.base_url());
+        debug!(">>> POST {url}");
+        let _permit = self.rate_limiter.acquire().await;
+        let (status, body) = timed(self.timings.as_deref(), "logout", async {
+            let response = self.post_to(&url).send().await?;
+            Ok((response.status(), response.text().await?))
+        })
+        .await?;
+        debug!("<<< {status}\n{body}");
+        if !status.is_success() {
+            warn!("Logout error: {status}\n{body}")
+        }
+        Ok(())
+    }
+
+    /// Stops tracking the context, when it has been released by other means (ex: reboot).
+    pub fn forget(&self) -> Result<()> {
+        if let Some(cache) = &self.context_cache {
+            cache.remove(&self.context_id)?;
+        }
+        Ok(())
+    }
+
+    /// Request within the session context
+    fn post(&self) -> RequestBuilder {
+        self.post_to(&self.base_url_ws)
+    }
+
+    fn post_to(&self, url: &str) -> RequestBuilder {
+        self.http_client
+            .post(url)
+            .header(ACCEPT, APPLICATION_SAH_WS_CALL)
+            .header(X_CONTEXT, &self.context_id)
+    }
+
+    /// Livebox base url, without the `/ws` suffix
+    fn base_url(&self) -> &str {
+        self.base_url_ws
+            .strip_suffix("/ws")
+            .unwrap_or(&self.base_url_ws)
+    }
+
     async fn exec<R: Serialize>(&self, request: R) -> Result<Value> {
-        debug!(
-            ">>> POST {}\n{}",
-            &self.base_url_ws,
-            serde_json::to_string_pretty(&request)?
-        );
-        let response = self
-            .http_client
-            .post(&self.base_url_ws)
-            .header(CONTENT_TYPE, APPLICATION_SAH_WS_CALL)
-            .json(&request)
-            .send()
-            .await?;
+        let request = self.profile.apply(serde_json::to_value(request)?);
+        if let Some(group) = &self.change_group {
+            if audit::is_mutation(request["method"].as_str().unwrap_or_default()) {
+                self.check_group(group).await?;
+            }
+        }
+        self.exec_value(request).await
+    }
 
-        let status = response.status();
-        let body = response.text().await?;
+    /// Sends a request without checking the groups of the user.
+    async fn exec_value(&self, request: Value) -> Result<Value> {
+        let body = async {
+            match self.api_flavor {
+                ApiFlavor::Legacy => {
+                    let call = legacy::SysbusCall::new(self.base_url(), &request)?;
+                    self.send(&call.url, "application/json", &call).await
+                }
+                _ => {
+                    self.send(&self.base_url_ws, APPLICATION_SAH_WS_CALL, &request)
+                        .await
+                }
+            }
+        }
+        .await;
+        let text = |field: &str| request[field].as_str().unwrap_or_default();
+        self.audit((text("service"), text("method")), &request, &body);
+        Ok(serde_json::from_str(&body?)?)
+    }
+
+    /// Posts a body to an arbitrary path of the Livebox within the session, for endpoints living
+    /// outside of `/ws` (ex: `/sysbus/NMC:getWANStatus` on older firmware). Mutations are checked
+    /// against the change group like the other calls.
+    pub async fn raw(&self, path: &str, body: &Value) -> Result<Value> {
+        let (service, method) = raw_call(path, body);
+        if let Some(group) = &self.change_group {
+            if audit::is_mutation(&method) {
+                self.check_group(group).await?;
+            }
+        }
+        let url = format!("{}/{}", self.base_url(), path.trim_start_matches('/'));
+        let response = self.send(&url, "application/json", body).await;
+        self.audit((&service, &method), body, &response);
+        let body = response?;
+        Ok(serde_json::from_str(&body).unwrap_or(Value::String(body)))
+    }
+
+    fn audit(&self, call: (&str, &str), request: &Value, response: &Result<String>) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(&self.username, call, request, response);
+        }
+    }
+
+    /// Sends a request within the session and returns the successful response body.
+    async fn send<R: Serialize>(
+        &self,
+        url: &str,
+        content_type: &str,
+        request: &R,
+    ) -> Result<String> {
+        debug!(">>> POST {url}\n{}", redacted(request)?);
+        let _permit = self.rate_limiter.acquire().await;
+        let (status, body) = timed(self.timings.as_deref(), "call", async {
+            let response = self
+                .post_to(url)
+                .header(CONTENT_TYPE, content_type)
+                .json(request)
+                .send()
+                .await?;
+            Ok((response.status(), response.text().await?))
+        })
+        .await?;
         debug!("<<< {status}\n{body}");
+        if users::is_permission_denied(status.as_u16(), &body) {
+            return Err(anyhow!(PermissionDenied).context(ResponseBody(body)));
+        }
         if !status.is_success() {
             return Err(
-                anyhow!("Execution failed: {status}").context(format!("Response body: {body}"))
+                anyhow!(Message::ExecutionFailed(&status).to_string()).context(ResponseBody(body))
             );
         }
 
-        Ok(serde_json::from_str(&body)?)
+        Ok(body)
+    }
+
+    async fn exec_status<R: Serialize, T: DeserializeOwned>(&self, request: R) -> Result<T> {
+        let response = self.exec(request).await?;
+        Ok(serde_json::from_value::<StatusResponse<T>>(response)?.status)
     }
 
     pub async fn execute(&self, service: String, method: String) -> Result<Value> {
@@ -191,30 +638,49 @@ impl Client {
             .await?)
     }
 
-    async fn structured_nat_rules(&self) -> Result<Vec<NatRuleView>> {
-        let raw_rules = self.list_nat_rules().await?;
-        let rules: Vec<NatRuleView> = raw_rules
-            .get("status")
-            .unwrap()
-            .as_object()
-            .unwrap()
-            .values()
-            .into_iter()
-            .map(|value| serde_json::to_string(value).unwrap())
-            .map(|str| serde_json::from_str(&str).unwrap())
-            .collect();
-        Ok(rules)
+    pub async fn structured_nat_rules(&self) -> Result<Vec<NatRuleView>> {
+        let rules: HashMap<String, NatRuleView> = self
+            .exec_status(SysbusRequest::Firewall(FirewallMethod::GetPortForwarding {
+                parameters: NoParameters {},
+            }))
+            .await?;
+        Ok(rules.into_values().collect())
+    }
+
+    pub async fn get_firewall_level(&self) -> Result<FirewallLevel> {
+        self.exec_status(SysbusRequest::Firewall(FirewallMethod::GetFirewallLevel {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn set_firewall_level(&self, level: FirewallLevel) -> Result<Value> {
+        let result = self
+            .exec(SysbusRequest::Firewall(FirewallMethod::SetFirewallLevel {
+                parameters: SetFirewallLevelParams { level },
+            }))
+            .await?;
+        self.commit_firewall().await?;
+        Ok(result)
+    }
+
+    /// Persists the firewall changes, so they survive a reboot
+    pub async fn commit_firewall(&self) -> Result<Value> {
+        self.exec(SysbusRequest::Firewall(FirewallMethod::Commit {
+            parameters: NoParameters {},
+        }))
+        .await
     }
 
     async fn update_nat_rule<F>(&self, rule_id: String, transform_rule: F) -> Result<Value>
     where
-        F: FnOnce(&mut SetPortFowardingParams) -> (),
+        F: FnOnce(&mut SetPortFowardingParams),
     {
         let actual_rules = self.structured_nat_rules().await?;
         let rule_to_edit = actual_rules
             .iter()
             .find(|rule| rule.id == rule_id)
-            .ok_or_else(|| anyhow!("No rule with id {rule_id}"))?;
+            .ok_or_else(|| anyhow!(Message::NoRuleWithId(&rule_id).to_string()))?;
         let mut parameters: SetPortFowardingParams = rule_to_edit.into();
         transform_rule(&mut parameters);
         let result = self
@@ -222,10 +688,7 @@ impl Client {
                 parameters,
             }))
             .await?;
-        self.exec(SysbusRequest::Firewall(FirewallMethod::Commit {
-            parameters: NoParameters {},
-        }))
-        .await?;
+        self.commit_firewall().await?;
         Ok(result)
     }
 
@@ -248,12 +711,23 @@ impl Client {
             .await
     }
 
+    pub async fn set_nat_rule_destination(
+        &self,
+        rule_id: String,
+        destination: String,
+    ) -> Result<Value> {
+        self.update_nat_rule(rule_id, |params| {
+            params.destination_ip_address = destination
+        })
+        .await
+    }
+
     pub async fn remove_nat_rule(&self, rule_id: String) -> Result<Value> {
         let actual_rules = self.structured_nat_rules().await?;
         let rule_to_delete = actual_rules
             .iter()
             .find(|rule| rule.id == rule_id)
-            .ok_or_else(|| anyhow!("No rule with id {rule_id}"))?;
+            .ok_or_else(|| anyhow!(Message::NoRuleWithId(&rule_id).to_string()))?;
         let result = self
             .exec(SysbusRequest::Firewall(
                 FirewallMethod::DeletePortForwarding {
@@ -261,33 +735,126 @@ impl Client {
                 },
             ))
             .await?;
-        self.exec(SysbusRequest::Firewall(FirewallMethod::Commit {
-            parameters: NoParameters {},
-        }))
-        .await?;
+        self.commit_firewall().await?;
         Ok(result)
     }
 }
 
 #[derive(Serialize)]
-pub(super) struct GenericRequest<'a> {
+pub struct GenericRequest<'a> {
     service: &'a str,
     method: &'a str,
     parameters: HashMap<&'a str, &'a str>,
 }
 
+/// Body of a failed response, given as context of the error
+#[derive(Debug)]
+pub struct ResponseBody(pub(super) String);
+
+impl fmt::Display for ResponseBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::ResponseBody(&self.0))
+    }
+}
+
+/// Serializes a secret into a request body, never use it for anything else.
+fn expose_secret<S: Serializer>(secret: &SecretString, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(secret.expose_secret())
+}
+
+/// Pretty prints a request for logs, masking secrets.
+fn redacted<R: Serialize>(request: &R) -> Result<String> {
+    let mut request = serde_json::to_value(request)?;
+    mask_secrets(&mut request);
+    Ok(serde_json::to_string_pretty(&request)?)
+}
+
+fn mask_secrets(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                if SECRET_FIELDS.contains(&name.as_str()) {
+                    *field = Value::String("********".to_string());
+                } else {
+                    mask_secrets(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_secrets),
+        _ => {}
+    }
+}
+
+/// Service and method of a raw call, from its `/sysbus/Service/Name:method` path or else from
+/// the `/ws` style body.
+fn raw_call(path: &str, body: &Value) -> (String, String) {
+    let sysbus = path.trim_start_matches('/').strip_prefix("sysbus/");
+    match sysbus.and_then(|call| call.split('?').next()?.rsplit_once(':')) {
+        Some((service, method)) => (service.replace('/', "."), method.to_string()),
+        None => {
+            let text = |field: &str| body[field].as_str().unwrap_or_default().to_string();
+            (text("service"), text("method"))
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct GenericResponse<S, D> {
+    #[allow(dead_code)]
     status: S,
     data: D,
 }
 
+#[derive(Deserialize)]
+struct StatusResponse<S> {
+    status: S,
+}
+
+/// Request targeting a single NeMo interface, whose service name is only known at runtime
+#[derive(Serialize)]
+struct InterfaceRequest<M> {
+    service: String,
+    #[serde(flatten)]
+    method: M,
+}
+
+impl<M> InterfaceRequest<M> {
+    fn new(interface: &str, method: M) -> Self {
+        Self {
+            service: format!("NeMo.Intf.{interface}"),
+            method,
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(tag = "service")]
 enum SysbusRequest {
     #[serde(rename = "sah.Device.Information")]
     SahDeviceInformation(SahMethod),
     Firewall(FirewallMethod),
+    #[serde(rename = "NMC.WWAN")]
+    Wwan(wan::WwanMethod),
+    #[serde(rename = "NMC.Wifi")]
+    Wifi(wifi::WifiMethod),
+    #[serde(rename = "NeMo.Intf.lan")]
+    LanInterface(wifi::InterfaceMethod),
+    #[serde(rename = "DHCPv4.Server.Pool.default")]
+    DhcpPool(dhcp::DhcpPoolMethod),
+    DynDNS(ddns::DynDnsMethod),
+    Devices(devices::DevicesMethod),
+    #[serde(rename = "IGMPProxy")]
+    IgmpProxy(multicast::MulticastMethod),
+    #[serde(rename = "IGMPSnooping")]
+    IgmpSnooping(multicast::MulticastMethod),
+    #[serde(rename = "NMC")]
+    Nmc(system::NmcMethod),
+    #[serde(rename = "VoiceService.VoiceApplication")]
+    VoiceApplication(system::VoiceApplicationMethod),
+    #[serde(rename = "IPPingDiagnostics")]
+    IpPingDiagnostics(diagnostics::DiagnosticsMethod),
+    TraceRouteDiagnostics(diagnostics::DiagnosticsMethod),
+    UserManagement(users::UserManagementMethod),
 }
 
 #[derive(Serialize)]
@@ -304,7 +871,8 @@ struct LoginParameters {
     #[serde(rename = "applicationName")]
     application_name: String,
     username: String,
-    password: String,
+    #[serde(serialize_with = "expose_secret")]
+    password: SecretString,
 }
 
 type LoginResponse = GenericResponse<i64, LoginContext>;
@@ -331,7 +899,10 @@ struct LogoutResponse {
 struct LoginContext {
     #[serde(rename = "contextID")]
     context_id: String,
+    /// Missing from legacy firmware responses
+    #[serde(default)]
     groups: String,
+    #[serde(default)]
     username: String,
 }
 
@@ -346,10 +917,37 @@ enum FirewallMethod {
     DeletePortForwarding {
         parameters: DeletePortForwardingParams,
     },
+    #[serde(rename = "getDMZ")]
+    GetDmz { parameters: NoParameters },
+    #[serde(rename = "setDMZ")]
+    SetDmz { parameters: dmz::SetDmzParams },
+    #[serde(rename = "deleteDMZ")]
+    DeleteDmz { parameters: dmz::DeleteDmzParams },
+    #[serde(rename = "getFirewallLevel")]
+    GetFirewallLevel { parameters: NoParameters },
+    #[serde(rename = "setFirewallLevel")]
+    SetFirewallLevel { parameters: SetFirewallLevelParams },
+    #[serde(rename = "getALGs")]
+    GetAlgs { parameters: NoParameters },
+    #[serde(rename = "setALG")]
+    SetAlg { parameters: alg::SetAlgParams },
     #[serde(rename = "commit")]
     Commit { parameters: NoParameters },
 }
 
+#[derive(Serialize)]
+struct SetFirewallLevelParams {
+    level: FirewallLevel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, JsonSchema)]
+pub enum FirewallLevel {
+    Low,
+    Medium,
+    High,
+    Custom,
+}
+
 #[derive(Serialize)]
 struct NoParameters {}
 
@@ -395,13 +993,18 @@ impl SetPortFowardingParams {
         SetPortFowardingParams {
             id,
             description,
-            protocol: protocol,
+            protocol,
             external_port,
             internal_port,
             destination_ip_address,
             ..Default::default()
         }
     }
+
+    pub fn with_enable(mut self, enable: bool) -> Self {
+        self.enable = enable;
+        self
+    }
 }
 
 impl Default for SetPortFowardingParams {
@@ -411,7 +1014,7 @@ impl Default for SetPortFowardingParams {
             origin: "webui".to_string(),
             description: Default::default(),
             source_interface: "data".to_string(),
-            protocol: Protocol::TCP,
+            protocol: Protocol::Tcp,
             external_port: Default::default(),
             internal_port: Default::default(),
             destination_ip_address: Default::default(),
@@ -459,26 +1062,27 @@ impl From<&NatRuleView> for DeletePortForwardingParams {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub enum Protocol {
     #[serde(rename = "6")]
-    TCP,
+    Tcp,
     #[serde(rename = "17")]
-    UDP,
+    Udp,
     #[serde(rename = "6,17")]
-    ALL,
+    All,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[allow(dead_code)]
 pub struct NatRuleView {
     #[serde(rename = "Id")]
-    id: String,
+    pub id: String,
 
     #[serde(rename = "Origin")]
-    origin: String,
+    pub origin: String,
 
     #[serde(rename = "Description")]
-    description: String,
+    pub description: String,
 
     #[serde(rename = "Status")]
     status: RuleStatus,
@@ -487,19 +1091,19 @@ pub struct NatRuleView {
     source_interface: String,
 
     #[serde(rename = "Protocol")]
-    protocol: Protocol,
+    pub protocol: Protocol,
 
     #[serde(rename = "ExternalPort")]
-    external_port: String,
+    pub external_port: String,
 
     #[serde(rename = "InternalPort")]
-    internal_port: String,
+    pub internal_port: String,
 
     #[serde(rename = "SourcePrefix")]
-    source_prefix: String,
+    pub source_prefix: String,
 
     #[serde(rename = "DestinationIPAddress")]
-    destination_ip_address: String,
+    pub destination_ip_address: String,
 
     #[serde(rename = "DestinationMACAddress")]
     destination_mac_address: String,
@@ -517,11 +1121,11 @@ pub struct NatRuleView {
     upnp_v1_compat: bool,
 
     #[serde(rename = "Enable")]
-    enable: bool,
+    pub enable: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 enum RuleStatus {
     Enabled,
     Disabled,
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Client, FirewallMethod, NoParameters, SysbusRequest};
+
+impl Client {
+    /// Application Level Gateways by name
+    pub async fn list_algs(&self) -> Result<BTreeMap<String, AlgView>> {
+        self.exec_status(SysbusRequest::Firewall(FirewallMethod::GetAlgs {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn set_alg_enable(&self, alg: Alg, enable: bool) -> Result<Value> {
+        let result = self
+            .exec(SysbusRequest::Firewall(FirewallMethod::SetAlg {
+                parameters: SetAlgParams { alg, enable },
+            }))
+            .await?;
+        self.commit_firewall().await?;
+        Ok(result)
+    }
+}
+
+/// Application Level Gateway rewriting the addresses embedded in a protocol payload
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Alg {
+    #[serde(rename = "SIP")]
+    Sip,
+    #[serde(rename = "PPTP")]
+    Pptp,
+    #[serde(rename = "IPSec")]
+    Ipsec,
+    #[serde(rename = "RTSP")]
+    Rtsp,
+}
+
+impl Alg {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Alg::Sip => "SIP",
+            Alg::Pptp => "PPTP",
+            Alg::Ipsec => "IPSec",
+            Alg::Rtsp => "RTSP",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct SetAlgParams {
+    alg: Alg,
+    enable: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct AlgView {
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+}
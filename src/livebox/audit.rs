@@ -0,0 +1,140 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::Message;
+
+/// Append-only JSON lines log of the requests changing the Livebox configuration
+#[derive(Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    /// Space separated path of the invoked subcommand
+    command: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Local user running livebox-cli
+    pub user: String,
+    /// Livebox user of the session
+    pub livebox_user: String,
+    pub command: String,
+    pub service: String,
+    pub method: String,
+    /// SHA-256 of the request, parameters included
+    pub payload_sha256: String,
+    /// `ok` or `failed`
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether the method may change the configuration, only reads and diagnostics are not audited
+pub(super) fn is_mutation(method: &str) -> bool {
+    !(method.starts_with("get")
+        || method.starts_with("list")
+        || matches!(
+            method,
+            "startScan" | "execDiagnostic" | "createContext" | "releaseContext"
+        ))
+}
+
+/// `errors` array of a sysbus reply, when there are some
+fn sysbus_errors(body: &str) -> Option<String> {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|reply| match &reply["errors"] {
+            Value::Array(errors) if !errors.is_empty() => Some(reply["errors"].to_string()),
+            _ => None,
+        })
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf, command: String) -> Self {
+        Self { path, command }
+    }
+
+    /// Appends the request when it's a mutation, failures to write are logged. Replies holding
+    /// sysbus `errors` are recorded as failed.
+    pub(super) fn record(
+        &self,
+        livebox_user: &str,
+        (service, method): (&str, &str),
+        request: &Value,
+        response: &Result<String>,
+    ) {
+        if !is_mutation(method) {
+            return;
+        }
+        let error = match response {
+            Ok(body) => sysbus_errors(body),
+            Err(err) => Some(format!("{err:#}")),
+        };
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            user: env::var("USER")
+                .or_else(|_| env::var("LOGNAME"))
+                .unwrap_or_default(),
+            livebox_user: livebox_user.to_string(),
+            command: self.command.clone(),
+            service: service.to_string(),
+            method: method.to_string(),
+            payload_sha256: Sha256::digest(request.to_string())
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            status: if error.is_some() { "failed" } else { "ok" }.to_string(),
+            error,
+        };
+        if let Err(err) = self.append(&entry) {
+            warn!("Unable to write the audit log {:?}: {err:#}", self.path);
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Entries of the log, oldest first
+    pub fn load(path: &Path) -> Result<Vec<AuditEntry>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).with_context(|| Message::UnableToRead(path).to_string()),
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .with_context(|| Message::InvalidAuditEntry(line).to_string())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,65 @@
+//! Blocking facade of the Livebox client, for tools without an async runtime.
+//!
+//! ```no_run
+//! use livebox_cli::livebox::ClientBuilder;
+//!
+//! let client = ClientBuilder::default()
+//!     .with_credentials("admin".to_string(), "secret".to_string().into())
+//!     .build_blocking()?;
+//! let status = client.execute("NMC", "getWANStatus")?;
+//! let devices = client.run(|client| client.list_devices())?;
+//! client.logout()?;
+//! # anyhow::Ok(())
+//! ```
+
+use std::{collections::HashMap, future::Future};
+
+use anyhow::Result;
+use serde_json::Value;
+use tokio::runtime::{Builder, Runtime};
+
+use super::ClientBuilder;
+
+/// A logged in client whose calls block the current thread, each one being run to completion
+/// by a single-threaded runtime
+pub struct Client {
+    runtime: Runtime,
+    client: super::Client,
+}
+
+impl ClientBuilder {
+    /// Logs in and returns a blocking client, must not be called from an async context.
+    pub fn build_blocking(self) -> Result<Client> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let client = runtime.block_on(self.build())?;
+        Ok(Client { runtime, client })
+    }
+}
+
+impl Client {
+    /// Runs a call of the async client (ex: `client.run(|client| client.get_wan_status())`).
+    pub fn run<'a, T, F>(&'a self, call: impl FnOnce(&'a super::Client) -> F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.runtime.block_on(call(&self.client))
+    }
+
+    pub fn execute(&self, service: &str, method: &str) -> Result<Value> {
+        self.run(|client| client.execute(service.to_string(), method.to_string()))
+    }
+
+    pub fn execute_with_parameters(
+        &self,
+        service: &str,
+        method: &str,
+        parameters: HashMap<&str, &str>,
+    ) -> Result<Value> {
+        self.run(|client| client.execute_with_parameters(service, method, parameters))
+    }
+
+    /// Releases the session.
+    pub fn logout(self) -> Result<()> {
+        self.runtime.block_on(self.client.logout())
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use log::debug;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::ApiFlavor;
+
+/// Remaps requests for rebranded or customized firmware whose services and parameters differ
+/// from the Livebox ones
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// API generation of the firmware
+    pub api_flavor: Option<ApiFlavor>,
+    /// Service names, keyed by the Livebox name (ex: `"NMC.Wifi" = "NMC.WLAN"`)
+    #[serde(default)]
+    pub services: HashMap<String, String>,
+    /// Method names, keyed by the Livebox `service.method` (ex: `"NMC.getWANStatus" = "getWanStatus"`)
+    #[serde(default)]
+    pub methods: HashMap<String, String>,
+    /// Parameter names, keyed by the Livebox `service.method` then parameter name
+    #[serde(default)]
+    pub parameters: HashMap<String, HashMap<String, String>>,
+}
+
+impl Profile {
+    fn is_empty(&self) -> bool {
+        self.services.is_empty() && self.methods.is_empty() && self.parameters.is_empty()
+    }
+
+    /// Rewrites a `{service, method, parameters}` request for the firmware.
+    pub fn apply(&self, mut request: Value) -> Value {
+        if self.is_empty() {
+            return request;
+        }
+        let (Some(service), Some(method)) = (
+            request["service"].as_str().map(str::to_string),
+            request["method"].as_str().map(str::to_string),
+        ) else {
+            return request;
+        };
+        let key = format!("{service}.{method}");
+        if let (Some(renames), Some(parameters)) = (
+            self.parameters.get(&key),
+            request.get_mut("parameters").and_then(Value::as_object_mut),
+        ) {
+            for (name, renamed) in renames {
+                if let Some(value) = parameters.remove(name) {
+                    parameters.insert(renamed.clone(), value);
+                }
+            }
+        }
+        if let Some(renamed) = self.methods.get(&key) {
+            request["method"] = Value::String(renamed.clone());
+        }
+        if let Some(renamed) = self.services.get(&service) {
+            request["service"] = Value::String(renamed.clone());
+        }
+        debug!(
+            "{key} remapped to {}.{}",
+            request["service"], request["method"]
+        );
+        request
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{expose_secret, Client, NoParameters, SysbusRequest};
+
+impl Client {
+    pub async fn list_ddns_hosts(&self) -> Result<Vec<DynDnsHostView>> {
+        self.exec_status(SysbusRequest::DynDNS(DynDnsMethod::GetHosts {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn add_ddns_host(
+        &self,
+        service: String,
+        hostname: String,
+        username: String,
+        password: SecretString,
+    ) -> Result<Value> {
+        self.exec(SysbusRequest::DynDNS(DynDnsMethod::AddHost {
+            parameters: AddHostParams {
+                service,
+                hostname,
+                username,
+                password,
+            },
+        }))
+        .await
+    }
+
+    pub async fn remove_ddns_host(&self, hostname: String) -> Result<Value> {
+        self.exec(SysbusRequest::DynDNS(DynDnsMethod::DelHost {
+            parameters: DelHostParams { hostname },
+        }))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum DynDnsMethod {
+    #[serde(rename = "getHosts")]
+    GetHosts { parameters: NoParameters },
+    #[serde(rename = "addHost")]
+    AddHost { parameters: AddHostParams },
+    #[serde(rename = "delHost")]
+    DelHost { parameters: DelHostParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct AddHostParams {
+    service: String,
+    hostname: String,
+    username: String,
+    #[serde(serialize_with = "expose_secret")]
+    password: SecretString,
+}
+
+#[derive(Serialize)]
+pub(super) struct DelHostParams {
+    hostname: String,
+}
+
+/// The box never sends back DynDNS passwords.
+#[derive(Deserialize, Serialize)]
+pub struct DynDnsHostView {
+    pub service: String,
+    pub hostname: String,
+    pub username: String,
+}
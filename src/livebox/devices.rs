@@ -0,0 +1,53 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Client, SysbusRequest};
+
+impl Client {
+    /// Lists devices known by the Livebox on the LAN side.
+    pub async fn list_devices(&self) -> Result<Vec<DeviceView>> {
+        self.exec_status(SysbusRequest::Devices(DevicesMethod::Get {
+            parameters: GetDevicesParams {
+                expression: "lan and not self".to_string(),
+            },
+        }))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum DevicesMethod {
+    #[serde(rename = "get")]
+    Get { parameters: GetDevicesParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct GetDevicesParams {
+    expression: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DeviceView {
+    #[serde(rename = "Key")]
+    pub key: String,
+
+    #[serde(rename = "Name")]
+    pub name: String,
+
+    #[serde(rename = "PhysAddress", default)]
+    pub phys_address: String,
+
+    #[serde(rename = "IPAddress", default)]
+    pub ip_address: String,
+
+    #[serde(rename = "Active", default)]
+    pub active: bool,
+}
+
+impl DeviceView {
+    pub fn has_mac_address(&self, mac_address: &str) -> bool {
+        self.phys_address.eq_ignore_ascii_case(mac_address)
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{system::NmcMethod, Client, GenericResponse, NoParameters, SysbusRequest};
+
+impl Client {
+    pub async fn list_static_leases(&self) -> Result<Vec<StaticLeaseView>> {
+        self.exec_status(SysbusRequest::DhcpPool(DhcpPoolMethod::GetStaticLeases {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn add_static_lease(&self, mac_address: String, ip_address: String) -> Result<Value> {
+        self.exec(SysbusRequest::DhcpPool(DhcpPoolMethod::AddStaticLease {
+            parameters: AddStaticLeaseParams {
+                mac_address,
+                ip_address,
+            },
+        }))
+        .await
+    }
+
+    /// LAN address of the Livebox and range of addresses leased by its DHCP server
+    pub async fn get_lan_ip(&self) -> Result<LanIpView> {
+        let response = self
+            .exec(SysbusRequest::Nmc(NmcMethod::GetLanIp {
+                parameters: NoParameters {},
+            }))
+            .await?;
+        Ok(serde_json::from_value::<GenericResponse<bool, LanIpView>>(response)?.data)
+    }
+
+    pub async fn set_dhcp_range(&self, min_address: String, max_address: String) -> Result<Value> {
+        let lan_ip = LanIpView {
+            dhcp_min_address: min_address,
+            dhcp_max_address: max_address,
+            ..self.get_lan_ip().await?
+        };
+        self.exec(SysbusRequest::Nmc(NmcMethod::SetLanIp {
+            parameters: lan_ip,
+        }))
+        .await
+    }
+
+    /// Options sent by the DHCP server, firmware without custom options support reject the call
+    pub async fn list_dhcp_options(&self) -> Result<Vec<DhcpOptionView>> {
+        self.exec_status(SysbusRequest::DhcpPool(DhcpPoolMethod::GetOptions {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    /// Sends the option with the given hexBinary value, replacing any previous value.
+    pub async fn set_dhcp_option(&self, tag: u8, value: String) -> Result<Value> {
+        self.exec(SysbusRequest::DhcpPool(DhcpPoolMethod::SetOption {
+            parameters: SetOptionParams {
+                tag,
+                value,
+                enable: true,
+            },
+        }))
+        .await
+    }
+
+    pub async fn remove_dhcp_option(&self, tag: u8) -> Result<Value> {
+        self.exec(SysbusRequest::DhcpPool(DhcpPoolMethod::RemoveOption {
+            parameters: RemoveOptionParams { tag },
+        }))
+        .await
+    }
+
+    pub async fn remove_static_lease(&self, mac_address: String) -> Result<Value> {
+        self.exec(SysbusRequest::DhcpPool(DhcpPoolMethod::DeleteStaticLease {
+            parameters: DeleteStaticLeaseParams { mac_address },
+        }))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum DhcpPoolMethod {
+    #[serde(rename = "getStaticLeases")]
+    GetStaticLeases { parameters: NoParameters },
+    #[serde(rename = "addStaticLease")]
+    AddStaticLease { parameters: AddStaticLeaseParams },
+    #[serde(rename = "deleteStaticLease")]
+    DeleteStaticLease { parameters: DeleteStaticLeaseParams },
+    #[serde(rename = "getOptions")]
+    GetOptions { parameters: NoParameters },
+    #[serde(rename = "setOption")]
+    SetOption { parameters: SetOptionParams },
+    #[serde(rename = "removeOption")]
+    RemoveOption { parameters: RemoveOptionParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct SetOptionParams {
+    #[serde(rename = "Tag")]
+    tag: u8,
+
+    #[serde(rename = "Value")]
+    value: String,
+
+    #[serde(rename = "Enable")]
+    enable: bool,
+}
+
+#[derive(Serialize)]
+pub(super) struct RemoveOptionParams {
+    #[serde(rename = "Tag")]
+    tag: u8,
+}
+
+#[derive(Serialize)]
+pub(super) struct AddStaticLeaseParams {
+    #[serde(rename = "MACAddress")]
+    mac_address: String,
+
+    #[serde(rename = "IPAddress")]
+    ip_address: String,
+}
+
+#[derive(Serialize)]
+pub(super) struct DeleteStaticLeaseParams {
+    #[serde(rename = "MACAddress")]
+    mac_address: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StaticLeaseView {
+    #[serde(rename = "MACAddress")]
+    pub mac_address: String,
+
+    #[serde(rename = "IPAddress")]
+    pub ip_address: String,
+}
+
+/// DHCP option, the value is hexBinary encoded
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct DhcpOptionView {
+    #[serde(rename = "Tag")]
+    pub tag: u8,
+
+    #[serde(rename = "Value")]
+    pub value: String,
+
+    #[serde(rename = "Enable", default)]
+    pub enable: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LanIpView {
+    #[serde(rename = "Address")]
+    pub address: String,
+
+    #[serde(rename = "Netmask")]
+    pub netmask: String,
+
+    #[serde(rename = "DHCPEnable")]
+    pub dhcp_enable: bool,
+
+    #[serde(rename = "DHCPMinAddress")]
+    pub dhcp_min_address: String,
+
+    #[serde(rename = "DHCPMaxAddress")]
+    pub dhcp_max_address: String,
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{Client, SysbusRequest};
+
+impl Client {
+    /// Pings a host from the Livebox itself.
+    pub async fn ping(&self, host: String, count: u32, timeout_ms: u32) -> Result<Value> {
+        self.exec(SysbusRequest::IpPingDiagnostics(
+            DiagnosticsMethod::ExecDiagnostic {
+                parameters: DiagnosticParams::Ping {
+                    host,
+                    number_of_repetitions: count,
+                    timeout: timeout_ms,
+                },
+            },
+        ))
+        .await
+    }
+
+    /// Traces the route to a host from the Livebox itself.
+    pub async fn traceroute(
+        &self,
+        host: String,
+        max_hop_count: u32,
+        timeout_ms: u32,
+    ) -> Result<Value> {
+        self.exec(SysbusRequest::TraceRouteDiagnostics(
+            DiagnosticsMethod::ExecDiagnostic {
+                parameters: DiagnosticParams::TraceRoute {
+                    host,
+                    max_hop_count,
+                    timeout: timeout_ms,
+                },
+            },
+        ))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum DiagnosticsMethod {
+    #[serde(rename = "execDiagnostic")]
+    ExecDiagnostic { parameters: DiagnosticParams },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(super) enum DiagnosticParams {
+    Ping {
+        #[serde(rename = "Host")]
+        host: String,
+        #[serde(rename = "NumberOfRepetitions")]
+        number_of_repetitions: u32,
+        #[serde(rename = "Timeout")]
+        timeout: u32,
+    },
+    TraceRoute {
+        #[serde(rename = "Host")]
+        host: String,
+        #[serde(rename = "MaxHopCount")]
+        max_hop_count: u32,
+        #[serde(rename = "Timeout")]
+        timeout: u32,
+    },
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Client, FirewallMethod, NoParameters, SysbusRequest};
+
+const DMZ_ID: &str = "webui";
+
+impl Client {
+    pub async fn get_dmz(&self) -> Result<Option<DmzView>> {
+        let mut dmz: HashMap<String, DmzView> = self
+            .exec_status(SysbusRequest::Firewall(FirewallMethod::GetDmz {
+                parameters: NoParameters {},
+            }))
+            .await?;
+        Ok(dmz.remove(DMZ_ID))
+    }
+
+    pub async fn set_dmz(&self, destination_ip_address: String) -> Result<Value> {
+        let result = self
+            .exec(SysbusRequest::Firewall(FirewallMethod::SetDmz {
+                parameters: SetDmzParams {
+                    id: DMZ_ID.to_string(),
+                    source_interface: "data".to_string(),
+                    destination_ip_address,
+                    enable: true,
+                },
+            }))
+            .await?;
+        self.commit_firewall().await?;
+        Ok(result)
+    }
+
+    pub async fn remove_dmz(&self) -> Result<Value> {
+        let result = self
+            .exec(SysbusRequest::Firewall(FirewallMethod::DeleteDmz {
+                parameters: DeleteDmzParams {
+                    id: DMZ_ID.to_string(),
+                },
+            }))
+            .await?;
+        self.commit_firewall().await?;
+        Ok(result)
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct SetDmzParams {
+    id: String,
+
+    #[serde(rename = "sourceInterface")]
+    source_interface: String,
+
+    #[serde(rename = "destinationIPAddress")]
+    destination_ip_address: String,
+
+    enable: bool,
+}
+
+#[derive(Serialize)]
+pub(super) struct DeleteDmzParams {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct DmzView {
+    #[serde(rename = "DestinationIPAddress")]
+    pub destination_ip_address: String,
+
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+}
@@ -0,0 +1,116 @@
+//! Livebox 2/3 firmware, authenticating with `/authenticate` and exposing path-style
+//! `/sysbus/Service/Name:method` calls instead of `/ws`.
+
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use reqwest::Client as ReqwestClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::i18n::Message;
+
+use super::{users, InvalidCredentials, LoginContext, ResponseBody};
+
+/// API generation spoken by the Livebox
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiFlavor {
+    /// `/ws` API, falling back to the legacy one when the Livebox doesn't know it
+    #[default]
+    Auto,
+    /// `/ws` API of Livebox 4 and later
+    Ws,
+    /// Path-style API of Livebox 2/3
+    Legacy,
+}
+
+/// Login rejected because the Livebox doesn't expose the `/ws` API
+#[derive(Debug)]
+pub struct UnsupportedApi;
+
+impl fmt::Display for UnsupportedApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::UnsupportedFirmware)
+    }
+}
+
+impl std::error::Error for UnsupportedApi {}
+
+pub(super) fn is_unsupported_api(status: u16) -> bool {
+    status == 404 || status == 405
+}
+
+#[derive(Deserialize)]
+struct AuthenticateResponse {
+    data: LoginContext,
+}
+
+/// Opens a cookie based session.
+pub(super) async fn authenticate(
+    http_client: &ReqwestClient,
+    base_url: &str,
+    username: &str,
+    password: &SecretString,
+) -> Result<LoginContext> {
+    let response = http_client
+        .post(format!("{base_url}/authenticate"))
+        .query(&[
+            ("username", username),
+            ("password", password.expose_secret()),
+        ])
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if users::is_invalid_credentials(status.as_u16()) {
+        return Err(anyhow!(InvalidCredentials).context(ResponseBody(body)));
+    }
+    if !status.is_success() {
+        return Err(
+            anyhow!(Message::AuthenticationFailed(&status).to_string()).context(ResponseBody(body))
+        );
+    }
+    let mut context = serde_json::from_str::<AuthenticateResponse>(&body)
+        .with_context(|| ResponseBody(body.clone()))?
+        .data;
+    if context.username.is_empty() {
+        context.username = username.to_string();
+    }
+    Ok(context)
+}
+
+pub(super) fn logout_url(base_url: &str) -> String {
+    format!("{base_url}/logout")
+}
+
+/// Path-style call equivalent to a `/ws` request
+#[derive(Serialize)]
+pub(super) struct SysbusCall {
+    #[serde(skip)]
+    pub(super) url: String,
+    parameters: Value,
+}
+
+impl SysbusCall {
+    pub(super) fn new<R: Serialize>(base_url: &str, request: &R) -> Result<Self> {
+        let mut request = serde_json::to_value(request)?;
+        let field = |request: &mut Value, name: &str| match request.get_mut(name).map(Value::take) {
+            Some(Value::String(value)) => Ok(value),
+            _ => Err(anyhow!(
+                Message::NoRequestField(name, &request.to_string()).to_string()
+            )),
+        };
+        let service = field(&mut request, "service")?;
+        let method = field(&mut request, "method")?;
+        let parameters = request
+            .get_mut("parameters")
+            .map(Value::take)
+            .unwrap_or_else(|| json!({}));
+        let url = format!("{base_url}/sysbus/{}:{method}", service.replace('.', "/"));
+        debug!("{service}.{method} translated to {url}");
+        Ok(Self { url, parameters })
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::{
+    ddns::DynDnsHostView, devices::DeviceView, dhcp::StaticLeaseView, system::VoipTrunkView,
+    system::WanStatus, wifi::WifiStatus, GenericResponse, NatRuleView, StatusResponse,
+};
+
+/// Typed model a raw sysbus response can be mapped to
+#[derive(Debug)]
+pub struct Model {
+    /// Name given to `exec --model`
+    pub name: &'static str,
+    /// Method whose response matches the model
+    pub method: &'static str,
+    normalize: fn(Value) -> Result<Value>,
+}
+
+impl Model {
+    /// Validates the raw response of `service.method` against the model and keeps only the
+    /// known fields.
+    pub fn normalize(&self, service: &str, method: &str, response: Value) -> Result<Value> {
+        if format!("{service}.{method}") != self.method {
+            warn!("{} is the response model of {}", self.name, self.method);
+        }
+        (self.normalize)(response)
+    }
+}
+
+/// Known response models
+pub const MODELS: &[Model] = &[
+    Model {
+        name: "WANStatus",
+        method: "NMC.getWANStatus",
+        normalize: from_data::<WanStatus>,
+    },
+    Model {
+        name: "DeviceList",
+        method: "Devices.get",
+        normalize: from_status::<Vec<DeviceView>>,
+    },
+    Model {
+        name: "PortForwarding",
+        method: "Firewall.getPortForwarding",
+        normalize: from_status::<HashMap<String, NatRuleView>>,
+    },
+    Model {
+        name: "StaticLeases",
+        method: "DHCPv4.Server.Pool.default.getStaticLeases",
+        normalize: from_status::<Vec<StaticLeaseView>>,
+    },
+    Model {
+        name: "DynDNSHosts",
+        method: "DynDNS.getHosts",
+        normalize: from_status::<Vec<DynDnsHostView>>,
+    },
+    Model {
+        name: "WifiStatus",
+        method: "NMC.Wifi.get",
+        normalize: from_status::<WifiStatus>,
+    },
+    Model {
+        name: "VoIPTrunks",
+        method: "VoiceService.VoiceApplication.listTrunks",
+        normalize: from_status::<Vec<VoipTrunkView>>,
+    },
+];
+
+/// Looks up a model by name, ignoring case.
+pub fn find_model(name: &str) -> Option<&'static Model> {
+    MODELS
+        .iter()
+        .find(|model| model.name.eq_ignore_ascii_case(name))
+}
+
+fn from_status<T: DeserializeOwned + Serialize>(response: Value) -> Result<Value> {
+    let status = serde_json::from_value::<StatusResponse<T>>(response)?.status;
+    Ok(serde_json::to_value(status)?)
+}
+
+fn from_data<T: DeserializeOwned + Serialize>(response: Value) -> Result<Value> {
+    let data = serde_json::from_value::<GenericResponse<bool, T>>(response)?.data;
+    Ok(serde_json::to_value(data)?)
+}
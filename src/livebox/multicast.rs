@@ -0,0 +1,72 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Client, NoParameters, SysbusRequest};
+
+impl Client {
+    /// State of the IGMP proxy forwarding multicast groups from the WAN, and of the IGMP snooping
+    /// restricting multicast traffic to the LAN ports and WiFi stations that joined the groups
+    pub async fn get_multicast_status(&self) -> Result<MulticastStatus> {
+        Ok(MulticastStatus {
+            proxy: self
+                .exec_status(SysbusRequest::IgmpProxy(MulticastMethod::Get {
+                    parameters: NoParameters {},
+                }))
+                .await?,
+            snooping: self
+                .exec_status(SysbusRequest::IgmpSnooping(MulticastMethod::Get {
+                    parameters: NoParameters {},
+                }))
+                .await?,
+        })
+    }
+
+    pub async fn set_igmp_snooping(&self, enable: bool) -> Result<Value> {
+        self.exec(SysbusRequest::IgmpSnooping(MulticastMethod::Set {
+            parameters: SetParams {
+                parameters: MulticastConfig { enable },
+            },
+        }))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum MulticastMethod {
+    #[serde(rename = "get")]
+    Get { parameters: NoParameters },
+    #[serde(rename = "set")]
+    Set { parameters: SetParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct SetParams {
+    parameters: MulticastConfig,
+}
+
+#[derive(Serialize)]
+struct MulticastConfig {
+    #[serde(rename = "Enable")]
+    enable: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MulticastStatus {
+    #[serde(rename = "Proxy")]
+    pub proxy: MulticastServiceView,
+
+    #[serde(rename = "Snooping")]
+    pub snooping: MulticastServiceView,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct MulticastServiceView {
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    wifi::{GetMibsParams, InterfaceMethod},
+    Client, SysbusRequest,
+};
+
+impl Client {
+    /// Dumps the ARP and IPv6 neighbour tables, resolving entries against the device list.
+    pub async fn list_neighbours(&self) -> Result<Vec<Neighbour>> {
+        let mibs: NeighMibs = self
+            .exec_status(SysbusRequest::LanInterface(InterfaceMethod::GetMibs {
+                parameters: GetMibsParams {
+                    mibs: "neigh".to_string(),
+                    traverse: Some("down".to_string()),
+                },
+            }))
+            .await?;
+        let devices = self.list_devices().await?;
+        let mut neighbours: Vec<Neighbour> = mibs
+            .neigh
+            .into_iter()
+            .flat_map(|(interface, entries)| {
+                entries
+                    .into_values()
+                    .map(move |entry| (interface.clone(), entry))
+            })
+            .map(|(interface, entry)| Neighbour {
+                name: devices
+                    .iter()
+                    .find(|device| device.has_mac_address(&entry.ll_address))
+                    .map(|device| device.name.clone()),
+                interface,
+                entry,
+            })
+            .collect();
+        neighbours.sort_by(|a, b| (&a.interface, &a.entry.dst).cmp(&(&b.interface, &b.entry.dst)));
+        Ok(neighbours)
+    }
+}
+
+#[derive(Deserialize)]
+struct NeighMibs {
+    neigh: HashMap<String, HashMap<String, NeighbourView>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct NeighbourView {
+    #[serde(rename = "Dst")]
+    pub dst: String,
+
+    #[serde(rename = "LLAddress")]
+    pub ll_address: String,
+
+    #[serde(rename = "State")]
+    pub state: String,
+
+    #[serde(rename = "Family")]
+    pub family: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Neighbour {
+    #[serde(rename = "Interface")]
+    pub interface: String,
+
+    /// Name of the matching device, unknown devices may be rogue ones
+    #[serde(rename = "Name")]
+    pub name: Option<String>,
+
+    #[serde(flatten)]
+    pub entry: NeighbourView,
+}
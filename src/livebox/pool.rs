@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use reqwest::ClientBuilder as ReqwestClientBuilder;
+
+/// Connection reuse settings, long running commands keep a single connection to the Livebox alive
+#[derive(Clone, Debug)]
+pub struct Pool {
+    /// Delay after which an unused connection is closed, never when unset
+    pub idle_timeout: Option<Duration>,
+    /// Maximum number of unused connections kept open
+    pub max_idle: usize,
+    /// Interval of TCP keep-alive probes, disabled when unset
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(Duration::from_secs(90)),
+            max_idle: 1,
+            tcp_keepalive: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl Pool {
+    pub(super) fn configure(&self, builder: ReqwestClientBuilder) -> ReqwestClientBuilder {
+        builder
+            .pool_idle_timeout(self.idle_timeout)
+            .pool_max_idle_per_host(self.max_idle)
+            .tcp_keepalive(self.tcp_keepalive)
+            .http2_keep_alive_interval(self.tcp_keepalive)
+            .http2_keep_alive_while_idle(true)
+    }
+}
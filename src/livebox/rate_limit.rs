@@ -0,0 +1,57 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    num::NonZeroUsize,
+    time::Duration,
+};
+
+use tokio::{
+    sync::{Mutex, Semaphore, SemaphorePermit},
+    time::{sleep_until, Instant},
+};
+
+/// Politeness settings protecting the Livebox weak CPU from bursts of requests
+#[derive(Clone, Debug, Default)]
+pub struct RateLimit {
+    /// Minimum delay between the start of two requests
+    pub min_interval: Duration,
+    /// Maximum number of requests in flight, unlimited when unset
+    pub max_concurrent: Option<NonZeroUsize>,
+    /// Random delay added to the minimum interval so that several instances don't poll in lockstep
+    pub jitter: Duration,
+}
+
+pub(super) struct RateLimiter {
+    settings: RateLimit,
+    semaphore: Option<Semaphore>,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(settings: RateLimit) -> Self {
+        Self {
+            semaphore: settings.max_concurrent.map(|max| Semaphore::new(max.get())),
+            next_slot: Mutex::new(Instant::now()),
+            settings,
+        }
+    }
+
+    /// Waits for a request slot, the returned permit must be held until the response is received.
+    pub(super) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore never closed")),
+            None => None,
+        };
+        let mut next_slot = self.next_slot.lock().await;
+        sleep_until(*next_slot).await;
+        *next_slot = Instant::now() + self.settings.min_interval + self.jitter();
+        permit
+    }
+
+    fn jitter(&self) -> Duration {
+        let max = self.settings.jitter.as_millis() as u64;
+        if max == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(RandomState::new().hash_one(Instant::now()) % max)
+    }
+}
@@ -0,0 +1,272 @@
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    process, thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_TYPE, COOKIE},
+    ClientBuilder as ReqwestClientBuilder,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Message;
+
+use super::{
+    users::ErrorsResponse, LogoutParameters, SahMethod, SysbusRequest, APPLICATION_SAH_WS_CALL,
+    X_CONTEXT, X_SAH_LOGOUT,
+};
+
+/// Login rejected because the Livebox reached its maximum number of sessions
+#[derive(Debug)]
+pub struct TooManySessions;
+
+impl fmt::Display for TooManySessions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::TooManySessions)
+    }
+}
+
+impl std::error::Error for TooManySessions {}
+
+/// Sysbus error code of logins refused because every session slot is taken
+const MAX_SESSIONS_REACHED: u32 = 1114114;
+
+/// Delay after which a lock left behind by a killed process is broken
+const STALE_LOCK: Duration = Duration::from_secs(10);
+
+pub(super) fn is_too_many_sessions(status: u16, body: &str) -> bool {
+    status == 429
+        || serde_json::from_str::<ErrorsResponse>(body).is_ok_and(|response| {
+            response
+                .errors
+                .iter()
+                .any(|error| error.error == MAX_SESSIONS_REACHED)
+        })
+}
+
+/// A context opened by livebox-cli
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CachedContext {
+    pub context_id: String,
+    pub base_url_ws: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    pub cookie: Option<String>,
+    /// Unix timestamp of the login
+    pub created_at: u64,
+    /// Process which opened the context
+    pub pid: u32,
+}
+
+impl CachedContext {
+    pub(super) fn new(context_id: String, base_url_ws: String, cookie: Option<String>) -> Self {
+        Self {
+            context_id,
+            base_url_ws,
+            cookie,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            pid: process::id(),
+        }
+    }
+
+    /// Releases the context, using its own session cookie.
+    pub async fn release(&self) -> Result<()> {
+        let mut request = ReqwestClientBuilder::default()
+            .build()?
+            .post(&self.base_url_ws)
+            .header(CONTENT_TYPE, APPLICATION_SAH_WS_CALL)
+            .header(X_CONTEXT, &self.context_id)
+            .header(AUTHORIZATION, format!("{X_SAH_LOGOUT} {}", self.context_id))
+            .json(&SysbusRequest::SahDeviceInformation(
+                SahMethod::ReleaseContext {
+                    parameters: LogoutParameters::default(),
+                },
+            ));
+        if let Some(cookie) = &self.cookie {
+            request = request.header(COOKIE, cookie);
+        }
+        let response = request.send().await?;
+        debug!(
+            "<<< {}\n{}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        );
+        Ok(())
+    }
+}
+
+/// Tracks contexts opened by livebox-cli so that leaked ones can be released later. The file
+/// holds live session credentials, it is only readable by its owner and changed under a lock
+/// shared by concurrent processes.
+#[derive(Clone)]
+pub struct ContextCache {
+    path: PathBuf,
+}
+
+impl ContextCache {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Cached contexts, oldest first
+    pub fn load(&self) -> Result<Vec<CachedContext>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| Message::UnableToRead(&self.path).to_string())
+            }
+        };
+        let mut contexts: Vec<CachedContext> = serde_json::from_str(&content)?;
+        contexts.sort_by_key(|context| context.created_at);
+        Ok(contexts)
+    }
+
+    /// Replaces the file at once, readers never see a partial write.
+    fn save(&self, contexts: &[CachedContext]) -> Result<()> {
+        let temporary = self.path.with_extension("json.tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(&temporary)
+            .with_context(|| Message::UnableToCreate(&temporary).to_string())?;
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(serde_json::to_string_pretty(contexts)?.as_bytes())?;
+        fs::rename(&temporary, &self.path)
+            .with_context(|| Message::UnableToWrite(&self.path).to_string())
+    }
+
+    /// Applies a change to the cached contexts while holding the lock.
+    fn update(&self, change: impl FnOnce(&mut Vec<CachedContext>)) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = Lock::acquire(self.path.with_extension("json.lock"))?;
+        let mut contexts = self.load()?;
+        change(&mut contexts);
+        self.save(&contexts)
+    }
+
+    pub fn add(&self, context: CachedContext) -> Result<()> {
+        self.update(|contexts| contexts.push(context))
+    }
+
+    pub fn remove(&self, context_id: &str) -> Result<()> {
+        self.update(|contexts| contexts.retain(|context| context.context_id != context_id))
+    }
+}
+
+/// Lock file created exclusively, removed when dropped
+struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    fn acquire(path: PathBuf) -> Result<Self> {
+        let started = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(err).with_context(|| Message::UnableToCreate(&path).to_string())
+                }
+            }
+            let age = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            // a process killed while holding the lock leaves it behind
+            if age.is_some_and(|age| age > STALE_LOCK) {
+                debug!("Breaking stale lock {path:?}");
+                fs::remove_file(&path).ok();
+                continue;
+            }
+            if started.elapsed().unwrap_or_default() > STALE_LOCK {
+                return Err(anyhow!(Message::UnableToLock(&path).to_string()));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, sync::Arc};
+
+    use super::*;
+
+    fn cache(name: &str) -> ContextCache {
+        let dir = env::temp_dir().join(format!("livebox-cli-{name}-{}", process::id()));
+        fs::remove_dir_all(&dir).ok();
+        ContextCache::new(dir.join("contexts.json"))
+    }
+
+    fn context(id: usize) -> CachedContext {
+        CachedContext::new(
+            format!("context-{id}"),
+            "http://livebox/ws".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn detects_the_session_cap() {
+        let refused =
+            r#"{"status":null,"errors":[{"error":1114114,"description":"Max sessions"}]}"#;
+        assert!(is_too_many_sessions(200, refused));
+        assert!(is_too_many_sessions(429, ""));
+        let denied = r#"{"status":null,"errors":[{"error":13,"description":"session max age"}]}"#;
+        assert!(!is_too_many_sessions(401, denied));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cache_is_only_readable_by_its_owner() {
+        let cache = cache("private");
+        cache.add(context(1)).unwrap();
+        let mode = fs::metadata(&cache.path).unwrap().permissions().mode();
+        fs::remove_dir_all(cache.path.parent().unwrap()).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn concurrent_changes_are_kept() {
+        let cache = Arc::new(cache("concurrent"));
+        let threads: Vec<_> = (0..8)
+            .map(|id| {
+                let cache = cache.clone();
+                thread::spawn(move || cache.add(context(id)).unwrap())
+            })
+            .collect();
+        threads
+            .into_iter()
+            .for_each(|thread| thread.join().unwrap());
+        cache.remove("context-0").unwrap();
+
+        let contexts = cache.load().unwrap();
+        fs::remove_dir_all(cache.path.parent().unwrap()).unwrap();
+        assert_eq!(contexts.len(), 7);
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    dhcp::LanIpView, Client, GenericResponse, NoParameters, SysbusRequest, APPLICATION_NAME,
+};
+
+impl Client {
+    /// Reboots the Livebox, the current session is lost.
+    pub async fn reboot(&self) -> Result<Value> {
+        self.exec(SysbusRequest::Nmc(NmcMethod::Reboot {
+            parameters: RebootParams {
+                reason: APPLICATION_NAME.to_string(),
+            },
+        }))
+        .await
+    }
+
+    /// Restores the factory settings, the Livebox reboots and the current session is lost.
+    pub async fn factory_reset(&self) -> Result<Value> {
+        self.exec(SysbusRequest::Nmc(NmcMethod::Reset {
+            parameters: RebootParams {
+                reason: APPLICATION_NAME.to_string(),
+            },
+        }))
+        .await
+    }
+
+    pub async fn get_wan_status(&self) -> Result<WanStatus> {
+        let response = self
+            .exec(SysbusRequest::Nmc(NmcMethod::GetWanStatus {
+                parameters: NoParameters {},
+            }))
+            .await?;
+        Ok(serde_json::from_value::<GenericResponse<bool, WanStatus>>(response)?.data)
+    }
+
+    pub async fn list_voip_trunks(&self) -> Result<Vec<VoipTrunkView>> {
+        self.exec_status(SysbusRequest::VoiceApplication(
+            VoiceApplicationMethod::ListTrunks {
+                parameters: NoParameters {},
+            },
+        ))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum NmcMethod {
+    #[serde(rename = "reboot")]
+    Reboot { parameters: RebootParams },
+    #[serde(rename = "reset")]
+    Reset { parameters: RebootParams },
+    #[serde(rename = "getWANStatus")]
+    GetWanStatus { parameters: NoParameters },
+    #[serde(rename = "getWanModeList")]
+    GetWanModeList { parameters: NoParameters },
+    #[serde(rename = "setWanMode")]
+    SetWanMode {
+        parameters: super::wan::SetWanModeParams,
+    },
+    #[serde(rename = "getLANIP")]
+    GetLanIp { parameters: NoParameters },
+    #[serde(rename = "setLANIP")]
+    SetLanIp { parameters: LanIpView },
+}
+
+#[derive(Serialize)]
+pub(super) struct RebootParams {
+    reason: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WanStatus {
+    #[serde(rename = "WanState")]
+    pub wan_state: String,
+
+    #[serde(rename = "LinkState")]
+    pub link_state: String,
+
+    #[serde(rename = "ConnectionState")]
+    pub connection_state: String,
+
+    #[serde(rename = "IPAddress")]
+    pub ip_address: String,
+
+    #[serde(rename = "WanMode", default)]
+    pub wan_mode: String,
+}
+
+impl WanStatus {
+    pub fn is_up(&self) -> bool {
+        self.wan_state == "up" && self.link_state == "up"
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum VoiceApplicationMethod {
+    #[serde(rename = "listTrunks")]
+    ListTrunks { parameters: NoParameters },
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VoipTrunkView {
+    pub trunk_lines: Vec<VoipLineView>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VoipLineView {
+    pub enable: String,
+    pub status: String,
+}
+
+impl VoipTrunkView {
+    /// Every enabled line is registered
+    pub fn is_registered(&self) -> bool {
+        self.trunk_lines
+            .iter()
+            .filter(|line| line.enable == "Enabled")
+            .all(|line| line.status == "Up")
+    }
+}
@@ -0,0 +1,520 @@
+//! In-process Livebox answering the login, generic calls and NAT endpoints from fixtures, to test
+//! tools built on the client without hardware.
+//!
+//! ```no_run
+//! use livebox_cli::livebox::test_support::MockLiveboxBuilder;
+//! use serde_json::json;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let livebox = MockLiveboxBuilder::default()
+//!     .with_response("NMC", "getWANStatus", json!({"status": true, "data": {"WanState": "up"}}))
+//!     .start()?;
+//! let client = livebox.client_builder().build().await?;
+//! let status = client.execute("NMC".to_string(), "getWANStatus".to_string()).await?;
+//! client.logout().await?;
+//! assert_eq!(livebox.open_contexts(), 0);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+    mask_secrets, users::PERMISSION_DENIED, ClientBuilder, NatRuleView, Protocol, RuleStatus,
+    SetPortFowardingParams, X_CONTEXT, X_SAH_LOGOUT,
+};
+
+/// Credentials of the user available unless replaced
+pub const DEFAULT_USERNAME: &str = "admin";
+pub const DEFAULT_PASSWORD: &str = "admin";
+
+/// Sysbus error code of calls to unknown services or methods
+const NOT_FOUND: u32 = 196618;
+
+/// Users, canned responses and NAT rules of a mock Livebox
+pub struct MockLiveboxBuilder {
+    users: Vec<MockUser>,
+    responses: HashMap<(String, String), Value>,
+    nat_rules: BTreeMap<String, Value>,
+    login_groups: bool,
+    cookie_domain: Option<String>,
+}
+
+struct MockUser {
+    username: String,
+    password: String,
+    groups: Vec<String>,
+}
+
+impl Default for MockLiveboxBuilder {
+    fn default() -> Self {
+        Self {
+            users: vec![MockUser {
+                username: DEFAULT_USERNAME.to_string(),
+                password: DEFAULT_PASSWORD.to_string(),
+                groups: vec!["http".to_string(), "admin".to_string()],
+            }],
+            responses: HashMap::new(),
+            nat_rules: BTreeMap::new(),
+            login_groups: true,
+            cookie_domain: None,
+        }
+    }
+}
+
+impl MockLiveboxBuilder {
+    /// Adds a user, or replaces the one with the same name. Users lacking the `admin` group are
+    /// denied NAT changes.
+    pub fn with_user(mut self, username: &str, password: &str, groups: &[&str]) -> Self {
+        self.users.retain(|user| user.username != username);
+        self.users.push(MockUser {
+            username: username.to_string(),
+            password: password.to_string(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Body answered to every call of a method, takes precedence over the NAT endpoints
+    /// (ex: `json!({"status": true, "data": {"WanState": "up"}})`).
+    pub fn with_response(mut self, service: &str, method: &str, body: Value) -> Self {
+        self.responses
+            .insert((service.to_string(), method.to_string()), body);
+        self
+    }
+
+    /// Logins answer no groups, like legacy firmware, they are listed by `UserManagement.getUsers`
+    /// only.
+    pub fn without_login_groups(mut self) -> Self {
+        self.login_groups = false;
+        self
+    }
+
+    /// Session cookies are set for a domain, like the Livebox does for its host name.
+    pub fn with_cookie_domain(mut self, domain: &str) -> Self {
+        self.cookie_domain = Some(domain.to_string());
+        self
+    }
+
+    /// Existing port forwarding rule, its id is prefixed by its origin like the Livebox does.
+    pub fn with_nat_rule(mut self, rule: SetPortFowardingParams) -> Self {
+        let rule = serde_json::to_value(rule)
+            .and_then(serde_json::from_value::<PortForwarding>)
+            .expect("port forwarding parameters");
+        let rule = rule.into_view();
+        self.nat_rules
+            .insert(rule.id.clone(), serde_json::to_value(rule).unwrap());
+        self
+    }
+
+    /// Listens on a random local port, requests are served by background threads until the
+    /// returned Livebox is dropped.
+    pub fn start(self) -> Result<MockLivebox> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            users: self.users,
+            responses: self.responses,
+            nat_rules: self.nat_rules,
+            login_groups: self.login_groups,
+            cookie_domain: self.cookie_domain,
+            contexts: HashMap::new(),
+            next_context: 1,
+            requests: Vec::new(),
+            cookies: Vec::new(),
+        }));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (server_state, server_stopped) = (state.clone(), stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if server_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = server_state.clone();
+                thread::spawn(move || serve(stream, &state));
+            }
+        });
+        Ok(MockLivebox {
+            address,
+            state,
+            stopped,
+        })
+    }
+}
+
+/// A running mock Livebox
+pub struct MockLivebox {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+}
+
+struct State {
+    users: Vec<MockUser>,
+    responses: HashMap<(String, String), Value>,
+    nat_rules: BTreeMap<String, Value>,
+    login_groups: bool,
+    cookie_domain: Option<String>,
+    /// Groups of the user of each open context
+    contexts: HashMap<String, Vec<String>>,
+    next_context: u32,
+    requests: Vec<Value>,
+    /// `Cookie` headers of the requests
+    cookies: Vec<String>,
+}
+
+impl MockLivebox {
+    /// Starts a mock Livebox with the default user and no fixtures.
+    pub fn start() -> Result<Self> {
+        MockLiveboxBuilder::default().start()
+    }
+
+    /// Base url to give to the client or `--base-url`, without the `/ws` suffix
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Client builder targeting the mock Livebox with the default credentials
+    pub fn client_builder(&self) -> ClientBuilder {
+        ClientBuilder::default()
+            .with_base_url(self.base_url())
+            .with_credentials(
+                DEFAULT_USERNAME.to_string(),
+                DEFAULT_PASSWORD.to_string().into(),
+            )
+    }
+
+    /// Requests received so far, passwords masked
+    pub fn requests(&self) -> Vec<Value> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// `Cookie` headers received so far
+    pub fn cookies(&self) -> Vec<String> {
+        self.state.lock().unwrap().cookies.clone()
+    }
+
+    /// Current port forwarding rules, sorted by id
+    pub fn nat_rules(&self) -> Vec<NatRuleView> {
+        let state = self.state.lock().unwrap();
+        state
+            .nat_rules
+            .values()
+            .map(|rule| serde_json::from_value(rule.clone()).unwrap())
+            .collect()
+    }
+
+    /// Number of contexts not released yet
+    pub fn open_contexts(&self) -> usize {
+        self.state.lock().unwrap().contexts.len()
+    }
+}
+
+impl Drop for MockLivebox {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wakes the listener up so it notices it has been stopped
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// Parameters of `setPortForwarding`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PortForwarding {
+    id: String,
+    origin: String,
+    #[serde(default)]
+    description: String,
+    source_interface: String,
+    protocol: Protocol,
+    external_port: String,
+    internal_port: String,
+    #[serde(rename = "destinationIPAddress")]
+    destination_ip_address: String,
+    #[serde(default, rename = "destinationMACAddress")]
+    destination_mac_address: String,
+    enable: bool,
+}
+
+impl PortForwarding {
+    fn into_view(self) -> NatRuleView {
+        let prefix = format!("{}_", self.origin);
+        let id = match self.id.starts_with(&prefix) {
+            true => self.id,
+            false => format!("{prefix}{}", self.id),
+        };
+        NatRuleView {
+            id,
+            origin: self.origin,
+            description: self.description,
+            status: match self.enable {
+                true => RuleStatus::Enabled,
+                false => RuleStatus::Disabled,
+            },
+            source_interface: self.source_interface,
+            protocol: self.protocol,
+            external_port: self.external_port,
+            internal_port: self.internal_port,
+            source_prefix: String::new(),
+            destination_ip_address: self.destination_ip_address,
+            destination_mac_address: self.destination_mac_address,
+            lease_duration: 0,
+            hairpin_nat: true,
+            symmetric_snat: false,
+            upnp_v1_compat: false,
+            enable: self.enable,
+        }
+    }
+}
+
+/// Answers the requests of a kept-alive connection until it is closed.
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+    let mut writer = stream;
+    while let Ok(Some(Request { headers, body })) = read_request(&mut reader) {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(request) => {
+                let mut state = state.lock().unwrap();
+                state.cookies.extend(header("cookie").map(str::to_string));
+                state.handle(request, header(X_CONTEXT), header("authorization"))
+            }
+            Err(_) => Response::new(400, json!({"status": null})),
+        };
+        if write_response(&mut writer, response).is_err() {
+            return;
+        }
+    }
+}
+
+struct Request {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Reads the next request of the connection, none once closed.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    // request line
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request { headers, body }))
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+    cookie: Option<String>,
+}
+
+impl Response {
+    fn new(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body,
+            cookie: None,
+        }
+    }
+
+    fn ok(body: Value) -> Self {
+        Self::new(200, body)
+    }
+
+    /// Sysbus error, answered with a successful HTTP status like the Livebox does
+    fn error(code: u32, description: &str, info: &str) -> Self {
+        Self::ok(json!({
+            "status": null,
+            "errors": [{"error": code, "description": description, "info": info}],
+        }))
+    }
+}
+
+fn write_response(writer: &mut impl Write, response: Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        _ => "Unauthorized",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+        response.status,
+        body.len()
+    );
+    if let Some(cookie) = response.cookie {
+        head.push_str(&format!("Set-Cookie: {cookie}; Path=/\r\n"));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(body.as_bytes())?;
+    writer.flush()
+}
+
+impl State {
+    fn handle(
+        &mut self,
+        request: Value,
+        context: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Response {
+        let mut recorded = request.clone();
+        mask_secrets(&mut recorded);
+        self.requests.push(recorded);
+
+        let service = request["service"].as_str().unwrap_or_default();
+        let method = request["method"].as_str().unwrap_or_default();
+        let parameters = &request["parameters"];
+        match (service, method) {
+            ("sah.Device.Information", "createContext") => return self.create_context(parameters),
+            ("sah.Device.Information", "releaseContext") => {
+                let context = authorization
+                    .and_then(|authorization| authorization.strip_prefix(X_SAH_LOGOUT))
+                    .map(str::trim);
+                if let Some(context) = context {
+                    self.contexts.remove(context);
+                }
+                return Response::new(401, json!({"status": 1}));
+            }
+            _ => {}
+        }
+        let Some(groups) = context.and_then(|context| self.contexts.get(context)) else {
+            return Response::error(PERMISSION_DENIED, "Permission denied", method);
+        };
+        let admin = groups.iter().any(|group| group == "admin");
+
+        if let Some(body) = self
+            .responses
+            .get(&(service.to_string(), method.to_string()))
+        {
+            return Response::ok(body.clone());
+        }
+        match (service, method) {
+            ("Firewall", "getPortForwarding") => Response::ok(json!({"status": self.nat_rules})),
+            ("Firewall", "setPortForwarding" | "deletePortForwarding") if !admin => {
+                Response::error(PERMISSION_DENIED, "Permission denied", method)
+            }
+            ("Firewall", "setPortForwarding") => {
+                match serde_json::from_value::<PortForwarding>(parameters.clone()) {
+                    Ok(rule) => {
+                        let rule = rule.into_view();
+                        let id = rule.id.clone();
+                        self.nat_rules
+                            .insert(id.clone(), serde_json::to_value(rule).unwrap());
+                        Response::ok(json!({"status": id}))
+                    }
+                    Err(err) => Response::error(NOT_FOUND, "Invalid parameters", &err.to_string()),
+                }
+            }
+            ("Firewall", "deletePortForwarding") => {
+                let id = parameters["id"].as_str().unwrap_or_default();
+                let removed = self.nat_rules.remove(id).is_some();
+                Response::ok(json!({"status": removed}))
+            }
+            ("Firewall", "commit") => Response::ok(json!({"status": true})),
+            ("UserManagement", "getUsers") => {
+                let users: Vec<_> = self
+                    .users
+                    .iter()
+                    .map(|user| json!({"name": user.username, "groups": user.groups}))
+                    .collect();
+                Response::ok(json!({"status": users}))
+            }
+            ("UserManagement", "changePassword") => {
+                let name = parameters["name"].as_str().unwrap_or_default();
+                let password = parameters["password"].as_str().unwrap_or_default();
+                let Some(user) = self
+                    .users
+                    .iter_mut()
+                    .find(|user| user.username == name && user.password == password)
+                else {
+                    return Response::error(PERMISSION_DENIED, "Permission denied", method);
+                };
+                user.password = parameters["newPassword"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                Response::ok(json!({"status": true}))
+            }
+            _ => Response::error(NOT_FOUND, "Object or parameter not found", service),
+        }
+    }
+
+    fn create_context(&mut self, parameters: &Value) -> Response {
+        let username = parameters["username"].as_str().unwrap_or_default();
+        let password = parameters["password"].as_str().unwrap_or_default();
+        let Some(user) = self
+            .users
+            .iter()
+            .find(|user| user.username == username && user.password == password)
+        else {
+            return Response::new(
+                401,
+                json!({"status": null, "errors": [{"error": PERMISSION_DENIED, "description": "Permission denied"}]}),
+            );
+        };
+        let context_id = format!("mock-context-{}", self.next_context);
+        self.next_context += 1;
+        let groups = match self.login_groups {
+            true => user.groups.join(","),
+            false => String::new(),
+        };
+        self.contexts
+            .insert(context_id.clone(), user.groups.clone());
+        let domain = self
+            .cookie_domain
+            .as_ref()
+            .map(|domain| format!("; Domain={domain}"))
+            .unwrap_or_default();
+        Response {
+            cookie: Some(format!("sessid={context_id}{domain}")),
+            ..Response::ok(json!({
+                "status": 0,
+                "data": {"contextID": context_id, "username": username, "groups": groups},
+            }))
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use tokio::net::{lookup_host, TcpStream};
+
+use crate::i18n::Message;
+
+/// Durations of the phases of a command, shared by the clients of a run
+#[derive(Debug, Default)]
+pub struct Timings {
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+}
+
+impl Timings {
+    pub(super) fn record(&self, phase: &'static str, duration: Duration) {
+        self.phases
+            .lock()
+            .expect("timings lock poisoned")
+            .push((phase, duration));
+    }
+
+    /// Runs the future and records its duration under the given phase.
+    pub(super) async fn time<F: Future>(&self, phase: &'static str, future: F) -> F::Output {
+        let start = Instant::now();
+        let output = future.await;
+        self.record(phase, start.elapsed());
+        output
+    }
+
+    /// Resolves the Livebox address and opens a TCP connection to it, the HTTP client doesn't
+    /// expose these phases.
+    pub(super) async fn measure_network(&self, base_url: &str) -> Result<()> {
+        let url = Url::parse(base_url)?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!(Message::NoHost(url.as_str()).to_string()))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+        let address = self
+            .time("dns", lookup_host((host, port)))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow!(Message::NoAddress(host).to_string()))?;
+        self.time("connect", TcpStream::connect(address)).await?;
+        Ok(())
+    }
+}
+
+/// Times the future when timings are collected.
+pub(super) async fn timed<F: Future>(
+    timings: Option<&Timings>,
+    phase: &'static str,
+    future: F,
+) -> F::Output {
+    match timings {
+        Some(timings) => timings.time(phase, future).await,
+        None => future.await,
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases = self.phases.lock().expect("timings lock poisoned");
+        for (phase, duration) in phases.iter() {
+            writeln!(f, "{phase:<8} {:>10.3} ms", duration.as_secs_f64() * 1000.0)?;
+        }
+        let total: Duration = phases.iter().map(|(_, duration)| *duration).sum();
+        write!(
+            f,
+            "{:<8} {:>10.3} ms",
+            "total",
+            total.as_secs_f64() * 1000.0
+        )
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::i18n::Message;
+
+use super::{expose_secret, Client, NoParameters, SysbusRequest};
+
+/// Sysbus error code of calls the authenticated user isn't allowed to make
+pub(super) const PERMISSION_DENIED: u32 = 13;
+
+/// Call rejected because the authenticated user lacks a permission group
+#[derive(Debug)]
+pub struct PermissionDenied;
+
+impl fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::PermissionDenied)
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
+/// Login rejected because of a wrong username or password
+#[derive(Debug)]
+pub struct InvalidCredentials;
+
+impl fmt::Display for InvalidCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::InvalidCredentials)
+    }
+}
+
+impl std::error::Error for InvalidCredentials {}
+
+#[derive(Deserialize)]
+pub(super) struct ErrorsResponse {
+    #[serde(default)]
+    pub(super) errors: Vec<SysbusError>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct SysbusError {
+    pub(super) error: u32,
+}
+
+pub(super) fn is_invalid_credentials(status: u16) -> bool {
+    status == 401
+}
+
+pub(super) fn is_permission_denied(status: u16, body: &str) -> bool {
+    status == 403
+        || serde_json::from_str::<ErrorsResponse>(body).is_ok_and(|response| {
+            response
+                .errors
+                .iter()
+                .any(|error| error.error == PERMISSION_DENIED)
+        })
+}
+
+/// Whether the groups include the required one, unknown when there are none
+pub(super) fn has_group(groups: &[String], group: &str) -> Option<bool> {
+    (!groups.is_empty()).then(|| groups.iter().any(|actual| actual == group))
+}
+
+/// Groups of a user listed by `UserManagement.getUsers`, as a comma separated string or a list
+fn user_groups(users: &Value, username: &str) -> Vec<String> {
+    let user = users["status"].as_array().and_then(|users| {
+        users.iter().find(|user| {
+            user.get("name")
+                .or_else(|| user.get("Name"))
+                .and_then(Value::as_str)
+                == Some(username)
+        })
+    });
+    let groups = user.and_then(|user| user.get("groups").or_else(|| user.get("Groups")));
+    match groups {
+        Some(Value::String(groups)) => groups
+            .split(',')
+            .filter(|group| !group.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Some(Value::Array(groups)) => groups
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Client {
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Permission groups of the authenticated user
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    /// Groups of the authenticated user, asked to the Livebox when the login response has none
+    /// (ex: legacy firmware). Empty when the Livebox doesn't tell.
+    pub async fn known_groups(&self) -> &[String] {
+        if !self.groups.is_empty() {
+            return &self.groups;
+        }
+        self.queried_groups
+            .get_or_init(|| async {
+                let request = SysbusRequest::UserManagement(UserManagementMethod::GetUsers {
+                    parameters: NoParameters {},
+                });
+                let users = async {
+                    let request = self.profile.apply(serde_json::to_value(request)?);
+                    self.exec_value(request).await
+                };
+                let groups = match users.await {
+                    Ok(users) => user_groups(&users, &self.username),
+                    Err(err) => {
+                        warn!("Unable to list the Livebox users: {err:#}");
+                        Vec::new()
+                    }
+                };
+                if groups.is_empty() {
+                    warn!(
+                        "Groups of {} unknown, changes are sent unchecked",
+                        self.username
+                    );
+                }
+                groups
+            })
+            .await
+    }
+
+    /// Fails when the authenticated user lacks the group, changes are sent unchecked when its
+    /// groups are unknown.
+    pub(super) async fn check_group(&self, group: &str) -> Result<()> {
+        match has_group(self.known_groups().await, group) {
+            Some(true) => Ok(()),
+            Some(false) => Err(anyhow!(PermissionDenied)),
+            None => Ok(()),
+        }
+    }
+
+    /// Changes the password of a Livebox user, the current session stays open.
+    pub async fn change_password(
+        &self,
+        name: String,
+        password: SecretString,
+        new_password: SecretString,
+    ) -> Result<Value> {
+        self.exec(SysbusRequest::UserManagement(
+            UserManagementMethod::ChangePassword {
+                parameters: ChangePasswordParams {
+                    name,
+                    password,
+                    new_password,
+                },
+            },
+        ))
+        .await
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum UserManagementMethod {
+    #[serde(rename = "getUsers")]
+    GetUsers { parameters: NoParameters },
+    #[serde(rename = "changePassword")]
+    ChangePassword { parameters: ChangePasswordParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct ChangePasswordParams {
+    name: String,
+    #[serde(serialize_with = "expose_secret")]
+    password: SecretString,
+    #[serde(rename = "newPassword", serialize_with = "expose_secret")]
+    new_password: SecretString,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn checks_groups() {
+        let groups = |groups: &[&str]| {
+            groups
+                .iter()
+                .map(|group| group.to_string())
+                .collect::<Vec<_>>()
+        };
+        let cases = [
+            (groups(&["http", "admin"]), Some(true)),
+            (groups(&["http"]), Some(false)),
+            (groups(&["remoteadmin"]), Some(false)),
+            (groups(&[]), None),
+        ];
+        for (groups, expected) in cases {
+            assert_eq!(has_group(&groups, "admin"), expected, "{groups:?}");
+        }
+    }
+
+    #[test]
+    fn reads_groups_of_listed_users() {
+        let users = json!({"status": [
+            {"name": "admin", "groups": "http,admin"},
+            {"Name": "guest", "Groups": ["http"]},
+        ]});
+        assert_eq!(user_groups(&users, "admin"), ["http", "admin"]);
+        assert_eq!(user_groups(&users, "guest"), ["http"]);
+        assert!(user_groups(&users, "other").is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use log::debug;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{system::NmcMethod, Client, NoParameters, StatusResponse, SysbusRequest};
+
+impl Client {
+    /// WAN modes the Livebox supports (ex: `VDSL_DHCP`, `GPON_DHCP`)
+    pub async fn list_wan_modes(&self) -> Result<Vec<String>> {
+        self.exec_status(SysbusRequest::Nmc(NmcMethod::GetWanModeList {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn set_wan_mode(&self, wan_mode: String) -> Result<Value> {
+        self.exec(SysbusRequest::Nmc(NmcMethod::SetWanMode {
+            parameters: SetWanModeParams { wan_mode },
+        }))
+        .await
+    }
+
+    /// The 4G backup dongle, `None` when the Livebox has none
+    pub async fn get_backup_4g(&self) -> Result<Option<Backup4gView>> {
+        let response = self
+            .exec(SysbusRequest::Wwan(WwanMethod::Get {
+                parameters: NoParameters {},
+            }))
+            .await?;
+        Ok(
+            serde_json::from_value::<StatusResponse<Backup4gView>>(response)
+                .inspect_err(|err| debug!("no 4G backup: {err}"))
+                .ok()
+                .map(|response| response.status),
+        )
+    }
+
+    pub async fn get_wan_mode(&self) -> Result<WanModeView> {
+        Ok(WanModeView {
+            mode: self.get_wan_status().await?.wan_mode,
+            available: self.list_wan_modes().await?,
+            backup_4g: self.get_backup_4g().await?,
+        })
+    }
+}
+
+/// Kind of uplink of a WAN mode
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WanAccess {
+    Dsl,
+    Fiber,
+    Backup4g,
+}
+
+impl WanAccess {
+    /// Uplink of a Livebox WAN mode, from its prefix (ex: `GPON` in `GPON_DHCP`)
+    pub fn of(wan_mode: &str) -> Option<Self> {
+        let access = wan_mode.split('_').next().unwrap_or_default();
+        match access.to_ascii_uppercase().as_str() {
+            "ADSL" | "VDSL" | "DSL" => Some(WanAccess::Dsl),
+            "GPON" | "XGSPON" | "SFP" | "FTTH" => Some(WanAccess::Fiber),
+            "LTE" | "WWAN" | "4G" => Some(WanAccess::Backup4g),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WanAccess::Dsl => "DSL",
+            WanAccess::Fiber => "fiber",
+            WanAccess::Backup4g => "4G backup",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct SetWanModeParams {
+    #[serde(rename = "WanMode")]
+    wan_mode: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum WwanMethod {
+    #[serde(rename = "get")]
+    Get { parameters: NoParameters },
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WanModeView {
+    /// The current WAN mode
+    #[serde(rename = "Mode")]
+    pub mode: String,
+
+    /// WAN modes the Livebox supports
+    #[serde(rename = "Available")]
+    pub available: Vec<String>,
+
+    #[serde(rename = "Backup4G", skip_serializing_if = "Option::is_none")]
+    pub backup_4g: Option<Backup4gView>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Backup4gView {
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+
+    #[serde(rename = "ConnectionStatus", default)]
+    pub connection_status: String,
+
+    #[serde(rename = "SignalStrength", skip_serializing_if = "Option::is_none")]
+    pub signal_strength: Option<i32>,
+}
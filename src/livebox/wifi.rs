@@ -0,0 +1,487 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{anyhow, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::i18n::Message;
+
+use super::{Client, InterfaceRequest, NoParameters, SysbusRequest};
+
+impl Client {
+    pub async fn get_wifi_status(&self) -> Result<WifiStatus> {
+        self.exec_status(SysbusRequest::Wifi(WifiMethod::Get {
+            parameters: NoParameters {},
+        }))
+        .await
+    }
+
+    pub async fn set_wifi_enable(&self, enable: bool) -> Result<Value> {
+        self.exec(SysbusRequest::Wifi(WifiMethod::Set {
+            parameters: SetWifiParams { enable },
+        }))
+        .await
+    }
+
+    pub async fn list_access_points(&self) -> Result<HashMap<String, AccessPointView>> {
+        let mibs: WlanVapMibs = self
+            .exec_status(SysbusRequest::LanInterface(InterfaceMethod::GetMibs {
+                parameters: GetMibsParams {
+                    mibs: "wlanvap".to_string(),
+                    traverse: None,
+                },
+            }))
+            .await?;
+        Ok(mibs.wlanvap)
+    }
+
+    /// First private or guest access point, its credentials are shared by all bands.
+    pub async fn get_access_point(&self, guest: bool) -> Result<AccessPointView> {
+        let mut access_points: Vec<_> = self
+            .list_access_points()
+            .await?
+            .into_iter()
+            .filter(|(name, _)| name.contains("guest") == guest)
+            .collect();
+        access_points.sort_by(|(a, _), (b, _)| a.cmp(b));
+        access_points
+            .into_iter()
+            .next()
+            .map(|(_, access_point)| access_point)
+            .ok_or_else(|| anyhow!(Message::NoMatchingAccessPoint.to_string()))
+    }
+
+    pub async fn configure_access_point(
+        &self,
+        name: String,
+        ssid: Option<String>,
+        passphrase: Option<String>,
+    ) -> Result<Value> {
+        let config = AccessPointConfig {
+            ssid,
+            security: passphrase.map(|key_pass_phrase| SecurityConfig { key_pass_phrase }),
+            wps: None,
+        };
+        self.set_access_point_config(name, config).await
+    }
+
+    /// Enables or disables WiFi Protected Setup on the access point.
+    pub async fn set_wps_enable(&self, name: String, enable: bool) -> Result<Value> {
+        let config = AccessPointConfig {
+            ssid: None,
+            security: None,
+            wps: Some(WpsConfig { enable }),
+        };
+        self.set_access_point_config(name, config).await
+    }
+
+    async fn set_access_point_config(
+        &self,
+        name: String,
+        config: AccessPointConfig,
+    ) -> Result<Value> {
+        self.exec(SysbusRequest::LanInterface(
+            InterfaceMethod::SetWlanConfig {
+                parameters: SetWlanConfigParams {
+                    mibs: WlanConfig {
+                        wlanvap: Some(HashMap::from([(name, config)])),
+                        wlanradio: None,
+                    },
+                },
+            },
+        ))
+        .await
+    }
+
+    pub async fn list_radios(&self) -> Result<HashMap<String, RadioView>> {
+        let mibs: WlanRadioMibs = self
+            .exec_status(SysbusRequest::LanInterface(InterfaceMethod::GetMibs {
+                parameters: GetMibsParams {
+                    mibs: "wlanradio".to_string(),
+                    traverse: None,
+                },
+            }))
+            .await?;
+        Ok(mibs.wlanradio)
+    }
+
+    pub async fn configure_radio(&self, band: FrequencyBand, config: RadioConfig) -> Result<Value> {
+        let name = self
+            .list_radios()
+            .await?
+            .into_iter()
+            .find(|(_, radio)| radio.operating_frequency_band == band)
+            .map(|(name, _)| name)
+            .ok_or_else(|| anyhow!(Message::NoRadio(band.as_str()).to_string()))?;
+        self.exec(SysbusRequest::LanInterface(
+            InterfaceMethod::SetWlanConfig {
+                parameters: SetWlanConfigParams {
+                    mibs: WlanConfig {
+                        wlanvap: None,
+                        wlanradio: Some(HashMap::from([(name, config)])),
+                    },
+                },
+            },
+        ))
+        .await
+    }
+
+    /// Triggers a neighbor access points scan on each radio, optionally restricted to a band,
+    /// and collects the results once `wait` elapsed.
+    pub async fn scan_neighbors(
+        &self,
+        band: Option<FrequencyBand>,
+        wait: Duration,
+    ) -> Result<Vec<NeighborAccessPoint>> {
+        let radios: Vec<(String, FrequencyBand)> = self
+            .list_radios()
+            .await?
+            .into_iter()
+            .map(|(name, radio)| (name, radio.operating_frequency_band))
+            .filter(|(_, radio_band)| band.is_none_or(|band| band == *radio_band))
+            .collect();
+        for (name, _) in &radios {
+            self.exec(InterfaceRequest::new(
+                name,
+                InterfaceMethod::StartScan {
+                    parameters: NoParameters {},
+                },
+            ))
+            .await?;
+        }
+        tokio::time::sleep(wait).await;
+        let mut neighbors = Vec::new();
+        for (name, band) in radios {
+            let results: Vec<ScanResultView> = self
+                .exec_status(InterfaceRequest::new(
+                    &name,
+                    InterfaceMethod::GetScanResults {
+                        parameters: NoParameters {},
+                    },
+                ))
+                .await?;
+            neighbors.extend(
+                results
+                    .into_iter()
+                    .map(|scan| NeighborAccessPoint { band, scan }),
+            );
+        }
+        Ok(neighbors)
+    }
+
+    /// Lists stations associated to every access point along with their link statistics.
+    pub async fn list_stations(&self) -> Result<Vec<AssociatedStation>> {
+        let mut stations = Vec::new();
+        for (name, access_point) in self.list_access_points().await? {
+            let radio: WlanRadioMibs = self
+                .exec_status(InterfaceRequest::new(
+                    &name,
+                    InterfaceMethod::GetMibs {
+                        parameters: GetMibsParams {
+                            mibs: "wlanradio".to_string(),
+                            traverse: Some("down".to_string()),
+                        },
+                    },
+                ))
+                .await?;
+            let band = radio
+                .wlanradio
+                .into_values()
+                .next()
+                .map(|radio| radio.operating_frequency_band);
+            let access_point_stations: Vec<StationView> = self
+                .exec_status(InterfaceRequest::new(
+                    &name,
+                    InterfaceMethod::GetStationStats {
+                        parameters: NoParameters {},
+                    },
+                ))
+                .await?;
+            stations.extend(
+                access_point_stations
+                    .into_iter()
+                    .map(|station| AssociatedStation {
+                        access_point: name.clone(),
+                        ssid: access_point.ssid.clone(),
+                        band,
+                        station,
+                    }),
+            );
+        }
+        Ok(stations)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StationView {
+    #[serde(rename = "MACAddress")]
+    pub mac_address: String,
+
+    #[serde(rename = "SignalStrength")]
+    pub signal_strength: i16,
+
+    #[serde(rename = "LastDataDownlinkRate")]
+    pub last_data_downlink_rate: u32,
+
+    #[serde(rename = "LastDataUplinkRate")]
+    pub last_data_uplink_rate: u32,
+
+    #[serde(rename = "OperatingStandard")]
+    pub operating_standard: String,
+
+    #[serde(rename = "TxBytes")]
+    pub tx_bytes: u64,
+
+    #[serde(rename = "RxBytes")]
+    pub rx_bytes: u64,
+
+    /// Share of the radio airtime used by the station, not reported by every firmware
+    #[serde(
+        rename = "AirtimeUsage",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub airtime_usage: Option<u8>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AssociatedStation {
+    #[serde(rename = "AccessPoint")]
+    pub access_point: String,
+
+    #[serde(rename = "SSID")]
+    pub ssid: String,
+
+    #[serde(rename = "OperatingFrequencyBand")]
+    pub band: Option<FrequencyBand>,
+
+    #[serde(flatten)]
+    pub station: StationView,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ScanResultView {
+    #[serde(rename = "SSID")]
+    pub ssid: String,
+
+    #[serde(rename = "BSSID")]
+    pub bssid: String,
+
+    #[serde(rename = "Channel")]
+    pub channel: u16,
+
+    #[serde(rename = "RSSI")]
+    pub rssi: i16,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct NeighborAccessPoint {
+    #[serde(rename = "OperatingFrequencyBand")]
+    pub band: FrequencyBand,
+
+    #[serde(flatten)]
+    pub scan: ScanResultView,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum WifiMethod {
+    #[serde(rename = "get")]
+    Get { parameters: NoParameters },
+    #[serde(rename = "set")]
+    Set { parameters: SetWifiParams },
+}
+
+#[derive(Serialize)]
+pub(super) struct SetWifiParams {
+    #[serde(rename = "Enable")]
+    enable: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct WifiStatus {
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+
+    /// Radios are up
+    #[serde(rename = "Status")]
+    pub status: bool,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method")]
+pub(super) enum InterfaceMethod {
+    #[serde(rename = "getMIBs")]
+    GetMibs { parameters: GetMibsParams },
+    #[serde(rename = "setWLANConfig")]
+    SetWlanConfig { parameters: SetWlanConfigParams },
+    #[serde(rename = "startScan")]
+    StartScan { parameters: NoParameters },
+    #[serde(rename = "getScanResults")]
+    GetScanResults { parameters: NoParameters },
+    #[serde(rename = "getStationStats")]
+    GetStationStats { parameters: NoParameters },
+}
+
+#[derive(Serialize)]
+pub(super) struct GetMibsParams {
+    pub(super) mibs: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) traverse: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(super) struct SetWlanConfigParams {
+    mibs: WlanConfig,
+}
+
+#[derive(Serialize)]
+struct WlanConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wlanvap: Option<HashMap<String, AccessPointConfig>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wlanradio: Option<HashMap<String, RadioConfig>>,
+}
+
+#[derive(Serialize)]
+struct AccessPointConfig {
+    #[serde(rename = "SSID", skip_serializing_if = "Option::is_none")]
+    ssid: Option<String>,
+
+    #[serde(rename = "Security", skip_serializing_if = "Option::is_none")]
+    security: Option<SecurityConfig>,
+
+    #[serde(rename = "WPS", skip_serializing_if = "Option::is_none")]
+    wps: Option<WpsConfig>,
+}
+
+#[derive(Serialize)]
+struct WpsConfig {
+    #[serde(rename = "Enable")]
+    enable: bool,
+}
+
+#[derive(Serialize)]
+struct SecurityConfig {
+    #[serde(rename = "KeyPassPhrase")]
+    key_pass_phrase: String,
+}
+
+#[derive(Deserialize)]
+struct WlanVapMibs {
+    wlanvap: HashMap<String, AccessPointView>,
+}
+
+#[derive(Deserialize)]
+pub struct AccessPointView {
+    #[serde(rename = "SSID")]
+    pub ssid: String,
+
+    #[serde(rename = "Security")]
+    pub security: SecurityView,
+
+    /// Missing on firmware not reporting the WPS state
+    #[serde(rename = "WPS", default)]
+    pub wps: Option<WpsView>,
+}
+
+#[derive(Deserialize)]
+pub struct WpsView {
+    #[serde(rename = "Enable")]
+    pub enable: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SecurityView {
+    #[serde(rename = "KeyPassPhrase")]
+    pub key_pass_phrase: String,
+
+    /// Security mode (ex: `WPA2-Personal`, `None` for an open network)
+    #[serde(rename = "ModeEnabled", default)]
+    pub mode_enabled: String,
+}
+
+#[derive(Deserialize)]
+struct WlanRadioMibs {
+    wlanradio: HashMap<String, RadioView>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, JsonSchema)]
+#[allow(clippy::enum_variant_names)]
+pub enum FrequencyBand {
+    #[serde(rename = "2.4GHz")]
+    Band2_4GHz,
+    #[serde(rename = "5GHz")]
+    Band5GHz,
+    #[serde(rename = "6GHz")]
+    Band6GHz,
+}
+
+impl FrequencyBand {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FrequencyBand::Band2_4GHz => "2.4GHz",
+            FrequencyBand::Band5GHz => "5GHz",
+            FrequencyBand::Band6GHz => "6GHz",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RadioView {
+    #[serde(rename = "OperatingFrequencyBand")]
+    pub operating_frequency_band: FrequencyBand,
+
+    #[serde(rename = "Channel")]
+    pub channel: u16,
+
+    #[serde(rename = "AutoChannelEnable")]
+    pub auto_channel_enable: bool,
+
+    #[serde(rename = "OperatingChannelBandwidth")]
+    pub operating_channel_bandwidth: String,
+
+    #[serde(rename = "TransmitPower")]
+    pub transmit_power: i16,
+}
+
+impl RadioView {
+    /// Whether applying the settings would change the radio
+    pub fn is_changed_by(&self, config: &RadioConfig) -> bool {
+        config
+            .channel
+            .is_some_and(|channel| channel != self.channel)
+            || config
+                .auto_channel_enable
+                .is_some_and(|enable| enable != self.auto_channel_enable)
+            || config
+                .operating_channel_bandwidth
+                .as_ref()
+                .is_some_and(|bandwidth| *bandwidth != self.operating_channel_bandwidth)
+            || config
+                .transmit_power
+                .is_some_and(|power| power != self.transmit_power)
+    }
+}
+
+/// Radio settings to change, unset values are left untouched
+#[derive(Serialize, Default)]
+pub struct RadioConfig {
+    #[serde(rename = "Channel", skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u16>,
+
+    #[serde(rename = "AutoChannelEnable", skip_serializing_if = "Option::is_none")]
+    pub auto_channel_enable: Option<bool>,
+
+    #[serde(
+        rename = "OperatingChannelBandwidth",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub operating_channel_bandwidth: Option<String>,
+
+    #[serde(rename = "TransmitPower", skip_serializing_if = "Option::is_none")]
+    pub transmit_power: Option<i16>,
+}
@@ -1,10 +1,68 @@
-use anyhow::{anyhow, Result};
-use clap::{arg, builder::PossibleValue, Args, Parser, Subcommand, ValueEnum};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    io::{self, IsTerminal},
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
+use anyhow::{anyhow, Context, Result};
+use clap::{
+    builder::PossibleValue, error::ErrorKind, ArgGroup, Args, CommandFactory, FromArgMatches,
+    Parser, Subcommand, ValueEnum,
+};
+
+use handlebars::{no_escape, Handlebars, Template};
+use i18n::{Lang, Message};
 use livebox::SetPortFowardingParams;
+use livebox_cli::{i18n, livebox};
+use prompt::Confirmation;
+use schemars::{generate::SchemaSettings, JsonSchema, Schema};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use serde_json_path::JsonPath;
+use shutdown::Shutdown;
+use tokio::sync::OnceCell;
+
+mod admin;
+mod aliases;
+mod baseline;
+mod changes;
+mod compose;
+mod config;
+mod daemon;
+mod declarative;
+mod errors;
+mod healthcheck;
+mod idempotent;
+mod inventory;
+mod mqtt;
+mod nat;
+mod permissions;
+mod pipeline;
+mod plugins;
+mod prompt;
+mod qr;
+mod reboot;
+mod redact;
+mod rules;
+mod schedule;
+mod setup;
+mod shutdown;
+mod systemd;
+mod ui_proxy;
+#[cfg(feature = "self-update")]
+mod update;
 
-mod livebox;
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+/// Exit code of `exec --baseline` when the response drifted
+const DRIFT_EXIT_CODE: i32 = 2;
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
 
 #[derive(Debug, Parser)]
 struct CliArgs {
@@ -12,24 +70,88 @@ struct CliArgs {
     #[arg(long = "base-url", env, default_value = livebox::LIVEBOX_BASE_URL)]
     livebox_api_baseurl: String,
 
+    /// Livebox API generation, `legacy` for Livebox 2/3 firmware
+    #[arg(long, env = "LIVEBOX_API_FLAVOR", value_enum, default_value_t = ApiFlavor::Auto)]
+    api_flavor: ApiFlavor,
+
+    /// compatibility profile of rebranded firmware: `funbox`, a name from the `profiles`
+    /// configuration directory or a TOML file
+    #[arg(long, env = "LIVEBOX_CLI_PROFILE")]
+    profile: Option<String>,
+
     /// Livebox administration username
     #[arg(short, long, default_value = "admin")]
     username: String,
 
     /// Livebox administration password
-    #[arg(short, long)]
-    password: String,
+    #[arg(short, long, env = "LIVEBOX_PASSWORD", hide_env_values = true)]
+    password: Option<SecretString>,
 
     #[command(subcommand)]
     command: Commands,
 
+    #[command(flatten)]
+    output: OutputArgs,
+
+    /// configuration file [default: ~/.config/livebox-cli/config.toml]
+    #[arg(long, env = "LIVEBOX_CLI_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// minimum milliseconds between two requests to the Livebox
+    #[arg(long)]
+    min_request_interval: Option<u64>,
+
+    /// maximum number of concurrent requests to the Livebox, at least 1
+    #[arg(long)]
+    max_concurrent_requests: Option<NonZeroUsize>,
+
+    /// maximum random milliseconds added between two requests
+    #[arg(long)]
+    request_jitter: Option<u64>,
+
+    /// language of messages, defaults to the `LANG` environment variable
+    #[arg(long, env = "LIVEBOX_CLI_LANG", value_enum)]
+    lang: Option<Lang>,
+
+    /// print the JSON Schema of the command output instead of running it
+    #[arg(long, global = true)]
+    schema: bool,
+
+    /// print the duration of each phase (dns, connect, login, call, logout) to stderr
+    #[arg(long, global = true)]
+    timing: bool,
+
+    /// don't ask for confirmation before destructive operations
+    #[arg(short, long, global = true)]
+    yes: bool,
+
+    /// only report whether idempotent commands would change the configuration
+    #[arg(long, global = true)]
+    check: bool,
+}
+
+#[derive(Debug, Args)]
+struct OutputArgs {
     /// json path expression to filter output (ex: `$.IPAddress`)
     #[arg(short, long)]
     query: Option<JsonPath>,
 
     /// output raw strings, not JSON text
     #[arg(short = 'r', long = "raw")]
-    output_raw_strings: bool,
+    raw_strings: bool,
+
+    /// handlebars template rendered with the output (ex: `{{data.IPAddress}} up={{data.LinkState}}`)
+    #[arg(long, value_parser = parse_template)]
+    format: Option<String>,
+
+    /// don't color the changes printed by mutating commands
+    #[arg(long)]
+    no_color: bool,
+
+    /// mask MAC addresses, public IP addresses, SSIDs, serial and phone numbers, with consistent
+    /// pseudonyms, to share the output
+    #[arg(long)]
+    redact: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -43,24 +165,272 @@ enum Commands {
         /// method name (ex: `getWANStatus`)
         #[arg(short, long)]
         method: String,
+
+        /// validate and normalize the response with a typed model (ex: `WANStatus`)
+        #[arg(long, value_parser = parse_model)]
+        model: Option<&'static livebox::Model>,
+
+        /// method parameter, `{}` is replaced by each value of `--for-each` (ex: `mac={}`)
+        #[arg(long = "param", value_parser = pipeline::parse_parameter)]
+        parameters: Vec<(String, String)>,
+
+        /// listing call run first, as `service:method` (ex: `Devices:get`)
+        #[arg(long, requires = "for_each", value_parser = parse_call)]
+        from: Option<(String, String)>,
+
+        /// json path selecting the values of the listing call the method is invoked for, `{}` in
+        /// the service name is replaced too (ex: `$.status[*].Key`)
+        #[arg(long, requires = "from")]
+        for_each: Option<JsonPath>,
+
+        /// maximum number of concurrent calls with `--for-each`
+        #[arg(long, default_value = "4")]
+        concurrency: usize,
+
+        /// JSON file of a previous response, only the differences are output as a JSON patch and
+        /// the exit code is 2 when there are some
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+    },
+    /// Chain sysbus invocations in a single session, piping values between steps
+    #[command(group(ArgGroup::new("source").required(true)))]
+    Pipeline {
+        /// YAML file of steps
+        #[arg(long, group = "source")]
+        file: Option<PathBuf>,
+
+        /// `exec` and `pick` steps separated by `|`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, group = "source")]
+        steps: Vec<String>,
+    },
+    /// POST a body to an arbitrary Livebox path within the session (ex: `/sysbus/...` on older
+    /// firmware)
+    Raw {
+        /// path relative to the base url (ex: `/sysbus/NMC:getWANStatus`)
+        #[arg(long)]
+        path: String,
+
+        /// JSON request body
+        #[arg(long, default_value = "{}", value_parser = parse_json)]
+        body: Value,
     },
     /// Edit NAT rules
-    NAT {
+    Nat {
         #[command(subcommand)]
         action: FirewallActions,
     },
+    /// Manage WiFi
+    Wifi {
+        #[command(subcommand)]
+        action: WifiActions,
+    },
+    /// Inspect and switch the WAN access
+    Wan {
+        #[command(subcommand)]
+        action: WanActions,
+    },
+    /// Inspect the local network
+    Lan {
+        #[command(subcommand)]
+        action: LanActions,
+    },
+    /// Configure the DHCP server
+    Dhcp {
+        #[command(subcommand)]
+        action: DhcpActions,
+    },
+    /// List devices and manage their aliases
+    Devices {
+        #[command(subcommand)]
+        action: DevicesActions,
+    },
+    /// Control the IGMP snooping restricting IPTV multicast to the devices watching it
+    Multicast {
+        #[command(subcommand)]
+        action: MulticastActions,
+    },
+    /// Run network diagnostics from the Livebox
+    Diag {
+        #[command(subcommand)]
+        action: DiagActions,
+    },
+    /// Configure the firewall
+    Firewall {
+        #[command(subcommand)]
+        action: FirewallSettingsActions,
+    },
+    /// Expose a host to the Internet
+    Dmz {
+        #[command(subcommand)]
+        action: DmzActions,
+    },
+    /// Reboot the Livebox
+    Reboot(RebootOptions),
+    /// Restore the factory settings, the whole configuration is lost
+    FactoryReset,
+    /// Run monitoring probes, print a one-line verdict and the JSON detail, and exit with the
+    /// Nagios plugins exit codes
+    Healthcheck {
+        /// probes to run
+        #[arg(
+            long = "probe",
+            value_enum,
+            value_delimiter = ',',
+            default_value = "login,wan,dns,voip"
+        )]
+        probes: Vec<healthcheck::Probe>,
+
+        /// name the Livebox DNS resolver must resolve
+        #[arg(long, default_value = "orange.fr")]
+        dns_name: String,
+    },
+    /// Watch the Livebox until interrupted, printing events and running the matching rules
+    Daemon {
+        /// YAML file of rules reacting to events
+        #[arg(long)]
+        rules: Option<PathBuf>,
+
+        /// Seconds between two polls
+        #[arg(long, default_value = "30")]
+        interval: u64,
+
+        /// print a systemd unit running the daemon with the current binary and settings
+        #[arg(long)]
+        generate_systemd_unit: bool,
+    },
+    /// Serve the Livebox web UI within the session until interrupted (ex: through an SSH tunnel)
+    UiProxy {
+        /// local address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+    },
+    /// Manage sessions opened by livebox-cli
+    Session {
+        #[command(subcommand)]
+        action: SessionActions,
+    },
+    /// Run commands at recurring times, as long as `schedule run` is running
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleActions,
+    },
+    /// Read the log of configuration changes made by livebox-cli
+    Audit {
+        #[command(subcommand)]
+        action: AuditActions,
+    },
+    /// Manage the administration account
+    Admin {
+        #[command(subcommand)]
+        action: AdminActions,
+    },
+    /// Show the authenticated user, its permission groups and the commands they allow
+    Whoami,
+    /// Walk through the first-boot settings: admin password, WiFi, DHCP range and WPS
+    Setup,
+    /// List plugins found on PATH, invoke them as `livebox-cli <name>`
+    Plugins,
+    /// Replace this binary with the latest release, once its checksum is verified, `--check` only
+    /// reports the latest version
+    #[cfg(feature = "self-update")]
+    SelfUpdate,
+    #[command(external_subcommand)]
+    Plugin(Vec<String>),
+    /// Converge the Livebox configuration to a declarative YAML document
+    Apply {
+        /// configuration file (ex: `livebox.yaml`)
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// only show the changes that would be applied
+        #[arg(long)]
+        dry_run: bool,
+
+        /// what to do when a change fails
+        #[arg(long, value_enum, default_value = "stop")]
+        on_error: OnError,
+    },
+    /// Export the Livebox configuration using the declarative schema
+    Snapshot {
+        /// destination file (ex: `state.json`)
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    /// Report drift between the Livebox configuration and a declarative YAML document
+    Diff {
+        /// configuration file (ex: `livebox.yaml`)
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+}
+
+impl Commands {
+    /// Whether the command only reports the changes it would make
+    fn dry_run(&self) -> bool {
+        matches!(
+            self,
+            Commands::Apply { dry_run: true, .. }
+                | Commands::Nat {
+                    action: FirewallActions::FromCompose { dry_run: true, .. },
+                }
+        )
+    }
 }
 
 #[derive(Debug, Subcommand)]
 enum FirewallActions {
     List,
     Add(FirewallRule),
+    /// Add a rule removed once expired, by `nat gc` or the daemon
+    AddTemp {
+        #[command(flatten)]
+        rule: FirewallRule,
+
+        /// lifetime of the rule (ex: `90m`, `2h`, `1d`)
+        #[arg(long, value_parser = parse_duration)]
+        expires: Duration,
+    },
+    /// Remove the temporary rules which expired
+    Gc,
     Enable(NamedFirewallRule),
     Disable(NamedFirewallRule),
     Remove(NamedFirewallRule),
+    /// Remove disabled rules or rules whose destination is no longer a known device
+    Prune(PruneOptions),
+    /// Forward the ports published by the services of a docker-compose file
+    FromCompose {
+        /// compose file (ex: `docker-compose.yml`)
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// address of the Docker host, defaults to the address used to reach the Livebox
+        #[arg(long)]
+        host: Option<String>,
+
+        /// only report the rules to create or update
+        #[arg(long)]
+        dry_run: bool,
+
+        /// what to do when a rule can't be created or updated
+        #[arg(long, value_enum, default_value = "stop")]
+        on_error: OnError,
+    },
 }
 
 #[derive(Debug, Args)]
+#[group(skip)]
+#[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+struct PruneOptions {
+    /// remove disabled rules
+    #[arg(long, group = "criteria")]
+    disabled: bool,
+
+    /// remove rules whose destination is missing from the device list
+    #[arg(long, group = "criteria")]
+    destination_unreachable: bool,
+}
+
+#[derive(Debug, Clone, Args)]
 struct FirewallRule {
     /// A unique identifier
     #[arg(long)]
@@ -89,52 +459,532 @@ struct FirewallRule {
     /// The destination port
     #[arg(long = "dport")]
     destination_port: i16,
+
+    /// Keep the destination up to date with the device address, in daemon mode
+    #[arg(long)]
+    track: bool,
 }
 
-impl Into<SetPortFowardingParams> for FirewallRule {
-    fn into(self) -> SetPortFowardingParams {
+impl FirewallRule {
+    /// Rule id and destination to track
+    fn tracked(&self) -> Option<(String, String)> {
+        self.track
+            .then(|| (self.id.clone(), self.destination_host.clone()))
+    }
+}
+
+impl From<FirewallRule> for SetPortFowardingParams {
+    fn from(rule: FirewallRule) -> Self {
         SetPortFowardingParams::new(
-            self.id,
-            self.description,
-            self.protocol.into(),
-            self.source_port.to_string(),
-            self.destination_port.to_string(),
-            self.destination_host,
+            rule.id,
+            rule.description,
+            rule.protocol.into(),
+            rule.source_port.to_string(),
+            rule.destination_port.to_string(),
+            rule.destination_host,
         )
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 enum Protocol {
-    TCP,
-    UDP,
-    ALL,
+    Tcp,
+    Udp,
+    All,
+}
+
+impl From<Protocol> for livebox::Protocol {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Tcp => livebox::Protocol::Tcp,
+            Protocol::Udp => livebox::Protocol::Udp,
+            Protocol::All => livebox::Protocol::All,
+        }
+    }
 }
 
-impl Into<livebox::Protocol> for Protocol {
-    fn into(self) -> livebox::Protocol {
-        match self {
-            Protocol::TCP => livebox::Protocol::TCP,
-            Protocol::UDP => livebox::Protocol::UDP,
-            Protocol::ALL => livebox::Protocol::ALL,
+impl From<livebox::Protocol> for Protocol {
+    fn from(protocol: livebox::Protocol) -> Self {
+        match protocol {
+            livebox::Protocol::Tcp => Protocol::Tcp,
+            livebox::Protocol::Udp => Protocol::Udp,
+            livebox::Protocol::All => Protocol::All,
         }
     }
 }
 
 impl ValueEnum for Protocol {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Protocol::TCP, Protocol::UDP, Protocol::ALL]
+        &[Protocol::Tcp, Protocol::Udp, Protocol::All]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
         Some(match &self {
-            Protocol::TCP => PossibleValue::new("tcp"),
-            Protocol::UDP => PossibleValue::new("udp"),
-            Protocol::ALL => PossibleValue::new("all"),
+            Protocol::Tcp => PossibleValue::new("tcp"),
+            Protocol::Udp => PossibleValue::new("udp"),
+            Protocol::All => PossibleValue::new("all"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+struct RebootOptions {
+    /// Wait for the Livebox to be reachable and accept logins again
+    #[arg(long)]
+    wait: bool,
+
+    /// Also wait for the given services to be ready, implies `--wait`
+    #[arg(long, value_enum, value_delimiter = ',')]
+    wait_services: Vec<reboot::Service>,
+
+    /// Maximum seconds to wait for each of the shutdown, reachability and login stages
+    #[arg(long, default_value = "300")]
+    reachable_timeout: u64,
+
+    /// Maximum seconds to wait for each service
+    #[arg(long, default_value = "180")]
+    service_timeout: u64,
+}
+
+impl From<RebootOptions> for Option<reboot::WaitOptions> {
+    fn from(options: RebootOptions) -> Self {
+        (options.wait || !options.wait_services.is_empty()).then(|| reboot::WaitOptions {
+            reachable_timeout: Duration::from_secs(options.reachable_timeout),
+            services: options.wait_services,
+            service_timeout: Duration::from_secs(options.service_timeout),
         })
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum AdminActions {
+    /// Change the password, then log in again with the new one
+    ChangePassword {
+        /// read the new password from the first line of stdin instead of prompting for it
+        #[arg(long)]
+        password_stdin: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SessionActions {
+    /// List contexts opened by livebox-cli and not released yet
+    List,
+    /// Release contexts opened by livebox-cli
+    Revoke {
+        /// context identifier
+        #[arg(required_unless_present = "all")]
+        context_id: Option<String>,
+
+        /// release every context
+        #[arg(long, conflicts_with = "context_id")]
+        all: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AuditActions {
+    /// List the recorded changes, oldest first
+    Show {
+        /// only show the last changes
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// only show the changes made by this local user
+        #[arg(long)]
+        user: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ScheduleActions {
+    /// Schedule a command (ex: `schedule add "0 2 * * *" -- nat disable game-server`)
+    Add {
+        /// cron expression in the local time zone: minute, hour, day of month, month and day of
+        /// week
+        #[arg(value_parser = schedule::parse_cron)]
+        cron: String,
+
+        /// livebox-cli arguments, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// List the scheduled commands
+    List,
+    /// Remove a scheduled command
+    Remove {
+        /// task id, as listed
+        id: u32,
+    },
+    /// Run the scheduled commands when due until interrupted, each in its own session
+    Run,
+}
+
+#[derive(Debug, Subcommand)]
+enum LanActions {
+    /// Dump ARP and IPv6 neighbour tables with device names
+    Neighbours,
+}
+
+#[derive(Debug, Subcommand)]
+enum DhcpActions {
+    /// Manage options sent to DHCP clients (ex: PXE boot server), on firmware supporting custom
+    /// options
+    Options {
+        #[command(subcommand)]
+        action: DhcpOptionActions,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DhcpOptionActions {
+    /// List options, values are hexBinary encoded
+    List,
+    /// Send an option
+    Set {
+        /// option code (ex: `66` for the TFTP server name)
+        #[arg(long)]
+        option: u8,
+
+        /// option value, sent as text unless `--hex` is given (ex: `tftp.lan`)
+        #[arg(long)]
+        value: String,
+
+        /// the value is already hexBinary encoded
+        #[arg(long)]
+        hex: bool,
+    },
+    /// Stop sending an option
+    Unset {
+        /// option code
+        #[arg(long)]
+        option: u8,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DevicesActions {
+    /// List devices known by the Livebox, with their alias
+    List,
+    /// Print the devices with an address in an inventory format
+    Export {
+        #[arg(long, value_enum)]
+        format: inventory::ExportFormat,
+    },
+    /// Name devices, aliases are accepted wherever a host is expected
+    Alias {
+        #[command(subcommand)]
+        action: AliasActions,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasActions {
+    /// List aliases and the MAC address they name
+    List,
+    /// Name a device
+    Set {
+        /// MAC address of the device
+        #[arg(value_parser = parse_mac)]
+        mac: String,
+
+        /// alias (ex: `nas`)
+        name: String,
+    },
+    /// Forget an alias
+    Remove {
+        /// alias
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum FirewallSettingsActions {
+    /// Manage the Application Level Gateways rewriting addresses in protocol payloads
+    Alg {
+        #[command(subcommand)]
+        action: AlgActions,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AlgActions {
+    /// List the gateways and whether they are enabled
+    List,
+    /// Enable a gateway
+    Enable {
+        #[arg(value_enum)]
+        alg: Alg,
+    },
+    /// Disable a gateway, ex: the SIP one breaking self-hosted PBX
+    Disable {
+        #[arg(value_enum)]
+        alg: Alg,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Alg {
+    Sip,
+    Pptp,
+    Ipsec,
+    Rtsp,
+}
+
+impl From<Alg> for livebox::Alg {
+    fn from(alg: Alg) -> Self {
+        match alg {
+            Alg::Sip => livebox::Alg::Sip,
+            Alg::Pptp => livebox::Alg::Pptp,
+            Alg::Ipsec => livebox::Alg::Ipsec,
+            Alg::Rtsp => livebox::Alg::Rtsp,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum DmzActions {
+    /// Show the exposed host
+    Show,
+    /// Forward every port not used by NAT rules to a host
+    Set {
+        /// destination IP address
+        destination: String,
+    },
+    /// Stop exposing the host
+    Remove,
+}
+
+#[derive(Debug, Subcommand)]
+enum WanActions {
+    /// Manage the WAN access type of boxes supporting multiple uplinks
+    Mode {
+        #[command(subcommand)]
+        action: WanModeActions,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum WanModeActions {
+    /// Show the current WAN mode, the supported ones and the 4G backup dongle state
+    Show,
+    /// Switch the WAN access, the Internet connection is interrupted
+    Set {
+        #[arg(value_enum)]
+        access: WanAccess,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum WanAccess {
+    /// ADSL or VDSL line
+    Dsl,
+    /// GPON or SFP fiber
+    Fiber,
+    /// 4G backup dongle
+    Backup4g,
+}
+
+impl From<WanAccess> for livebox::WanAccess {
+    fn from(access: WanAccess) -> Self {
+        match access {
+            WanAccess::Dsl => livebox::WanAccess::Dsl,
+            WanAccess::Fiber => livebox::WanAccess::Fiber,
+            WanAccess::Backup4g => livebox::WanAccess::Backup4g,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum MulticastActions {
+    /// Show whether the IGMP proxy and snooping are enabled
+    Status,
+    /// Enable IGMP snooping, multicast is only sent to the devices that joined the groups
+    Enable,
+    /// Disable IGMP snooping, multicast floods every LAN port and WiFi station
+    Disable,
+}
+
+#[derive(Debug, Subcommand)]
+enum DiagActions {
+    /// Ping a host from the Livebox
+    Ping {
+        /// host name or IP address
+        host: String,
+
+        /// Number of echo requests
+        #[arg(short, long, default_value = "4")]
+        count: u32,
+
+        /// Timeout in milliseconds
+        #[arg(long, default_value = "1000")]
+        timeout: u32,
+    },
+    /// Trace the route to a host from the Livebox
+    Traceroute {
+        /// host name or IP address
+        host: String,
+
+        /// Maximum number of hops
+        #[arg(long, default_value = "30")]
+        max_hops: u32,
+
+        /// Timeout in milliseconds
+        #[arg(long, default_value = "5000")]
+        timeout: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum WifiActions {
+    /// Manage WiFi radios
+    Radio {
+        #[command(subcommand)]
+        action: RadioActions,
+    },
+    /// Scan neighbor access points
+    Scan {
+        /// Only scan the given band
+        #[arg(long, value_enum)]
+        band: Option<Band>,
+
+        /// Sort order
+        #[arg(long, value_enum, default_value = "signal")]
+        sort: ScanSort,
+
+        /// Seconds to wait for scan results
+        #[arg(long, default_value = "5")]
+        wait: u64,
+    },
+    /// Show associated stations with signal, rates and airtime statistics
+    Clients {
+        /// Refresh the output until interrupted
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between two refreshes
+        #[arg(long, default_value = "2", requires = "watch")]
+        interval: u64,
+    },
+    /// Print a QR code joining the WiFi network, or write it to a PNG or SVG file
+    Qr {
+        /// Share the guest network
+        #[arg(long)]
+        guest: bool,
+
+        /// Image file, `.png` or `.svg`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum ScanSort {
+    /// strongest signal first
+    Signal,
+    /// lowest channel first
+    Channel,
+}
+
+#[derive(Debug, Subcommand)]
+enum RadioActions {
+    /// Show radios channel, bandwidth and transmit power
+    Show,
+    /// Configure the radio operating on the given band
+    Set(RadioSettings),
+}
+
+#[derive(Debug, Args)]
+struct RadioSettings {
+    /// The radio frequency band
+    #[arg(long, value_enum)]
+    band: Band,
+
+    /// A fixed channel, disables automatic channel selection
+    #[arg(long, conflicts_with = "auto_channel")]
+    channel: Option<u16>,
+
+    /// Enable automatic channel selection
+    #[arg(long)]
+    auto_channel: bool,
+
+    /// The channel bandwidth in MHz (ex: `80`)
+    #[arg(long)]
+    bandwidth: Option<u16>,
+
+    /// The transmit power in percent
+    #[arg(long, value_parser = clap::value_parser!(i16).range(0..=100))]
+    power: Option<i16>,
+}
+
+impl From<&RadioSettings> for livebox::RadioConfig {
+    fn from(settings: &RadioSettings) -> Self {
+        livebox::RadioConfig {
+            channel: settings.channel,
+            auto_channel_enable: match settings.channel {
+                Some(_) => Some(false),
+                None => settings.auto_channel.then_some(true),
+            },
+            operating_channel_bandwidth: settings
+                .bandwidth
+                .map(|bandwidth| format!("{bandwidth}MHz")),
+            transmit_power: settings.power,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OnError {
+    /// apply the next changes
+    Continue,
+    /// leave the following changes unapplied
+    Stop,
+    /// revert the changes already applied
+    Rollback,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum ApiFlavor {
+    /// try the `/ws` API, then the legacy one
+    Auto,
+    /// `/ws` API of Livebox 4 and later
+    Ws,
+    /// `/authenticate` login and `/sysbus` calls of Livebox 2/3
+    Legacy,
+}
+
+impl From<ApiFlavor> for livebox::ApiFlavor {
+    fn from(api_flavor: ApiFlavor) -> Self {
+        match api_flavor {
+            ApiFlavor::Auto => livebox::ApiFlavor::Auto,
+            ApiFlavor::Ws => livebox::ApiFlavor::Ws,
+            ApiFlavor::Legacy => livebox::ApiFlavor::Legacy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[allow(clippy::enum_variant_names)]
+enum Band {
+    #[value(name = "2.4GHz")]
+    Band2_4GHz,
+    #[value(name = "5GHz")]
+    Band5GHz,
+    #[value(name = "6GHz")]
+    Band6GHz,
+}
+
+impl From<Band> for livebox::FrequencyBand {
+    fn from(band: Band) -> Self {
+        match band {
+            Band::Band2_4GHz => livebox::FrequencyBand::Band2_4GHz,
+            Band::Band5GHz => livebox::FrequencyBand::Band5GHz,
+            Band::Band6GHz => livebox::FrequencyBand::Band6GHz,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 struct NamedFirewallRule {
     /// rule identifier
@@ -142,42 +992,972 @@ struct NamedFirewallRule {
 }
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), anyhow::Error> {
+async fn main() {
     env_logger::init();
 
-    let args = CliArgs::parse();
+    let matches = CliArgs::command().get_matches();
+    let args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let command_path = permissions::command_path(&matches);
+    i18n::init(args.lang);
+
+    let base_url = args.livebox_api_baseurl.clone();
+    let no_color = args.output.no_color;
+    if let Err(err) = run(args, command_path).await {
+        eprintln!("{}", errors::render(&err, &base_url, no_color));
+        std::process::exit(1);
+    }
+}
+
+async fn run(mut args: CliArgs, command_path: String) -> Result<()> {
+    if args.check && !idempotent::supports_check(&args.command) {
+        CliArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                Message::CheckUnsupported(&command_path),
+            )
+            .exit()
+    }
+
+    if args.schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output_schema(&args.command)?)?
+        );
+        return Ok(());
+    }
+
+    let context_cache = config::cache_dir()
+        .map(|dir| livebox::ContextCache::new(dir.join("contexts.json")))
+        .ok_or_else(|| anyhow!(Message::NoCacheDirectory.to_string()))?;
+
+    let mut config = config::Config::load(args.config.as_ref())?;
+    let audit_log_path = config
+        .audit_log_path()
+        .ok_or_else(|| anyhow!(Message::NoStateDirectory.to_string()))?;
 
-    let client = livebox::ClientBuilder::default()
-        .with_base_url(args.livebox_api_baseurl)
-        .with_credentials(args.username, args.password)
-        .build()
+    config.rate_limit.min_interval_ms = args
+        .min_request_interval
+        .or(config.rate_limit.min_interval_ms);
+    config.rate_limit.max_concurrent = args
+        .max_concurrent_requests
+        .or(config.rate_limit.max_concurrent);
+    config.rate_limit.jitter_ms = args.request_jitter.or(config.rate_limit.jitter_ms);
+
+    let profile = match args.profile.as_ref().or(config.profile.as_ref()) {
+        Some(name) => config::load_profile(name)?,
+        None => livebox::Profile::default(),
+    };
+    let password = args.password.take();
+    let has_password = password.is_some();
+    let timings = args.timing.then(|| Arc::new(livebox::Timings::default()));
+    let mut client_builder = livebox::ClientBuilder::default()
+        .with_base_url(args.livebox_api_baseurl.clone())
+        .with_api_flavor(args.api_flavor.clone().into())
+        .with_profile(profile)
+        .with_rate_limit(config.rate_limit.to_rate_limit())
+        .with_pool(config.pool.to_pool())
+        .with_context_cache(context_cache.clone())
+        .with_audit_log(livebox::AuditLog::new(
+            audit_log_path.clone(),
+            command_path.clone(),
+        ))
+        .with_password_source(args.username.clone(), password_source(password));
+    if let Some(timings) = &timings {
+        client_builder = client_builder.with_timings(timings.clone());
+    }
+    let dry_run = args.check || args.command.dry_run();
+    // restricted accounts fail before the first change is sent
+    if !dry_run {
+        client_builder = client_builder.with_change_group(permissions::ADMIN_GROUP);
+    }
+    let session = Session {
+        builder: client_builder,
+        has_password,
+        client: OnceCell::new(),
+    };
+    let confirmation = Confirmation::new(args.yes, config.always_confirm.unwrap_or(true));
+    let shutdown = Shutdown::install()?;
+
+    aliases::resolve_hosts(&session, &mut args.command).await?;
+
+    // the session doesn't survive a reboot, which handles its own interruption and new session
+    let rebooting = matches!(args.command, Commands::Reboot(_) | Commands::FactoryReset);
+    let tracked = changes::Tracked::of(&args.command).filter(|_| !dry_run);
+    let command = async {
+        let before = match tracked {
+            Some(tracked) => Some(tracked.fetch(session.client().await?).await?),
+            None => None,
+        };
+        let outcome = run_command(
+            &args,
+            &session,
+            &context_cache,
+            &audit_log_path,
+            confirmation,
+            &shutdown,
+        )
         .await?;
+        if let (Some(tracked), Some(before)) = (tracked, before) {
+            let after = tracked.fetch(session.client().await?).await?;
+            tracked.report(&before, &after, &args.output)?;
+        }
+        anyhow::Ok(outcome)
+    };
+    let outcome = match rebooting {
+        true => Some(command.await),
+        false => shutdown.run(command).await,
+    };
+    let outcome = outcome.map(|outcome| {
+        outcome.map_err(|err| {
+            let groups = session.client.get().map(livebox::Client::groups);
+            match groups.and_then(|groups| permissions::missing_group(&command_path, groups)) {
+                Some(group) if err.is::<livebox::PermissionDenied>() => {
+                    err.context(Message::InsufficientPermissions(&command_path, group).to_string())
+                }
+                _ => err,
+            }
+        })
+    });
+    session.close(rebooting).await?;
+    report_timings(timings.as_deref());
+
+    let Some(outcome) = outcome else {
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    };
+    match outcome? {
+        Outcome::Json(response) => println!("{}", args.output.format(&response)?),
+        Outcome::Applied(report) => {
+            println!("{}", args.output.format(&report)?);
+            declarative::check_failures(&report)?;
+        }
+        Outcome::Exit(response, code) => {
+            println!("{}", args.output.format(&response)?);
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Outcome::Text(text) => print!("{text}"),
+        Outcome::ExitCode(code) => std::process::exit(code),
+    }
+    Ok(())
+}
+
+/// Connection settings, and the session opened by the first command needing one
+pub struct Session {
+    builder: livebox::ClientBuilder,
+    has_password: bool,
+    client: OnceCell<livebox::Client>,
+}
+
+impl Session {
+    /// Connection settings, exits when no password is given
+    fn builder(&self) -> &livebox::ClientBuilder {
+        if !self.has_password {
+            CliArgs::command()
+                .error(ErrorKind::MissingRequiredArgument, Message::MissingPassword)
+                .exit()
+        }
+        &self.builder
+    }
+
+    /// Session opened on first use
+    pub async fn client(&self) -> Result<&livebox::Client> {
+        self.client
+            .get_or_try_init(|| self.builder().clone().build())
+            .await
+    }
+
+    /// Ends the session if opened, it is only forgotten when the Livebox reboots
+    async fn close(self, rebooting: bool) -> Result<()> {
+        match self.client.into_inner() {
+            Some(client) if rebooting => client.forget(),
+            Some(client) => client.logout().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// How the result of a command is printed
+enum Outcome {
+    /// response in the output format
+    Json(Value),
+    /// report of applied changes, failing once printed when some changes failed
+    Applied(Value),
+    /// response in the output format, then exit with the code when not 0
+    Exit(Value, i32),
+    /// text printed as is
+    Text(String),
+    /// exit code of a command printing its own output
+    ExitCode(i32),
+}
+
+/// Source of the password given on the command line or by `LIVEBOX_PASSWORD`.
+///
+/// The variable is read again at each login rather than kept in memory. A `--password` stays in
+/// the process arguments whatever is done with it, it can't be zeroized and is kept by the source.
+fn password_source(
+    password: Option<SecretString>,
+) -> impl Fn() -> Result<SecretString> + Send + Sync + 'static {
+    let from_env = password.as_ref().is_some_and(|password| {
+        env::var("LIVEBOX_PASSWORD").is_ok_and(|value| value == password.expose_secret())
+    });
+    let given = password.filter(|_| !from_env);
+    move || {
+        let password = match &given {
+            Some(password) => Some(password.clone()),
+            None if from_env => env::var("LIVEBOX_PASSWORD").ok().map(SecretString::from),
+            None => None,
+        };
+        password.ok_or_else(|| anyhow!(Message::MissingPassword.to_string()))
+    }
+}
+
+fn report_timings(timings: Option<&livebox::Timings>) {
+    if let Some(timings) = timings {
+        eprintln!("{timings}");
+    }
+}
+
+fn show_audit_log(path: &Path, limit: Option<usize>, user: Option<&str>) -> Result<Value> {
+    let mut entries: Vec<_> = livebox::AuditLog::load(path)?
+        .into_iter()
+        .filter(|entry| user.is_none_or(|user| entry.user == user))
+        .collect();
+    if let Some(limit) = limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+    Ok(serde_json::to_value(entries)?)
+}
+
+fn manage_aliases(action: &AliasActions) -> Result<Value> {
+    let mut aliases = aliases::Aliases::load()?;
+    match action {
+        AliasActions::List => {}
+        AliasActions::Set { mac, name } => aliases.set(mac, name.clone())?,
+        AliasActions::Remove { name } => aliases.remove(name)?,
+    }
+    Ok(serde_json::to_value(aliases.list())?)
+}
+
+async fn revoke_sessions(
+    context_cache: &livebox::ContextCache,
+    context_id: Option<&str>,
+) -> Result<Value> {
+    let mut released = Vec::new();
+    for context in context_cache.load()? {
+        if context_id.is_some_and(|id| id != context.context_id) {
+            continue;
+        }
+        context.release().await?;
+        context_cache.remove(&context.context_id)?;
+        released.push(context.context_id);
+    }
+    Ok(serde_json::to_value(released)?)
+}
 
-    let response = match args.command {
-        Commands::Exec { service, method } => client.execute(service, method).await?,
-        Commands::NAT { action } => match action {
-            FirewallActions::List => client.list_nat_rules().await?,
-            FirewallActions::Add(rule) => client.add_nat_rule(rule.into()).await?,
-            FirewallActions::Enable(rule) => client.enable_nat_rule(rule.id).await?,
-            FirewallActions::Disable(rule) => client.disable_nat_rule(rule.id).await?,
-            FirewallActions::Remove(rule) => client.remove_nat_rule(rule.id).await?,
+async fn run_command(
+    args: &CliArgs,
+    session: &Session,
+    context_cache: &livebox::ContextCache,
+    audit_log_path: &Path,
+    confirmation: Confirmation,
+    shutdown: &Shutdown,
+) -> Result<Outcome> {
+    let check = args.check;
+    Ok(Outcome::Json(match &args.command {
+        Commands::Exec {
+            service,
+            method,
+            model,
+            parameters,
+            from,
+            for_each,
+            concurrency,
+            baseline,
+        } => {
+            let client = session.client().await?;
+            let normalize = |response| match model {
+                Some(model) => model.normalize(service, method, response),
+                None => Ok(response),
+            };
+            let response = match (from, for_each) {
+                (Some(from), Some(path)) => {
+                    let results = pipeline::for_each(
+                        client,
+                        from,
+                        path,
+                        (service, method),
+                        parameters,
+                        *concurrency,
+                        normalize,
+                    )
+                    .await?;
+                    // failed calls are reported by the exit code, once the results are printed
+                    if results.iter().any(|result| result.error.is_some()) {
+                        return Ok(Outcome::Exit(serde_json::to_value(results)?, 1));
+                    }
+                    serde_json::to_value(results)?
+                }
+                _ => {
+                    let parameters = parameters
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.as_str()))
+                        .collect();
+                    normalize(
+                        client
+                            .execute_with_parameters(service, method, parameters)
+                            .await?,
+                    )?
+                }
+            };
+            match baseline {
+                // drift is reported by the exit code, once the patch is printed
+                Some(baseline) => {
+                    let patch = baseline::compare(baseline, &response)?;
+                    let code = match patch.is_empty() {
+                        true => 0,
+                        false => DRIFT_EXIT_CODE,
+                    };
+                    return Ok(Outcome::Exit(serde_json::to_value(patch)?, code));
+                }
+                None => response,
+            }
+        }
+        Commands::Pipeline { file, steps } => {
+            let pipeline = match file {
+                Some(file) => pipeline::Pipeline::from_file(file)?,
+                None => pipeline::Pipeline::from_args(steps)?,
+            };
+            pipeline.run(session.client().await?).await?
+        }
+        Commands::Raw { path, body } => session.client().await?.raw(path, body).await?,
+        Commands::Nat { action } => {
+            let client = session.client().await?;
+            match action {
+                FirewallActions::List => client.list_nat_rules().await?,
+                FirewallActions::Add(rule) => {
+                    if let Some((id, destination)) = rule.tracked() {
+                        nat::track(client, id, &destination).await?;
+                    }
+                    client.add_nat_rule(rule.clone().into()).await?
+                }
+                FirewallActions::AddTemp { rule, expires } => {
+                    if let Some((id, destination)) = rule.tracked() {
+                        nat::track(client, id, &destination).await?;
+                    }
+                    let id = rule.id.clone();
+                    let rule = rule.clone().into();
+                    serde_json::to_value(nat::add_temporary(client, id, rule, *expires).await?)?
+                }
+                FirewallActions::Gc => serde_json::to_value(nat::remove_expired(client).await?)?,
+                FirewallActions::Remove(rule) => {
+                    confirmation
+                        .require(Message::ConfirmRemoveRule(&rule.id))
+                        .await?;
+                    client.remove_nat_rule(rule.id.clone()).await?
+                }
+                FirewallActions::Prune(options) => {
+                    nat::prune(
+                        client,
+                        options.disabled,
+                        options.destination_unreachable,
+                        confirmation,
+                    )
+                    .await?
+                }
+                FirewallActions::Enable(rule) => {
+                    idempotent::set_nat_rule_enable(client, &rule.id, true, check).await?
+                }
+                FirewallActions::Disable(rule) => {
+                    idempotent::set_nat_rule_enable(client, &rule.id, false, check).await?
+                }
+                FirewallActions::FromCompose {
+                    file,
+                    host,
+                    dry_run,
+                    on_error,
+                } => {
+                    let report =
+                        nat::from_compose(client, file, host.clone(), *dry_run || check, *on_error)
+                            .await?;
+                    return Ok(Outcome::Applied(report));
+                }
+            }
+        }
+        Commands::Wifi { action } => {
+            let client = session.client().await?;
+            match action {
+                WifiActions::Radio { action } => match action {
+                    RadioActions::Show => serde_json::to_value(client.list_radios().await?)?,
+                    RadioActions::Set(settings) => {
+                        let band = settings.band.clone().into();
+                        idempotent::configure_radio(client, band, settings.into(), check).await?
+                    }
+                },
+                WifiActions::Scan { band, sort, wait } => {
+                    let mut neighbors = client
+                        .scan_neighbors(band.clone().map(Into::into), Duration::from_secs(*wait))
+                        .await?;
+                    match sort {
+                        ScanSort::Signal => neighbors.sort_by_key(|ap| Reverse(ap.scan.rssi)),
+                        ScanSort::Channel => {
+                            neighbors.sort_by_key(|ap| (ap.scan.channel, Reverse(ap.scan.rssi)))
+                        }
+                    }
+                    serde_json::to_value(neighbors)?
+                }
+                WifiActions::Clients { watch: false, .. } => {
+                    serde_json::to_value(client.list_stations().await?)?
+                }
+                WifiActions::Clients {
+                    watch: true,
+                    interval,
+                } => loop {
+                    let stations = serde_json::to_value(client.list_stations().await?)?;
+                    let output = args.output.format(&stations)?;
+                    if io::stdout().is_terminal() {
+                        print!("{CLEAR_SCREEN}");
+                    }
+                    println!("{output}");
+                    tokio::time::sleep(Duration::from_secs(*interval)).await;
+                },
+                WifiActions::Qr { guest, output } => {
+                    let access_point = client.get_access_point(*guest).await?;
+                    return Ok(Outcome::Text(wifi_qr_code(
+                        &access_point,
+                        output.as_deref(),
+                    )?));
+                }
+            }
+        }
+        Commands::Dhcp {
+            action: DhcpActions::Options { action },
+        } => {
+            let client = session.client().await?;
+            match action {
+                DhcpOptionActions::List => serde_json::to_value(client.list_dhcp_options().await?)?,
+                DhcpOptionActions::Set { option, value, hex } => {
+                    let value = match hex {
+                        true if value.len() % 2 == 0
+                            && value.chars().all(|c| c.is_ascii_hexdigit()) =>
+                        {
+                            value.clone()
+                        }
+                        true => return Err(anyhow!(Message::InvalidHexValue(value).to_string())),
+                        false => value.bytes().map(|byte| format!("{byte:02x}")).collect(),
+                    };
+                    client.set_dhcp_option(*option, value).await?
+                }
+                DhcpOptionActions::Unset { option } => client.remove_dhcp_option(*option).await?,
+            }
+        }
+        Commands::Devices { action } => match action {
+            DevicesActions::List => {
+                let devices = session.client().await?.list_devices().await?;
+                serde_json::to_value(aliases::Aliases::load()?.annotate(devices))?
+            }
+            DevicesActions::Export { format } => {
+                let inventory =
+                    inventory::export(session.client().await?, *format, args.output.redact).await?;
+                return Ok(Outcome::Text(inventory));
+            }
+            DevicesActions::Alias { action } => manage_aliases(action)?,
+        },
+        Commands::Lan { action } => match action {
+            LanActions::Neighbours => {
+                serde_json::to_value(session.client().await?.list_neighbours().await?)?
+            }
+        },
+        Commands::Wan {
+            action: WanActions::Mode { action },
+        } => {
+            let client = session.client().await?;
+            match action {
+                WanModeActions::Show => serde_json::to_value(client.get_wan_mode().await?)?,
+                WanModeActions::Set { access } => {
+                    idempotent::set_wan_access(client, (*access).into(), check, confirmation)
+                        .await?
+                }
+            }
+        }
+        Commands::Multicast { action } => {
+            let client = session.client().await?;
+            match action {
+                MulticastActions::Status => {
+                    serde_json::to_value(client.get_multicast_status().await?)?
+                }
+                MulticastActions::Enable => {
+                    idempotent::set_igmp_snooping(client, true, check).await?
+                }
+                MulticastActions::Disable => {
+                    idempotent::set_igmp_snooping(client, false, check).await?
+                }
+            }
+        }
+        Commands::Diag { action } => {
+            let client = session.client().await?;
+            match action {
+                DiagActions::Ping {
+                    host,
+                    count,
+                    timeout,
+                } => client.ping(host.clone(), *count, *timeout).await?,
+                DiagActions::Traceroute {
+                    host,
+                    max_hops,
+                    timeout,
+                } => client.traceroute(host.clone(), *max_hops, *timeout).await?,
+            }
+        }
+        Commands::Firewall {
+            action: FirewallSettingsActions::Alg { action },
+        } => {
+            let client = session.client().await?;
+            match action {
+                AlgActions::List => serde_json::to_value(client.list_algs().await?)?,
+                AlgActions::Enable { alg } => {
+                    idempotent::set_alg_enable(client, (*alg).into(), true, check).await?
+                }
+                AlgActions::Disable { alg } => {
+                    idempotent::set_alg_enable(client, (*alg).into(), false, check).await?
+                }
+            }
+        }
+        Commands::Dmz { action } => {
+            let client = session.client().await?;
+            match action {
+                DmzActions::Show => serde_json::to_value(client.get_dmz().await?)?,
+                DmzActions::Set { destination } => {
+                    idempotent::set_dmz(client, destination, check, confirmation).await?
+                }
+                DmzActions::Remove => idempotent::remove_dmz(client, check).await?,
+            }
+        }
+        Commands::Reboot(options) => {
+            let client = session.client().await?;
+            confirmation.require(Message::ConfirmReboot).await?;
+            let builder = session.builder().clone();
+            reboot::reboot(client, builder, options.clone().into(), shutdown).await?
+        }
+        Commands::FactoryReset => {
+            let client = session.client().await?;
+            confirmation.require(Message::ConfirmFactoryReset).await?;
+            client.factory_reset().await?
+        }
+        Commands::Healthcheck { probes, dns_name } => {
+            let builder = session.builder().clone();
+            let report =
+                healthcheck::check(builder, &args.livebox_api_baseurl, probes, dns_name).await;
+            println!("{report}");
+            let code = report.status().exit_code();
+            return Ok(Outcome::Exit(serde_json::to_value(&report)?, code));
+        }
+        Commands::Daemon {
+            rules,
+            interval,
+            generate_systemd_unit: true,
+        } => {
+            return Ok(Outcome::Text(systemd::unit(
+                args,
+                rules.as_deref(),
+                *interval,
+            )?))
+        }
+        Commands::Daemon {
+            rules,
+            interval,
+            generate_systemd_unit: false,
+        } => {
+            let rules = rules.as_deref().map(rules::Rules::from_file).transpose()?;
+            let client = session.client().await?;
+            daemon::run(
+                client,
+                rules.as_ref(),
+                Duration::from_secs(*interval),
+                args.output.redact,
+            )
+            .await?
+        }
+        Commands::UiProxy { listen } => {
+            let client = session.client().await?;
+            ui_proxy::serve(client, &args.livebox_api_baseurl, *listen).await?;
+            return Ok(Outcome::Text(String::new()));
+        }
+        Commands::Session { action } => match action {
+            SessionActions::List => {
+                // session cookies stay in the cache file
+                let contexts: Vec<_> = context_cache
+                    .load()?
+                    .into_iter()
+                    .map(|context| livebox::CachedContext {
+                        cookie: None,
+                        ..context
+                    })
+                    .collect();
+                serde_json::to_value(contexts)?
+            }
+            SessionActions::Revoke { context_id, .. } => {
+                revoke_sessions(context_cache, context_id.as_deref()).await?
+            }
+        },
+        Commands::Schedule { action } => match action {
+            ScheduleActions::Add { cron, command } => {
+                let args = ["livebox-cli"]
+                    .into_iter()
+                    .chain(command.iter().map(String::as_str));
+                if let Err(err) = CliArgs::try_parse_from(args) {
+                    err.exit()
+                }
+                let mut schedule = schedule::Schedule::load()?;
+                serde_json::to_value(schedule.add(cron.clone(), command.clone())?)?
+            }
+            ScheduleActions::List => serde_json::to_value(schedule::Schedule::load()?.tasks())?,
+            ScheduleActions::Remove { id } => {
+                serde_json::to_value(schedule::Schedule::load()?.remove(*id)?)?
+            }
+            // scheduled commands are run by other processes with the same settings
+            ScheduleActions::Run => {
+                schedule::run(&systemd::connection_args(args)?, session.builder()).await?
+            }
+        },
+        Commands::Audit { action } => match action {
+            AuditActions::Show { limit, user } => {
+                show_audit_log(audit_log_path, *limit, user.as_deref())?
+            }
         },
+        Commands::Admin { action } => match action {
+            AdminActions::ChangePassword { password_stdin } => {
+                let client = session.client().await?;
+                let new_password = match password_stdin {
+                    true => prompt::password_from_stdin().await?,
+                    false => prompt::new_password().await?,
+                };
+                admin::change_password(client, session.builder(), new_password).await?
+            }
+        },
+        Commands::Whoami => {
+            let client = session.client().await?;
+            serde_json::to_value(permissions::WhoAmI::new(
+                client.username(),
+                client.groups(),
+                &CliArgs::command(),
+            ))?
+        }
+        Commands::Setup => setup::setup(session.client().await?, session.builder()).await?,
+        Commands::Plugins => serde_json::to_value(plugins::discover())?,
+        #[cfg(feature = "self-update")]
+        Commands::SelfUpdate => update::self_update(check).await?,
+        Commands::Plugin(plugin_args) => {
+            let client = session.client().await?;
+            let status = plugins::run(client, &args.livebox_api_baseurl, plugin_args.clone())?;
+            return Ok(Outcome::ExitCode(status.code().unwrap_or(1)));
+        }
+        Commands::Apply {
+            file,
+            dry_run,
+            on_error,
+        } => {
+            let desired_state = declarative::State::from_file(file)?;
+            let client = session.client().await?;
+            let report =
+                declarative::apply(client, &desired_state, *dry_run || check, *on_error).await?;
+            return Ok(Outcome::Applied(report));
+        }
+        Commands::Snapshot { file } => {
+            let state = declarative::snapshot(session.client().await?).await?;
+            state.to_file(file)?;
+            state.masked()?
+        }
+        Commands::Diff { file } => {
+            let desired_state = declarative::State::from_file(file)?;
+            declarative::diff(session.client().await?, &desired_state).await?
+        }
+    }))
+}
+
+/// QR code joining the WiFi network, printed in the terminal or written to an image file whose
+/// format is given by the extension
+fn wifi_qr_code(access_point: &livebox::AccessPointView, output: Option<&Path>) -> Result<String> {
+    let passphrase = match access_point.security.mode_enabled.as_str() {
+        "None" => "",
+        _ => &access_point.security.key_pass_phrase,
     };
-    client.logout().await?;
-
-    let output = match args.query {
-        Some(path) => path
-            .query(&response)
-            .exactly_one()
-            .map_err(|err| anyhow!(err).context("No match for given JsonPath"))?,
-        None => &response,
+    let qr_code = qr::QrCode::encode(qr::wifi_payload(&access_point.ssid, passphrase).as_bytes())?;
+    let Some(path) = output else {
+        return Ok(qr_code.to_ansi());
     };
-    let output = if args.output_raw_strings && output.is_string() {
-        output.as_str().unwrap().to_string()
-    } else {
-        serde_json::to_string_pretty(output)?
+    let image = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("png") => qr_code.to_png(),
+        Some("svg") => qr_code.to_svg().into_bytes(),
+        _ => return Err(anyhow!(Message::UnsupportedImageFormat(path).to_string())),
     };
+    fs::write(path, image).with_context(|| Message::UnableToCreate(path).to_string())?;
+    Ok(String::new())
+}
 
-    println!("{}", output);
-    Ok(())
+fn output_schema(command: &Commands) -> Result<Schema> {
+    let generator = SchemaSettings::default().for_serialize().into_generator();
+    Ok(match command {
+        Commands::Nat {
+            action: FirewallActions::Prune(_),
+        } => generator.into_root_schema_for::<nat::PruneReport>(),
+        Commands::Nat {
+            action: FirewallActions::FromCompose { .. },
+        } => generator.into_root_schema_for::<declarative::ApplyReport>(),
+        Commands::Nat {
+            action: FirewallActions::AddTemp { .. },
+        } => generator.into_root_schema_for::<nat::TemporaryRule>(),
+        Commands::Nat {
+            action: FirewallActions::Gc,
+        } => generator.into_root_schema_for::<Vec<nat::TemporaryRule>>(),
+        Commands::Nat {
+            action: FirewallActions::Enable(_) | FirewallActions::Disable(_),
+        }
+        | Commands::Firewall {
+            action:
+                FirewallSettingsActions::Alg {
+                    action: AlgActions::Enable { .. } | AlgActions::Disable { .. },
+                },
+        }
+        | Commands::Multicast {
+            action: MulticastActions::Enable | MulticastActions::Disable,
+        }
+        | Commands::Wan {
+            action:
+                WanActions::Mode {
+                    action: WanModeActions::Set { .. },
+                },
+        }
+        | Commands::Dmz {
+            action: DmzActions::Set { .. } | DmzActions::Remove,
+        }
+        | Commands::Wifi {
+            action: WifiActions::Radio {
+                action: RadioActions::Set(_),
+            },
+        } => generator.into_root_schema_for::<idempotent::ChangeReport>(),
+        Commands::Wifi { action } => match action {
+            WifiActions::Radio {
+                action: RadioActions::Show,
+            } => generator.into_root_schema_for::<HashMap<String, livebox::RadioView>>(),
+            WifiActions::Scan { .. } => {
+                generator.into_root_schema_for::<Vec<livebox::NeighborAccessPoint>>()
+            }
+            WifiActions::Clients { .. } => {
+                generator.into_root_schema_for::<Vec<livebox::AssociatedStation>>()
+            }
+            WifiActions::Qr { .. } => return Err(anyhow!(Message::TextOutput.to_string())),
+            _ => return Err(untyped_output()),
+        },
+        Commands::Lan {
+            action: LanActions::Neighbours,
+        } => generator.into_root_schema_for::<Vec<livebox::Neighbour>>(),
+        Commands::Session {
+            action: SessionActions::List,
+        } => generator.into_root_schema_for::<Vec<livebox::CachedContext>>(),
+        Commands::Audit {
+            action: AuditActions::Show { .. },
+        } => generator.into_root_schema_for::<Vec<livebox::AuditEntry>>(),
+        Commands::Schedule {
+            action: ScheduleActions::Add { .. } | ScheduleActions::Remove { .. },
+        } => generator.into_root_schema_for::<schedule::Task>(),
+        Commands::Schedule {
+            action: ScheduleActions::List,
+        } => generator.into_root_schema_for::<Vec<schedule::Task>>(),
+        Commands::Exec {
+            baseline: Some(_), ..
+        } => generator.into_root_schema_for::<Vec<baseline::Operation>>(),
+        Commands::Dmz {
+            action: DmzActions::Show,
+        } => generator.into_root_schema_for::<Option<livebox::DmzView>>(),
+        Commands::Wan {
+            action: WanActions::Mode {
+                action: WanModeActions::Show,
+            },
+        } => generator.into_root_schema_for::<livebox::WanModeView>(),
+        Commands::Multicast {
+            action: MulticastActions::Status,
+        } => generator.into_root_schema_for::<livebox::MulticastStatus>(),
+        Commands::Firewall {
+            action:
+                FirewallSettingsActions::Alg {
+                    action: AlgActions::List,
+                },
+        } => generator.into_root_schema_for::<BTreeMap<String, livebox::AlgView>>(),
+        Commands::Dhcp {
+            action:
+                DhcpActions::Options {
+                    action: DhcpOptionActions::List,
+                },
+        } => generator.into_root_schema_for::<Vec<livebox::DhcpOptionView>>(),
+        Commands::Devices { action } => match action {
+            DevicesActions::List => generator.into_root_schema_for::<Vec<aliases::AliasedDevice>>(),
+            DevicesActions::Alias { .. } => {
+                generator.into_root_schema_for::<BTreeMap<String, String>>()
+            }
+            DevicesActions::Export { .. } => return Err(anyhow!(Message::TextOutput.to_string())),
+        },
+        Commands::Reboot(_) => generator.into_root_schema_for::<reboot::RebootReport>(),
+        Commands::Healthcheck { .. } => {
+            generator.into_root_schema_for::<healthcheck::HealthReport>()
+        }
+        Commands::Apply { .. } => generator.into_root_schema_for::<declarative::ApplyReport>(),
+        Commands::Snapshot { .. } => generator.into_root_schema_for::<declarative::State>(),
+        Commands::Diff { .. } => generator.into_root_schema_for::<declarative::DiffReport>(),
+        Commands::Admin {
+            action: AdminActions::ChangePassword { .. },
+        } => generator.into_root_schema_for::<admin::PasswordChangeReport>(),
+        Commands::Setup => generator.into_root_schema_for::<setup::SetupReport>(),
+        Commands::Whoami => generator.into_root_schema_for::<permissions::WhoAmI>(),
+        #[cfg(feature = "self-update")]
+        Commands::SelfUpdate => generator.into_root_schema_for::<update::UpdateReport>(),
+        _ => return Err(untyped_output()),
+    })
+}
+
+fn parse_json(body: &str) -> serde_json::Result<Value> {
+    serde_json::from_str(body)
+}
+
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || Message::InvalidDuration(duration).to_string();
+    let (value, unit) = duration.split_at(
+        duration
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(duration.len()),
+    );
+    let unit = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    Ok(Duration::from_secs(
+        value.checked_mul(unit).ok_or_else(invalid)?,
+    ))
+}
+
+fn parse_call(call: &str) -> Result<(String, String), String> {
+    call.split_once(':')
+        .map(|(service, method)| (service.to_string(), method.to_string()))
+        .ok_or_else(|| Message::InvalidCall(call).to_string())
+}
+
+fn parse_model(name: &str) -> Result<&'static livebox::Model, String> {
+    livebox::find_model(name).ok_or_else(|| {
+        let names: Vec<_> = livebox::MODELS.iter().map(|model| model.name).collect();
+        Message::UnknownModel(name, &names.join(", ")).to_string()
+    })
+}
+
+fn parse_mac(mac: &str) -> Result<String, String> {
+    let bytes: Vec<_> = mac.split([':', '-']).collect();
+    match bytes.len() == 6
+        && bytes
+            .iter()
+            .all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        true => Ok(bytes.join(":").to_uppercase()),
+        false => Err(Message::InvalidMac(mac).to_string()),
+    }
+}
+
+fn untyped_output() -> anyhow::Error {
+    anyhow!(Message::UntypedOutput.to_string())
+}
+
+fn parse_template(template: &str) -> Result<String, String> {
+    Template::compile(template)
+        .map(|_| template.to_string())
+        .map_err(|err| err.to_string())
+}
+
+impl OutputArgs {
+    fn format(&self, response: &Value) -> Result<String> {
+        let redacted;
+        let response = match self.redact {
+            true => {
+                redacted = redact::redact(response.clone());
+                &redacted
+            }
+            false => response,
+        };
+        let output = match &self.query {
+            Some(path) => path
+                .query(response)
+                .exactly_one()
+                .map_err(|err| anyhow!(err).context(Message::NoJsonPathMatch.to_string()))?,
+            None => response,
+        };
+        if let Some(template) = &self.format {
+            let mut handlebars = Handlebars::new();
+            handlebars.register_escape_fn(no_escape);
+            return Ok(handlebars.render_template(template, output)?);
+        }
+        Ok(if self.raw_strings && output.is_string() {
+            output.as_str().unwrap().to_string()
+        } else {
+            serde_json::to_string_pretty(output)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let cases = [
+            ("90", 90),
+            ("90s", 90),
+            ("30m", 30 * 60),
+            ("2h", 2 * 60 * 60),
+            ("1d", 24 * 60 * 60),
+            ("0s", 0),
+        ];
+        for (duration, seconds) in cases {
+            assert_eq!(
+                parse_duration(duration),
+                Ok(Duration::from_secs(seconds)),
+                "{duration}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        let max = u64::MAX.to_string();
+        let overflow = format!("{}d", u64::MAX / (24 * 60 * 60) + 1);
+        for duration in [
+            "",
+            "s",
+            "1w",
+            "1.5h",
+            "-1s",
+            "1 h",
+            "h1",
+            &overflow,
+            &format!("{max}0"),
+        ] {
+            assert_eq!(
+                parse_duration(duration),
+                Err(Message::InvalidDuration(duration).to_string()),
+                "{duration}"
+            );
+        }
+        assert_eq!(parse_duration(&max), Ok(Duration::from_secs(u64::MAX)));
+    }
+
+    #[test]
+    fn keeps_the_password_given_on_the_command_line() {
+        let source = password_source(Some("command-line-secret".to_string().into()));
+        for _ in 0..2 {
+            assert_eq!(source().unwrap().expose_secret(), "command-line-secret");
+        }
+        let err = password_source(None)().unwrap_err();
+        assert_eq!(err.to_string(), Message::MissingPassword.to_string());
+    }
 }
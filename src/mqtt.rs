@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::i18n::Message;
+
+const DEFAULT_PORT: u16 = 1883;
+const CLIENT_ID: &str = "livebox-cli";
+const KEEP_ALIVE_SECONDS: u16 = 60;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const DISCONNECT: u8 = 0xe0;
+
+/// Message published to an MQTT 3.1.1 broker, at most once and without authentication
+#[derive(Deserialize)]
+pub struct Publication {
+    /// broker address (ex: `mqtt.lan` or `mqtt.lan:1883`)
+    pub broker: String,
+    pub topic: String,
+    #[serde(default)]
+    pub retain: bool,
+}
+
+impl Publication {
+    pub async fn publish(&self, payload: &[u8]) -> Result<()> {
+        let address = match self.broker.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => self.broker.clone(),
+            _ => format!("{}:{DEFAULT_PORT}", self.broker),
+        };
+        let mut stream = TcpStream::connect(address).await?;
+
+        let mut connect = Vec::new();
+        put_string(&mut connect, "MQTT");
+        // protocol level 4, clean session
+        connect.extend([4, 0x02]);
+        connect.extend(KEEP_ALIVE_SECONDS.to_be_bytes());
+        put_string(&mut connect, CLIENT_ID);
+        stream.write_all(&packet(CONNECT, &connect)).await?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack).await?;
+        match connack {
+            [CONNACK, 2, _, 0] => {}
+            [CONNACK, 2, _, code] => {
+                return Err(anyhow!(
+                    Message::BrokerRefused(&self.broker, code).to_string()
+                ))
+            }
+            _ => return Err(anyhow!(Message::NotABroker(&self.broker).to_string())),
+        }
+
+        let mut publish = Vec::new();
+        put_string(&mut publish, &self.topic);
+        publish.extend(payload);
+        stream
+            .write_all(&packet(PUBLISH | u8::from(self.retain), &publish))
+            .await?;
+        stream.write_all(&packet(DISCONNECT, &[])).await?;
+        Ok(())
+    }
+}
+
+fn put_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend((value.len() as u16).to_be_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+/// Control packet with its variable length encoded remaining length
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remaining length bytes of a packet with a body of the length
+    fn remaining_length(length: usize) -> Vec<u8> {
+        let packet = packet(PUBLISH, &vec![0; length]);
+        assert_eq!(packet[0], PUBLISH);
+        packet[1..packet.len() - length].to_vec()
+    }
+
+    #[test]
+    fn encodes_the_remaining_length() {
+        assert_eq!(remaining_length(0), [0x00]);
+        assert_eq!(remaining_length(127), [0x7f]);
+        assert_eq!(remaining_length(128), [0x80, 0x01]);
+        assert_eq!(remaining_length(16383), [0xff, 0x7f]);
+        assert_eq!(remaining_length(16384), [0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn prefixes_strings_with_their_length() {
+        let mut buffer = Vec::new();
+        put_string(&mut buffer, "MQTT");
+        assert_eq!(buffer, [0, 4, b'M', b'Q', b'T', b'T']);
+    }
+}
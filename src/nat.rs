@@ -0,0 +1,325 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    net::UdpSocket,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    compose, config,
+    declarative::{self, Change},
+    i18n::Message,
+    livebox::{Client, SetPortFowardingParams},
+    prompt::Confirmation,
+    OnError,
+};
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum PruneReason {
+    Disabled,
+    DestinationUnreachable,
+}
+
+impl PruneReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PruneReason::Disabled => "disabled",
+            PruneReason::DestinationUnreachable => "destination_unreachable",
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PrunedRule {
+    id: String,
+    destination: String,
+    reason: PruneReason,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PruneReport {
+    removed: Vec<PrunedRule>,
+}
+
+/// Removes disabled rules and rules forwarding to hosts missing from the device list, once
+/// confirmed.
+pub async fn prune(
+    client: &Client,
+    disabled: bool,
+    destination_unreachable: bool,
+    confirmation: Confirmation,
+) -> Result<Value> {
+    let known_addresses: HashSet<String> = match destination_unreachable {
+        true => client
+            .list_devices()
+            .await?
+            .into_iter()
+            .map(|device| device.ip_address)
+            .filter(|address| !address.is_empty())
+            .collect(),
+        false => HashSet::new(),
+    };
+    let mut rules = client.structured_nat_rules().await?;
+    rules.sort_by(|a, b| a.id.cmp(&b.id));
+    let candidates: Vec<_> = rules
+        .into_iter()
+        .filter_map(|rule| {
+            let reason = if disabled && !rule.enable {
+                PruneReason::Disabled
+            } else if destination_unreachable
+                && !known_addresses.contains(&rule.destination_ip_address)
+            {
+                PruneReason::DestinationUnreachable
+            } else {
+                return None;
+            };
+            Some(PrunedRule {
+                id: rule.id,
+                destination: rule.destination_ip_address,
+                reason,
+            })
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        return Ok(serde_json::to_value(PruneReport {
+            removed: candidates,
+        })?);
+    }
+    if confirmation.enabled() {
+        for rule in &candidates {
+            eprintln!(
+                "{} -> {} ({})",
+                rule.id,
+                rule.destination,
+                rule.reason.as_str()
+            );
+        }
+    }
+    let question = Message::ConfirmPrune(candidates.len()).to_string();
+    if !confirmation.confirm(question).await? {
+        return Ok(serde_json::to_value(PruneReport { removed: vec![] })?);
+    }
+    let mut removed = Vec::new();
+    for rule in candidates {
+        client.remove_nat_rule(rule.id.clone()).await?;
+        removed.push(rule);
+    }
+    Ok(serde_json::to_value(PruneReport { removed })?)
+}
+
+/// Forwards the ports published by a compose file to the Docker host, by default the address
+/// this host uses to reach the Livebox. Existing rules are updated, other rules are kept.
+pub async fn from_compose(
+    client: &Client,
+    file: &Path,
+    host: Option<String>,
+    dry_run: bool,
+    on_error: OnError,
+) -> Result<Value> {
+    let destination = match host {
+        Some(host) => host,
+        None => local_address(client.base_url_ws())?,
+    };
+    let rules = compose::nat_rules(file, &destination)?;
+    let actual_rules = client.structured_nat_rules().await?;
+    let changes = rules
+        .into_iter()
+        .filter(|rule| {
+            actual_rules
+                .iter()
+                .find(|actual| declarative::nat_rule_matches(actual, &rule.id))
+                .is_none_or(|actual| declarative::nat_rule_differs(actual, rule))
+        })
+        .map(|rule| Change::SetNatRule { rule })
+        .collect();
+    declarative::apply_changes(client, changes, dry_run, on_error).await
+}
+
+/// Address of the interface routing to the Livebox, no packet is sent.
+fn local_address(base_url: &str) -> Result<String> {
+    let url = Url::parse(base_url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!(Message::NoHost(url.as_str()).to_string()))?;
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect((host, url.port_or_known_default().unwrap_or(80)))?;
+    Ok(socket.local_addr()?.ip().to_string())
+}
+
+/// Rules to remove once expired, kept in `temporary-rules.toml` of the state directory
+struct TemporaryRules {
+    path: PathBuf,
+    /// Expiry, in seconds since the Unix epoch, by rule id
+    expiries: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TemporaryRule {
+    id: String,
+    /// Seconds since the Unix epoch
+    expires_at: u64,
+}
+
+impl TemporaryRule {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl TemporaryRules {
+    fn load() -> Result<Self> {
+        let (path, expiries) = config::load_state("temporary-rules.toml")?;
+        Ok(Self { path, expiries })
+    }
+
+    fn save(&self) -> Result<()> {
+        config::save_state(&self.path, &self.expiries)
+    }
+}
+
+/// Rules whose destination follows a device, kept in `tracked-rules.toml` of the state directory
+struct TrackedRules {
+    path: PathBuf,
+    /// Device MAC address by rule id
+    devices: BTreeMap<String, String>,
+}
+
+impl TrackedRules {
+    fn load() -> Result<Self> {
+        let (path, devices) = config::load_state("tracked-rules.toml")?;
+        Ok(Self { path, devices })
+    }
+
+    fn save(&self) -> Result<()> {
+        config::save_state(&self.path, &self.devices)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Adds a rule and registers its expiry, the rule is removed again when it can't be registered.
+pub async fn add_temporary(
+    client: &Client,
+    id: String,
+    rule: SetPortFowardingParams,
+    lifetime: Duration,
+) -> Result<TemporaryRule> {
+    let mut temporary_rules = TemporaryRules::load()?;
+    client.add_nat_rule(rule).await?;
+    let expires_at = now() + lifetime.as_secs();
+    temporary_rules.expiries.insert(id.clone(), expires_at);
+    if let Err(err) = temporary_rules.save() {
+        remove_rule(client, &id).await?;
+        return Err(err);
+    }
+    Ok(TemporaryRule { id, expires_at })
+}
+
+/// Removes the temporary rules which expired, the ones already removed are forgotten.
+pub async fn remove_expired(client: &Client) -> Result<Vec<TemporaryRule>> {
+    let mut temporary_rules = TemporaryRules::load()?;
+    let now = now();
+    let expired: Vec<_> = temporary_rules
+        .expiries
+        .iter()
+        .filter(|(_, expires_at)| **expires_at <= now)
+        .map(|(id, expires_at)| TemporaryRule {
+            id: id.clone(),
+            expires_at: *expires_at,
+        })
+        .collect();
+    let mut removed = Vec::new();
+    for rule in expired {
+        // forget the rules removed so far
+        if let Err(err) = remove_rule(client, &rule.id).await {
+            temporary_rules.save()?;
+            return Err(err);
+        }
+        temporary_rules.expiries.remove(&rule.id);
+        removed.push(rule);
+    }
+    if !removed.is_empty() {
+        temporary_rules.save()?;
+    }
+    Ok(removed)
+}
+
+async fn remove_rule(client: &Client, id: &str) -> Result<()> {
+    let actual = client
+        .structured_nat_rules()
+        .await?
+        .into_iter()
+        .find(|actual| declarative::nat_rule_matches(actual, id));
+    if let Some(actual) = actual {
+        client.remove_nat_rule(actual.id).await?;
+    }
+    Ok(())
+}
+
+/// Registers the device currently at the destination of a rule, the daemon then keeps the rule
+/// destination up to date with the device address. Rules never created are forgotten by the
+/// daemon.
+pub async fn track(client: &Client, id: String, destination: &str) -> Result<()> {
+    let device = client
+        .list_devices()
+        .await?
+        .into_iter()
+        .find(|device| device.ip_address == destination)
+        .ok_or_else(|| anyhow!(Message::NoDeviceAtAddress(destination).to_string()))?;
+    let mut tracked_rules = TrackedRules::load()?;
+    tracked_rules.devices.insert(id, device.phys_address);
+    tracked_rules.save()
+}
+
+/// Points the tracked rules to the current address of their device, the rules removed meanwhile
+/// are forgotten. Returns the updated rules with their new destination.
+pub async fn follow_tracked(client: &Client) -> Result<Vec<(String, String)>> {
+    let mut tracked_rules = TrackedRules::load()?;
+    if tracked_rules.devices.is_empty() {
+        return Ok(Vec::new());
+    }
+    let devices = client.list_devices().await?;
+    let actual_rules = client.structured_nat_rules().await?;
+    let mut updated = Vec::new();
+    let mut forgotten = false;
+    for (id, mac) in tracked_rules.devices.clone() {
+        let Some(actual) = actual_rules
+            .iter()
+            .find(|actual| declarative::nat_rule_matches(actual, &id))
+        else {
+            tracked_rules.devices.remove(&id);
+            forgotten = true;
+            continue;
+        };
+        let address = devices
+            .iter()
+            .find(|device| device.has_mac_address(&mac))
+            .map(|device| device.ip_address.clone())
+            .filter(|address| !address.is_empty());
+        if let Some(address) = address {
+            if address != actual.destination_ip_address {
+                client
+                    .set_nat_rule_destination(actual.id.clone(), address.clone())
+                    .await?;
+                updated.push((id, address));
+            }
+        }
+    }
+    if forgotten {
+        tracked_rules.save()?;
+    }
+    Ok(updated)
+}
@@ -0,0 +1,187 @@
+use clap::{ArgMatches, Command};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Group of Livebox users allowed to read the configuration
+const HTTP_GROUP: &str = "http";
+/// Group of Livebox users allowed to change the configuration
+pub const ADMIN_GROUP: &str = "admin";
+
+/// Group required by each command, the first matching prefix wins
+const REQUIRED_GROUPS: &[(&str, &str)] = &[
+    ("exec", HTTP_GROUP),
+    ("pipeline", HTTP_GROUP),
+    ("raw", HTTP_GROUP),
+    ("nat list", HTTP_GROUP),
+    ("nat", ADMIN_GROUP),
+    ("wifi radio set", ADMIN_GROUP),
+    ("wifi", HTTP_GROUP),
+    ("wan mode show", HTTP_GROUP),
+    ("wan", ADMIN_GROUP),
+    ("lan", HTTP_GROUP),
+    ("devices list", HTTP_GROUP),
+    ("devices export", HTTP_GROUP),
+    ("dhcp options list", HTTP_GROUP),
+    ("dhcp", ADMIN_GROUP),
+    ("multicast status", HTTP_GROUP),
+    ("multicast", ADMIN_GROUP),
+    ("diag", ADMIN_GROUP),
+    ("firewall alg list", HTTP_GROUP),
+    ("firewall", ADMIN_GROUP),
+    ("dmz show", HTTP_GROUP),
+    ("dmz", ADMIN_GROUP),
+    ("healthcheck", HTTP_GROUP),
+    ("daemon", HTTP_GROUP),
+    ("ui-proxy", HTTP_GROUP),
+    ("reboot", ADMIN_GROUP),
+    ("factory-reset", ADMIN_GROUP),
+    ("apply", ADMIN_GROUP),
+    ("snapshot", HTTP_GROUP),
+    ("diff", HTTP_GROUP),
+    ("setup", ADMIN_GROUP),
+    ("admin", ADMIN_GROUP),
+    ("whoami", HTTP_GROUP),
+];
+
+/// Group a command requires, `None` for commands not talking to the Livebox
+pub fn required_group(command: &str) -> Option<&'static str> {
+    REQUIRED_GROUPS
+        .iter()
+        .find(|(prefix, _)| {
+            command == *prefix
+                || command
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with(' '))
+        })
+        .map(|(_, group)| *group)
+}
+
+/// Group whose absence explains a permission denied error on the command, when the user lacks
+/// the group the command requires or the administration group
+pub fn missing_group(command: &str, groups: &[String]) -> Option<&'static str> {
+    let has = |group: &str| groups.iter().any(|actual| actual == group);
+    required_group(command)
+        .filter(|group| !has(group))
+        .or_else(|| (!has(ADMIN_GROUP)).then_some(ADMIN_GROUP))
+}
+
+/// Space separated path of the invoked subcommand (ex: `nat add`)
+pub fn command_path(matches: &ArgMatches) -> String {
+    let mut path = Vec::new();
+    let mut matches = matches;
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        path.push(name);
+        matches = sub_matches;
+    }
+    path.join(" ")
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WhoAmI {
+    username: String,
+    groups: Vec<String>,
+    allowed_commands: Vec<String>,
+    denied_commands: Vec<DeniedCommand>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DeniedCommand {
+    command: String,
+    missing_group: String,
+}
+
+impl WhoAmI {
+    pub fn new(username: &str, groups: &[String], cli: &Command) -> Self {
+        let mut allowed_commands = Vec::new();
+        let mut denied_commands = Vec::new();
+        for command in leaf_commands(cli, None) {
+            let Some(group) = required_group(&command) else {
+                continue;
+            };
+            if groups.iter().any(|actual| actual == group) {
+                allowed_commands.push(command);
+            } else {
+                denied_commands.push(DeniedCommand {
+                    command,
+                    missing_group: group.to_string(),
+                });
+            }
+        }
+        Self {
+            username: username.to_string(),
+            groups: groups.to_vec(),
+            allowed_commands,
+            denied_commands,
+        }
+    }
+}
+
+fn leaf_commands(command: &Command, parent: Option<&str>) -> Vec<String> {
+    let path = |name: &str| match parent {
+        Some(parent) => format!("{parent} {name}"),
+        None => name.to_string(),
+    };
+    command
+        .get_subcommands()
+        .filter(|subcommand| subcommand.get_name() != "help")
+        .flat_map(|subcommand| {
+            let name = path(subcommand.get_name());
+            match subcommand.has_subcommands() {
+                true => leaf_commands(subcommand, Some(&name)),
+                false => vec![name],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_the_group_of_the_first_matching_prefix() {
+        let cases = [
+            ("nat list", Some(HTTP_GROUP)),
+            ("nat add", Some(ADMIN_GROUP)),
+            ("nat from-compose", Some(ADMIN_GROUP)),
+            ("wifi radio set", Some(ADMIN_GROUP)),
+            ("wifi radio show", Some(HTTP_GROUP)),
+            ("wifi", Some(HTTP_GROUP)),
+            ("wan mode show", Some(HTTP_GROUP)),
+            ("wan mode set", Some(ADMIN_GROUP)),
+            ("dmz show", Some(HTTP_GROUP)),
+            ("dmz set", Some(ADMIN_GROUP)),
+            ("natural", None),
+            ("session list", None),
+            ("completion", None),
+        ];
+        for (command, group) in cases {
+            assert_eq!(required_group(command), group, "{command}");
+        }
+    }
+
+    #[test]
+    fn names_the_missing_group() {
+        let groups = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let cases = [
+            ("nat add", groups(&["http"]), Some(ADMIN_GROUP)),
+            ("nat add", groups(&["http", "admin"]), None),
+            ("nat list", groups(&[]), Some(HTTP_GROUP)),
+            ("nat list", groups(&["http"]), Some(ADMIN_GROUP)),
+            ("nat list", groups(&["http", "admin"]), None),
+            ("session list", groups(&["http"]), Some(ADMIN_GROUP)),
+        ];
+        for (command, groups, missing) in cases {
+            assert_eq!(
+                missing_group(command, &groups),
+                missing,
+                "{command} {groups:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::{collections::HashMap, fs::File, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser};
+use futures::{stream, StreamExt};
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use crate::{i18n::Message, livebox::Client};
+
+/// Placeholder replaced by the value produced by the previous step
+const PLACEHOLDER: &str = "{}";
+
+/// Steps run in a single session, each one receiving the value produced by the previous one.
+///
+/// Given on the command line, steps are separated by `|`:
+///
+/// ```sh
+/// livebox-cli pipeline exec -s Devices -m get '|' pick '$.status[?@.Name == "nas"].IPAddress'
+/// ```
+///
+/// or loaded from a YAML file:
+///
+/// ```yaml
+/// - exec:
+///     service: Devices
+///     method: get
+/// - pick: $.status[?@.Name == "nas"].IPAddress
+/// ```
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize, Parser)]
+#[command(no_binary_name = true)]
+#[serde(rename_all = "lowercase")]
+enum Step {
+    /// Invoke a sysbus method
+    Exec {
+        /// service name (ex: `NMC`)
+        #[arg(short, long)]
+        service: String,
+
+        /// method name (ex: `getWANStatus`)
+        #[arg(short, long)]
+        method: String,
+
+        /// method parameter, `{}` is replaced by the previous value (ex: `name={}`)
+        #[arg(long = "param", value_parser = parse_parameter)]
+        #[serde(default, deserialize_with = "deserialize_parameters")]
+        parameters: Vec<(String, String)>,
+    },
+    /// Select a value of the previous one, an array when several nodes match
+    Pick(Pick),
+}
+
+#[derive(Debug, Deserialize, Args)]
+#[serde(transparent)]
+struct Pick {
+    /// json path expression (ex: `$.status.IPAddress`)
+    path: JsonPath,
+}
+
+pub fn parse_parameter(parameter: &str) -> Result<(String, String), String> {
+    parameter
+        .split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| Message::InvalidParameter(parameter).to_string())
+}
+
+fn deserialize_parameters<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    let parameters: HashMap<String, String> = HashMap::deserialize(deserializer)?;
+    Ok(parameters.into_iter().collect())
+}
+
+impl Pipeline {
+    /// Parses the steps of the command line, separated by `|` arguments.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let steps = args
+            .split(|arg| arg == "|")
+            .enumerate()
+            .map(|(index, step)| {
+                Step::try_parse_from(step)
+                    .with_context(|| Message::InvalidPipelineStep(index + 1).to_string())
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { steps })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| Message::UnableToOpen(path).to_string())?;
+        let steps = serde_yaml::from_reader::<_, Vec<serde_yaml::Value>>(file)
+            .and_then(|steps| {
+                steps
+                    .into_iter()
+                    .map(serde_yaml::with::singleton_map::deserialize)
+                    .collect()
+            })
+            .with_context(|| Message::InvalidConfiguration(path).to_string())?;
+        Ok(Self { steps })
+    }
+
+    /// Runs the steps in order and returns the value produced by the last one.
+    pub async fn run(&self, client: &Client) -> Result<Value> {
+        let mut value = Value::Null;
+        for (index, step) in self.steps.iter().enumerate() {
+            value = step
+                .run(client, value)
+                .await
+                .with_context(|| Message::PipelineStepFailed(index + 1).to_string())?;
+        }
+        Ok(value)
+    }
+}
+
+impl Step {
+    async fn run(&self, client: &Client, input: Value) -> Result<Value> {
+        match self {
+            Step::Exec {
+                service,
+                method,
+                parameters,
+            } => exec(client, service, method, parameters, &text(&input)).await,
+            Step::Pick(Pick { path }) => {
+                let mut nodes = path.query(&input).all();
+                match nodes.len() {
+                    0 => Err(anyhow!(Message::NoJsonPathMatch.to_string())),
+                    1 => Ok(nodes.remove(0).clone()),
+                    _ => Ok(Value::Array(nodes.into_iter().cloned().collect())),
+                }
+            }
+        }
+    }
+}
+
+/// A value as substituted to the placeholder, strings without their quotes
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Invokes a method, `{}` in the service name and parameters being replaced by the value.
+async fn exec(
+    client: &Client,
+    service: &str,
+    method: &str,
+    parameters: &[(String, String)],
+    value: &str,
+) -> Result<Value> {
+    let service = service.replace(PLACEHOLDER, value);
+    let parameters: Vec<_> = parameters
+        .iter()
+        .map(|(name, parameter)| (name.as_str(), parameter.replace(PLACEHOLDER, value)))
+        .collect();
+    client
+        .execute_with_parameters(
+            &service,
+            method,
+            parameters
+                .iter()
+                .map(|(name, parameter)| (*name, parameter.as_str()))
+                .collect(),
+        )
+        .await
+}
+
+/// Call made for a value selected by `--for-each`, with its response or error
+#[derive(Serialize)]
+pub struct CallResult {
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Invokes a method once per value selected in the response of a listing call, a few calls at a
+/// time, each response going through `normalize`. The results are returned in the order of the
+/// values, failed calls are logged and flagged with their error.
+pub async fn for_each(
+    client: &Client,
+    (list_service, list_method): &(String, String),
+    path: &JsonPath,
+    (service, method): (&str, &str),
+    parameters: &[(String, String)],
+    concurrency: usize,
+    normalize: impl Fn(Value) -> Result<Value>,
+) -> Result<Vec<CallResult>> {
+    let listing = client
+        .execute(list_service.clone(), list_method.clone())
+        .await?;
+    let values: Vec<_> = path.query(&listing).all().into_iter().map(text).collect();
+    let responses: Vec<_> = stream::iter(&values)
+        .map(|value| exec(client, service, method, parameters, value))
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    let results: Vec<_> = values
+        .into_iter()
+        .zip(responses)
+        .map(|(value, response)| match response.and_then(&normalize) {
+            Ok(response) => CallResult {
+                value,
+                response: Some(response),
+                error: None,
+            },
+            Err(err) => {
+                warn!("{value}: {err:#}");
+                CallResult {
+                    value,
+                    response: None,
+                    error: Some(format!("{err:#}")),
+                }
+            }
+        })
+        .collect();
+    let failures = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .count();
+    if failures > 0 {
+        warn!("{}", Message::CallsFailed(failures, results.len()));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use livebox_cli::livebox::test_support::{MockLivebox, MockLiveboxBuilder};
+    use serde_json::json;
+
+    use super::*;
+
+    /// Arguments separated by spaces
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn describe(pipeline: &Pipeline) -> Vec<String> {
+        pipeline
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Exec {
+                    service,
+                    method,
+                    parameters,
+                } => {
+                    let mut parameters = parameters.clone();
+                    parameters.sort();
+                    format!("exec {service}.{method} {parameters:?}")
+                }
+                Step::Pick(Pick { path }) => format!("pick {path}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_steps_separated_by_pipes() {
+        let pipeline = Pipeline::from_args(&args(
+            "exec -s Devices -m get \
+             | pick $.status[0].Key \
+             | exec --service Devices.Device.{} --method get --param flag=ipv4",
+        ))
+        .unwrap();
+        assert_eq!(
+            describe(&pipeline),
+            [
+                r#"exec Devices.get []"#,
+                r#"pick $.status[0].Key"#,
+                r#"exec Devices.Device.{}.get [("flag", "ipv4")]"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        let cases = [
+            ("exec -s Devices", 1),
+            ("exec -s Devices -m get | select $", 2),
+            ("pick $..[ | pick $", 1),
+            ("pick $ | exec -s NMC -m get --param x", 2),
+            ("pick $ |", 2),
+        ];
+        for (steps, index) in cases {
+            let err = Pipeline::from_args(&args(steps)).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                Message::InvalidPipelineStep(index).to_string(),
+                "{steps:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_steps_of_a_file() {
+        let path = env::temp_dir().join(format!("livebox-cli-pipeline-{}.yml", process::id()));
+        fs::write(
+            &path,
+            r#"
+- exec:
+    service: Devices
+    method: get
+- pick: $.status[?@.Name == "nas"].Key
+- exec:
+    service: Devices.Device.{}
+    method: get
+    parameters:
+      flag: ipv4
+      depth: "1"
+"#,
+        )
+        .unwrap();
+        let pipeline = Pipeline::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            describe(&pipeline.unwrap()),
+            [
+                r#"exec Devices.get []"#,
+                r#"pick $.status[?@['Name']=='nas'].Key"#,
+                r#"exec Devices.Device.{}.get [("depth", "1"), ("flag", "ipv4")]"#,
+            ]
+        );
+    }
+
+    fn calls(livebox: &MockLivebox) -> Vec<Value> {
+        livebox
+            .requests()
+            .into_iter()
+            .filter(|request| request["service"] != "sah.Device.Information")
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn passes_the_value_of_each_step_to_the_next_one() {
+        let livebox = MockLiveboxBuilder::default()
+            .with_response(
+                "Devices",
+                "get",
+                json!({"status": [{"Key": "AA:BB", "Name": "nas"}]}),
+            )
+            .with_response(
+                "Devices.Device.AA:BB",
+                "get",
+                json!({"status": {"IPAddress": "192.168.1.10"}}),
+            )
+            .start()
+            .unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+
+        let pipeline = Pipeline::from_args(&args(
+            "exec -s Devices -m get \
+             | pick $.status[0].Key \
+             | exec -s Devices.Device.{} -m get --param key={} \
+             | pick $.status.IPAddress",
+        ))
+        .unwrap();
+        assert_eq!(pipeline.run(&client).await.unwrap(), json!("192.168.1.10"));
+        let calls = calls(&livebox);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1]["service"], "Devices.Device.AA:BB");
+        assert_eq!(calls[1]["parameters"], json!({"key": "AA:BB"}));
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_failed_step() {
+        let livebox = MockLiveboxBuilder::default()
+            .with_response("Devices", "get", json!({"status": []}))
+            .start()
+            .unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+
+        let pipeline = Pipeline::from_args(&args(
+            "exec -s Devices -m get | pick $.status[0].Key | exec -s Devices.Device.{} -m destroy",
+        ))
+        .unwrap();
+        let err = pipeline.run(&client).await.unwrap_err();
+        assert_eq!(err.to_string(), Message::PipelineStepFailed(2).to_string());
+        assert_eq!(
+            err.root_cause().to_string(),
+            Message::NoJsonPathMatch.to_string()
+        );
+        let calls = calls(&livebox);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["method"], "get");
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn calls_the_method_for_each_value_in_order() {
+        let livebox = MockLiveboxBuilder::default()
+            .with_user("guest", "secret", &["http"])
+            .start()
+            .unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+
+        let listing = ("UserManagement".to_string(), "getUsers".to_string());
+        let path = JsonPath::parse("$.status[*].name").unwrap();
+        // only the password of the guest is right
+        let parameters = [
+            ("name".to_string(), "{}".to_string()),
+            ("password".to_string(), "secret".to_string()),
+            ("newPassword".to_string(), "secret".to_string()),
+        ];
+        let results = for_each(
+            &client,
+            &listing,
+            &path,
+            ("UserManagement", "changePassword"),
+            &parameters,
+            2,
+            Ok,
+        )
+        .await
+        .unwrap();
+        let values: Vec<_> = results.iter().map(|result| result.value.as_str()).collect();
+        assert_eq!(values, ["admin", "guest"]);
+        assert!(results[0].response.is_none());
+        assert!(results[0].error.is_some());
+        assert_eq!(results[1].response, Some(json!({"status": true})));
+        assert!(results[1].error.is_none());
+        client.logout().await.unwrap();
+    }
+}
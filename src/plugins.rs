@@ -0,0 +1,87 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    collections::BTreeSet,
+    env,
+    fs::{self, DirEntry},
+    io::ErrorKind,
+    process::{Command, ExitStatus},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{i18n::Message, livebox::Client};
+
+const PLUGIN_PREFIX: &str = "livebox-cli-";
+/// Executable extensions when `PATHEXT` isn't set
+#[cfg(not(unix))]
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Lists `livebox-cli-<name>` executables found on `PATH`.
+pub fn discover() -> BTreeSet<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return BTreeSet::new();
+    };
+    env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| plugin_name(&entry))
+        .collect()
+}
+
+/// Name of a plugin executable, with the executable bit set
+#[cfg(unix)]
+fn plugin_name(entry: &DirEntry) -> Option<String> {
+    let metadata = entry.metadata().ok()?;
+    if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+        return None;
+    }
+    let file_name = entry.file_name().into_string().ok()?;
+    file_name.strip_prefix(PLUGIN_PREFIX).map(str::to_string)
+}
+
+/// Name of a plugin executable, with an extension of `PATHEXT` (ex: `.EXE`) which is left out
+#[cfg(not(unix))]
+fn plugin_name(entry: &DirEntry) -> Option<String> {
+    if !entry.metadata().ok()?.is_file() {
+        return None;
+    }
+    let file_name = entry.file_name().into_string().ok()?;
+    let (name, extension) = file_name.strip_prefix(PLUGIN_PREFIX)?.rsplit_once('.')?;
+    let extensions = env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+    extensions
+        .split(';')
+        .any(|executable| {
+            executable
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+        .then(|| name.to_string())
+}
+
+/// Runs the `livebox-cli-<name>` plugin sharing the authenticated session through environment variables:
+/// - `LIVEBOX_WS_URL`: sysbus endpoint
+/// - `LIVEBOX_CONTEXT_ID`: value of the `x-context` header
+/// - `LIVEBOX_COOKIE`: value of the `Cookie` header
+pub fn run(client: &Client, base_url: &str, args: Vec<String>) -> Result<ExitStatus> {
+    let (name, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow!(Message::MissingPluginName.to_string()))?;
+    let program = format!("{PLUGIN_PREFIX}{name}");
+    let mut command = Command::new(&program);
+    command
+        .args(args)
+        .env("LIVEBOX_API_BASEURL", base_url)
+        .env("LIVEBOX_WS_URL", client.base_url_ws())
+        .env("LIVEBOX_CONTEXT_ID", client.context_id());
+    if let Some(cookie) = client.cookie_header() {
+        command.env("LIVEBOX_COOKIE", cookie);
+    }
+    command.status().map_err(|err| match err.kind() {
+        ErrorKind::NotFound => {
+            anyhow!(Message::NoPlugin(name, &program).to_string())
+        }
+        _ => anyhow!(err).context(Message::UnableToRun(&program).to_string()),
+    })
+}
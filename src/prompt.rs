@@ -0,0 +1,144 @@
+use std::{
+    fmt,
+    io::{self, IsTerminal, Write},
+};
+
+use anyhow::{anyhow, Result};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::i18n::Message;
+
+/// Asks a question on stderr, an empty answer keeps the default value.
+pub async fn ask(question: String, default: String) -> Result<String> {
+    blocking(move || {
+        eprint!("{question} [{default}]: ");
+        let answer = read_line()?;
+        Ok(match answer.trim() {
+            "" => default,
+            answer => answer.to_string(),
+        })
+    })
+    .await
+}
+
+/// Asks a yes/no question on stderr.
+pub async fn confirm(question: String, default: bool) -> Result<bool> {
+    blocking(move || {
+        let choices = match default {
+            true => "Y/n",
+            false => "y/N",
+        };
+        loop {
+            eprint!("{question} [{choices}] ");
+            let answer = read_line()?.trim().to_lowercase();
+            match answer.as_str() {
+                "" => return Ok(default),
+                "y" | "yes" | "o" | "oui" => return Ok(true),
+                "n" | "no" | "non" => return Ok(false),
+                _ => continue,
+            }
+        }
+    })
+    .await
+}
+
+/// Confirmation policy of destructive operations
+#[derive(Clone, Copy, Debug)]
+pub enum Confirmation {
+    Skipped,
+    Prompted,
+    /// Stdin isn't a terminal to prompt on
+    Refused,
+}
+
+/// Destructive operation refused as stdin isn't a terminal and `--yes` wasn't given
+#[derive(Debug)]
+pub struct NotInteractive;
+
+impl fmt::Display for NotInteractive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Message::Aborted)
+    }
+}
+
+impl std::error::Error for NotInteractive {}
+
+impl Confirmation {
+    /// Prompts are skipped with `--yes` or when `always_confirm` is disabled, operations are
+    /// refused otherwise when stdin isn't a terminal.
+    pub fn new(yes: bool, always_confirm: bool) -> Self {
+        match (yes || !always_confirm, io::stdin().is_terminal()) {
+            (true, _) => Self::Skipped,
+            (false, true) => Self::Prompted,
+            (false, false) => Self::Refused,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        matches!(self, Self::Prompted)
+    }
+
+    /// Asks a yes/no question defaulting to no, accepts when prompts are skipped or fails when
+    /// there is no terminal to ask.
+    pub async fn confirm(&self, question: String) -> Result<bool> {
+        match self {
+            Self::Skipped => Ok(true),
+            Self::Prompted => confirm(question, false).await,
+            Self::Refused => Err(anyhow!(NotInteractive)),
+        }
+    }
+
+    /// Fails unless the operation is confirmed.
+    pub async fn require(&self, question: Message<'_>) -> Result<()> {
+        match self.confirm(question.to_string()).await? {
+            true => Ok(()),
+            false => Err(anyhow!(Message::Aborted.to_string())),
+        }
+    }
+}
+
+/// Reads a password from the terminal without echoing it, empty when nothing is typed.
+pub async fn password(prompt: String) -> Result<SecretString> {
+    blocking(move || Ok(SecretString::from(rpassword::prompt_password(prompt)?))).await
+}
+
+/// Reads a new password twice from the terminal.
+pub async fn new_password() -> Result<SecretString> {
+    loop {
+        let new_password = password(Message::NewPassword.to_string()).await?;
+        let confirmation = password(Message::ConfirmPassword.to_string()).await?;
+        if new_password.expose_secret() == confirmation.expose_secret() {
+            return Ok(new_password);
+        }
+        eprintln!("{}", Message::PasswordsMismatch);
+    }
+}
+
+/// Reads a password from the first line of the standard input.
+pub async fn password_from_stdin() -> Result<SecretString> {
+    blocking(|| {
+        let line = SecretString::from(read_line()?);
+        Ok(SecretString::from(
+            line.expose_secret().trim_end_matches(['\r', '\n']),
+        ))
+    })
+    .await
+}
+
+fn read_line() -> Result<String> {
+    io::stderr().flush()?;
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line)? {
+        0 => Err(anyhow!(io::Error::from(io::ErrorKind::UnexpectedEof))),
+        _ => Ok(line),
+    }
+}
+
+/// Prompts block on the terminal, keeping the runtime free to handle signals.
+async fn blocking<T, F>(prompt: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(prompt).await?
+}
@@ -0,0 +1,709 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, Result};
+
+use crate::i18n::Message;
+
+/// Modules of light border around the symbol, as required by readers
+const QUIET_ZONE: usize = 4;
+/// Pixels per module of PNG images
+const PNG_SCALE: usize = 8;
+
+/// Error correction blocks of the versions 1 to 10 at level M: error correction codewords per
+/// block, then count and data codewords of each group of blocks
+const BLOCKS: [(usize, [(usize, usize); 2]); 10] = [
+    (10, [(1, 16), (0, 0)]),
+    (16, [(1, 28), (0, 0)]),
+    (26, [(1, 44), (0, 0)]),
+    (18, [(2, 32), (0, 0)]),
+    (24, [(2, 43), (0, 0)]),
+    (16, [(4, 27), (0, 0)]),
+    (18, [(4, 31), (0, 0)]),
+    (22, [(2, 38), (2, 39)]),
+    (22, [(3, 36), (2, 37)]),
+    (26, [(4, 43), (1, 44)]),
+];
+
+/// Alignment pattern center coordinates of the versions 2 to 10
+const ALIGNMENT_POSITIONS: [&[usize]; 10] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+
+/// A QR code symbol encoding bytes at the medium error correction level, large enough for WiFi
+/// credentials (up to 213 bytes)
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    /// Modules of the finder, timing, alignment, format and version patterns
+    reserved: Vec<bool>,
+}
+
+/// Provisioning payload read by phone cameras to join a WiFi network, an empty passphrase for
+/// open networks
+pub fn wifi_payload(ssid: &str, passphrase: &str) -> String {
+    let escape = |value: &str| {
+        value.chars().fold(String::new(), |mut escaped, c| {
+            if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+    };
+    match passphrase.is_empty() {
+        true => format!("WIFI:T:nopass;S:{};;", escape(ssid)),
+        false => format!("WIFI:T:WPA;S:{};P:{};;", escape(ssid), escape(passphrase)),
+    }
+}
+
+impl QrCode {
+    /// Encodes the data in the smallest version holding it.
+    pub fn encode(data: &[u8]) -> Result<Self> {
+        let qr_code = Self::unmasked(data)?;
+        let mask = (0..8)
+            .min_by_key(|mask| qr_code.with_mask(*mask).penalty())
+            .unwrap_or_default();
+        Ok(qr_code.with_mask(mask))
+    }
+
+    /// Symbol of the smallest version holding the data, with its codewords placed but not masked
+    fn unmasked(data: &[u8]) -> Result<Self> {
+        let (version, (ec_length, groups)) = BLOCKS
+            .iter()
+            .enumerate()
+            .map(|(index, blocks)| (index + 1, *blocks))
+            .find(|(version, (_, groups))| {
+                let capacity: usize = groups.iter().map(|(count, length)| count * length).sum();
+                4 + count_bits(*version) + data.len() * 8 <= capacity * 8
+            })
+            .ok_or_else(|| anyhow!(Message::QrCodeTooLong(data.len()).to_string()))?;
+
+        let capacity: usize = groups.iter().map(|(count, length)| count * length).sum();
+        let mut bits = Vec::new();
+        push_bits(&mut bits, 0b0100, 4);
+        push_bits(&mut bits, data.len() as u32, count_bits(version));
+        for byte in data {
+            push_bits(&mut bits, *byte as u32, 8);
+        }
+        let terminator = (capacity * 8 - bits.len()).min(4);
+        push_bits(&mut bits, 0, terminator);
+        let padding = (8 - bits.len() % 8) % 8;
+        push_bits(&mut bits, 0, padding);
+        let mut codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | *bit as u8))
+            .collect();
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if codewords.len() == capacity {
+                break;
+            }
+            codewords.push(pad);
+        }
+
+        let mut qr_code = Self::new(version);
+        qr_code.place(&interleave(&codewords, ec_length, &groups));
+        Ok(qr_code)
+    }
+
+    /// Symbol with the mask applied and its format information drawn
+    fn with_mask(&self, mask: u32) -> Self {
+        let mut qr_code = self.masked(mask);
+        qr_code.draw_format(mask);
+        qr_code
+    }
+
+    /// Symbol of the version with its function patterns drawn
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        let mut qr_code = Self {
+            size,
+            modules: vec![false; size * size],
+            reserved: vec![false; size * size],
+        };
+        for i in 0..size {
+            qr_code.set_function(6, i, i % 2 == 0);
+            qr_code.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            qr_code.draw_finder(x, y);
+        }
+        let positions = ALIGNMENT_POSITIONS[version - 1];
+        for &x in positions {
+            for &y in positions {
+                let last = positions.len() - 1;
+                let overlaps_finder = (x == positions[0] || x == positions[last])
+                    && y == positions[0]
+                    || x == positions[0] && y == positions[last];
+                if !overlaps_finder {
+                    qr_code.draw_alignment(x, y);
+                }
+            }
+        }
+        // reserved until the mask is chosen
+        qr_code.draw_format(0);
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let version_bits = (version as u32) << 12 | remainder;
+            for i in 0..18 {
+                let dark = version_bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                qr_code.set_function(a, b, dark);
+                qr_code.set_function(b, a, dark);
+            }
+        }
+        qr_code
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.reserved[y * self.size + x] = true;
+    }
+
+    /// Finder pattern centered on the module, with its light separator
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4..=4_isize {
+            for dx in -4..=4_isize {
+                let (mx, my) = (x as isize + dx, y as isize + dy);
+                if (0..self.size as isize).contains(&mx) && (0..self.size as isize).contains(&my) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(mx as usize, my as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2..=2_isize {
+            for dx in -2..=2_isize {
+                let distance = dx.abs().max(dy.abs());
+                self.set_function(
+                    (x as isize + dx) as usize,
+                    (y as isize + dy) as usize,
+                    distance != 1,
+                );
+            }
+        }
+    }
+
+    /// Format information of the medium error correction level and the mask
+    fn draw_format(&mut self, mask: u32) {
+        let data = mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let format_bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |i: usize| format_bits >> i & 1 == 1;
+        let size = self.size;
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Places the codewords in the zigzag order, right to left in columns pairs
+    fn place(&mut self, codewords: &[u8]) {
+        let mut index = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for x in [right, right - 1] {
+                    let upward = (right + 1) & 2 == 0;
+                    let y = match upward {
+                        true => self.size - 1 - vertical,
+                        false => vertical,
+                    };
+                    if !self.reserved[y * self.size + x] && index < codewords.len() * 8 {
+                        self.modules[y * self.size + x] =
+                            codewords[index / 8] >> (7 - index % 8) & 1 == 1;
+                        index += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn masked(&self, mask: u32) -> Self {
+        let mut modules = self.modules.clone();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.reserved[y * self.size + x] {
+                    modules[y * self.size + x] ^= true;
+                }
+            }
+        }
+        Self {
+            size: self.size,
+            modules,
+            reserved: self.reserved.clone(),
+        }
+    }
+
+    /// Penalty of the masked symbol, the mask with the lowest one is the easiest to read
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| self.is_dark(j, i)).collect::<Vec<_>>(),
+                (0..size).map(|j| self.is_dark(i, j)).collect::<Vec<_>>(),
+            ]
+        });
+        for line in lines {
+            let mut run = 1;
+            for j in 1..=size {
+                if j < size && line[j] == line[j - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            for window in line.windows(11) {
+                let finder_like = [true, false, true, true, true, false, true];
+                let light = [false; 4];
+                if window[..7] == finder_like && window[7..] == light
+                    || window[..4] == light && window[4..] == finder_like
+                {
+                    penalty += 40;
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.is_dark(x, y);
+                if dark == self.is_dark(x + 1, y)
+                    && dark == self.is_dark(x, y + 1)
+                    && dark == self.is_dark(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|dark| **dark).count();
+        let total = size * size;
+        penalty + (dark * 20).abs_diff(total * 10) / total * 10
+    }
+
+    /// Modules including the quiet zone
+    fn bordered_size(&self) -> usize {
+        self.size + 2 * QUIET_ZONE
+    }
+
+    fn is_dark_bordered(&self, x: usize, y: usize) -> bool {
+        let inside = QUIET_ZONE..QUIET_ZONE + self.size;
+        inside.contains(&x) && inside.contains(&y) && self.is_dark(x - QUIET_ZONE, y - QUIET_ZONE)
+    }
+
+    /// Two rows of modules per line of upper half blocks, black on white whatever the terminal
+    /// colors
+    pub fn to_ansi(&self) -> String {
+        let size = self.bordered_size();
+        let mut rendered = String::new();
+        for y in (0..size).step_by(2) {
+            for x in 0..size {
+                let foreground = match self.is_dark_bordered(x, y) {
+                    true => 30,
+                    false => 97,
+                };
+                let background = match y + 1 < size && self.is_dark_bordered(x, y + 1) {
+                    true => 40,
+                    false => 107,
+                };
+                rendered.push_str(&format!("\x1b[{foreground};{background}m\u{2580}"));
+            }
+            rendered.push_str("\x1b[0m\n");
+        }
+        rendered
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.bordered_size();
+        let mut path = String::new();
+        for y in 0..size {
+            for x in 0..size {
+                if self.is_dark_bordered(x, y) {
+                    let _ = write!(path, "M{x},{y}h1v1h-1z");
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n\
+             <path d=\"{path}\" fill=\"#000\"/>\n\
+             </svg>\n"
+        )
+    }
+
+    /// Black and white PNG image, one bit per pixel
+    pub fn to_png(&self) -> Vec<u8> {
+        let pixels = self.bordered_size() * PNG_SCALE;
+        let row_length = pixels.div_ceil(8);
+        let mut raw = Vec::with_capacity((row_length + 1) * pixels);
+        for y in 0..pixels {
+            // no filter
+            raw.push(0);
+            let mut row = vec![0u8; row_length];
+            for x in 0..pixels {
+                // grayscale samples, 1 is white
+                if !self.is_dark_bordered(x / PNG_SCALE, y / PNG_SCALE) {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            raw.extend(row);
+        }
+        let mut header = Vec::new();
+        header.extend((pixels as u32).to_be_bytes());
+        header.extend((pixels as u32).to_be_bytes());
+        // bit depth 1, grayscale, deflate, no filter, no interlace
+        header.extend([1, 0, 0, 0, 0]);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(
+            &mut png,
+            b"IDAT",
+            &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 9),
+        );
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Bits of the byte count, depending on the version
+fn count_bits(version: usize) -> usize {
+    match version {
+        1..=9 => 8,
+        _ => 16,
+    }
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, length: usize) {
+    bits.extend((0..length).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Splits the data codewords in blocks, adds their error correction codewords and interleaves them
+fn interleave(codewords: &[u8], ec_length: usize, groups: &[(usize, usize); 2]) -> Vec<u8> {
+    let divisor = reed_solomon_divisor(ec_length);
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    for (count, length) in groups {
+        for _ in 0..*count {
+            let data = &codewords[offset..offset + length];
+            blocks.push((data, reed_solomon_remainder(data, &divisor)));
+            offset += length;
+        }
+    }
+    let longest = groups
+        .iter()
+        .map(|(_, length)| *length)
+        .max()
+        .unwrap_or_default();
+    let mut interleaved = Vec::new();
+    for i in 0..longest {
+        interleaved.extend(blocks.iter().filter_map(|(data, _)| data.get(i)));
+    }
+    for i in 0..ec_length {
+        interleaved.extend(blocks.iter().map(|(_, ec)| ec[i]));
+    }
+    interleaved
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut product: u16 = 0;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x11D);
+        product ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    product as u8
+}
+
+/// Coefficients of the generator polynomial, highest degree first without the leading 1
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (coefficient, divisor) in remainder.iter_mut().zip(divisor) {
+            *coefficient ^= gf_multiply(*divisor, factor);
+        }
+    }
+    remainder
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = png[start..].iter().fold(0xFFFF_FFFFu32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xEDB8_8320,
+            _ => crc >> 1,
+        })
+    });
+    png.extend((!crc).to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `wifi_payload("Livebox-1234", "secret")` encoded at the level M with the mask 2 by Kazuhiko
+    /// Arase's QRCode for JavaScript
+    const VERSION_3: [&str; 29] = [
+        "#######..#####.#...#..#######",
+        "#.....#...#.#######.#.#.....#",
+        "#.###.#.#.####....#...#.###.#",
+        "#.###.#.###.#.#.###.#.#.###.#",
+        "#.###.#.####.......#..#.###.#",
+        "#.....#.##.##..####.#.#.....#",
+        "#######.#.#.#.#.#.#.#.#######",
+        "........##.##.#.....#........",
+        "#.#####..##.....####..#####..",
+        "...#.#.#.######....#..#.#.##.",
+        ".....##.#.#..###.....###.#...",
+        "#.##.#.#######.##.#..##.#..##",
+        ".###.###.#.##.#######..####..",
+        "##..#....#.##...##.###.##.##.",
+        "#######.#.#....####.#.##..#..",
+        "######....###.###..###...#...",
+        ".#.#.###.##....#...#...#.#.##",
+        "#.#.#..#..##.##.#..####.##.#.",
+        "#.##..#.########..#..####....",
+        "#.#.##.###.###....##....#...#",
+        "#.#...#..#....#####.#######..",
+        "........####....##..#...#.#..",
+        "#######..#.....######.#.#.#..",
+        "#.....#.#####.#...###...##.#.",
+        "#.###.#.##..#..#.#..######...",
+        "#.###.#.#.#...#.#..#....##.##",
+        "#.###.#.##.###.#####..######.",
+        "#.....#..#..#..##...#...##.#.",
+        "#######.##..####.#.#.##.##...",
+    ];
+
+    /// Long credentials encoded at the level M with the mask 2 by the same reference encoder, with
+    /// two groups of blocks and the version information
+    const VERSION_9: [&str; 53] = [
+        "#######...####.####.....#.#.#.#....#.#...##...#######",
+        "#.....#....#..##.##....####.....#.##.###..##..#.....#",
+        "#.###.#.#.#.###.#..#....#...#.#.###..####..#..#.###.#",
+        "#.###.#.#...##.####........#.#.#....#....##.#.#.###.#",
+        "#.###.#.#..##..#...############.#..###.#.##...#.###.#",
+        "#.....#.#.####...##.#.#.#...#..#..#...##.##...#.....#",
+        "#######.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#######",
+        "........##..#.#....#....#...###.#......##.#..........",
+        "#.#####.....###.#...#...########.#..#.#...###.#####..",
+        ".#...#..#..##...##..##.#.##.###..#.###..####.....###.",
+        "#.###.##.##..#..###..###.#...#...####.##..#.#....#...",
+        "###..#.#..########.....###...#..##.#.#####.......#..#",
+        "#.#.###.##...#..#.#..#.....#..##.#.####..####...#....",
+        "#...#..#..##.......#.##.##.####....#.#...##.....##..#",
+        "..##..#...###.####.#.....#.....#.##.###...########...",
+        "#..###...#.##...##.#....##.#######....##.##...#..#.##",
+        ".#.#..##..#..###.##..#..#.#..###.#.##.....#.#...#.##.",
+        "..#.##.#.#..#..##...###..#.####.#...##.##.#..#.##..##",
+        "...####.......####....#....#......##..####....####...",
+        ".........#...##.#.########.#.#.####..#.##.#.##...#.#.",
+        "#.#...#.#.#..#.#.##.#.#..#######.#.##......#.#..###..",
+        "##.#.#.#.#.#...#.#...#.###..######...#.#####....#..##",
+        "####.##.#....##...#..#..#..#..#####.####...####...#..",
+        ".#.##...#.#..#..########.#..#####..#..###.##.#...#.##",
+        ".########....#..##..#...########.#####.#.#.######.#..",
+        "....#...#....#...########...#.##.....#.#.####...#.###",
+        "....#.#.###...##..#.#.###.#.#...###.###....##.#.##.#.",
+        "..###...#####.#.#....#.##...#.####...####.#.#...##.#.",
+        ".##.#####.#...##.#..#...#####..#.#.####..########..##",
+        "#.##.#.##..#....####.###..######...#.#.##.####.####..",
+        "...#.##...###..###...#..###.##...##.######.##........",
+        "#.##....##..##.....#.#..#.#..#####.....###.#######...",
+        ".#######..###.#..##.#....###.###.####.#...#.#..#..#.#",
+        ".#####..#....##.....#.##...#..#....###.#######..#...#",
+        ".#....#.#..###..#.#..#...####..#.######....###.##.#..",
+        "...##..###..#...#..#.#........####..###.#..#..#..#..#",
+        "...##.##.#####.#..#......#.##..#..######.#...#.#..#.#",
+        "##..#...#..#..#.#...####..##.####..###..####.####...#",
+        "..##..#.##.#.#.#.#.###......#..##.##.###.##.#..#.....",
+        ".#.#...#..##.#.#.......#..#..#..#.#..#.########.##..#",
+        "##.####.#....#.#.#####.#.#....##...##....##.##....##.",
+        "##..##.#.#.####...#.#..#..#####.#..###.#.####..###..#",
+        "##.#####..#.######.###...#..#....#.##.##...###..##...",
+        ".##.......#...#.#...#.#.#.....#.#..#.#..###.#.##.#...",
+        "...#..#####.#.#.#.###..######.##...##.#..##.#####.###",
+        "........#..#.#.#.##.#####...###....###..#.#.#...##..#",
+        "#######..#....#.###...#.#.#.#..######.##...##.#.#.#..",
+        "#.....#.#..#.##.##.####.#...#...####..#.#...#...##..#",
+        "#.###.#.##.####...##..#.########.#.##..#.#.######...#",
+        "#.###.#.#.##..##..##.#.###..#.#........#.##.##.#..###",
+        "#.###.#.#.###.##.#.#....#.#.#..#.##.#.##.###..####.##",
+        "#.....#...###....##...##....#.####......#......#.#.#.",
+        "#######.####.....##....###.#..##...##.#..##.####.##..",
+    ];
+
+    /// Format information of the medium error correction level by mask, ISO/IEC 18004 table C.1
+    const FORMAT_INFORMATION: [&str; 8] = [
+        "101010000010010",
+        "101000100100101",
+        "101111001111100",
+        "101101101001011",
+        "100010111111001",
+        "100000011001110",
+        "100111110010111",
+        "100101010100000",
+    ];
+
+    fn rows(qr_code: &QrCode) -> Vec<String> {
+        (0..qr_code.size)
+            .map(|y| {
+                (0..qr_code.size)
+                    .map(|x| match qr_code.is_dark(x, y) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn long_payload() -> String {
+        wifi_payload(
+            &format!("{}1234", "Livebox-".repeat(8)),
+            &"correct horse battery staple ".repeat(3),
+        )
+    }
+
+    fn bits(qr_code: &QrCode, positions: impl IntoIterator<Item = (usize, usize)>) -> String {
+        positions
+            .into_iter()
+            .map(|(x, y)| match qr_code.is_dark(x, y) {
+                true => '1',
+                false => '0',
+            })
+            .collect()
+    }
+
+    /// Both copies of the format information, most significant bit first
+    fn format_information(qr_code: &QrCode) -> [String; 2] {
+        let size = qr_code.size;
+        let around_finder = (0..6)
+            .map(|x| (x, 8))
+            .chain([(7, 8), (8, 8), (8, 7)])
+            .chain((0..6).rev().map(|y| (8, y)));
+        let split = (0..7)
+            .map(|i| (8, size - 1 - i))
+            .chain((size - 8..size).map(|x| (x, 8)));
+        [bits(qr_code, around_finder), bits(qr_code, split)]
+    }
+
+    #[test]
+    fn escapes_wifi_payload() {
+        assert_eq!(
+            wifi_payload("My;Box", r"p\a:ss"),
+            r"WIFI:T:WPA;S:My\;Box;P:p\\a\:ss;;"
+        );
+        assert_eq!(wifi_payload("Guests", ""), "WIFI:T:nopass;S:Guests;;");
+    }
+
+    #[test]
+    fn matches_a_reference_encoder() {
+        let payload = wifi_payload("Livebox-1234", "secret");
+        let qr_code = QrCode::encode(payload.as_bytes()).unwrap();
+        assert_eq!(rows(&qr_code), VERSION_3);
+
+        let qr_code = QrCode::encode(long_payload().as_bytes()).unwrap();
+        assert_eq!(rows(&qr_code), VERSION_9);
+    }
+
+    #[test]
+    fn draws_the_format_information_of_each_mask() {
+        let payload = wifi_payload("Livebox-1234", "secret");
+        let qr_code = QrCode::unmasked(payload.as_bytes()).unwrap();
+        for (mask, expected) in FORMAT_INFORMATION.into_iter().enumerate() {
+            let masked = qr_code.with_mask(mask as u32);
+            assert_eq!(format_information(&masked), [expected; 2], "mask {mask}");
+            assert!(masked.is_dark(8, masked.size - 8), "dark module");
+        }
+    }
+
+    #[test]
+    fn draws_the_version_information() {
+        let qr_code = QrCode::encode(long_payload().as_bytes()).unwrap();
+        let size = qr_code.size;
+        assert_eq!(size, 53);
+        let bottom_left = (0..18).rev().map(|i| (i / 3, size - 11 + i % 3));
+        let top_right = (0..18).rev().map(|i| (size - 11 + i % 3, i / 3));
+        // ISO/IEC 18004 table D.1
+        assert_eq!(bits(&qr_code, bottom_left), "001001101010011001");
+        assert_eq!(bits(&qr_code, top_right), "001001101010011001");
+    }
+
+    #[test]
+    fn rejects_data_beyond_version_10() {
+        assert_eq!(QrCode::encode(&[b'a'; 213]).unwrap().size, 57);
+        assert!(QrCode::encode(&[b'a'; 214]).is_err());
+    }
+}
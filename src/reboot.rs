@@ -0,0 +1,166 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use log::debug;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    i18n::Message,
+    livebox::{Client, ClientBuilder},
+    shutdown::Shutdown,
+};
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Services whose readiness can be awaited after a reboot
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum Service {
+    /// WAN link up
+    Wan,
+    /// WiFi radios up
+    Wifi,
+    /// VoIP lines registered
+    Voip,
+}
+
+pub struct WaitOptions {
+    /// Maximum duration of the shutdown, reachability and login stages
+    pub reachable_timeout: Duration,
+    /// Services to wait for once logged in again
+    pub services: Vec<Service>,
+    /// Maximum duration of each service stage
+    pub service_timeout: Duration,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct StageReport {
+    stage: String,
+    elapsed_seconds: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RebootReport {
+    rebooted: bool,
+    stages: Vec<StageReport>,
+}
+
+/// Polls `check` until it yields a value, giving up after `timeout`.
+async fn wait_for<T, F, Fut>(
+    stage: &str,
+    timeout: Duration,
+    stages: &mut Vec<StageReport>,
+    mut check: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>>>,
+{
+    let start = Instant::now();
+    loop {
+        match check().await {
+            Ok(Some(value)) => {
+                stages.push(StageReport {
+                    stage: stage.to_string(),
+                    elapsed_seconds: start.elapsed().as_secs(),
+                });
+                return Ok(value);
+            }
+            Ok(None) => debug!("{stage} not ready yet"),
+            Err(err) => debug!("{stage} not ready yet: {err:#}"),
+        }
+        if start.elapsed() >= timeout {
+            return Err(anyhow!(
+                Message::NotReadyAfter(stage, timeout.as_secs()).to_string()
+            ));
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+}
+
+/// Reboots the Livebox and optionally waits for it and the given services to be ready again.
+pub async fn reboot(
+    client: &Client,
+    builder: ClientBuilder,
+    wait: Option<WaitOptions>,
+    shutdown: &Shutdown,
+) -> Result<Value> {
+    client.reboot().await?;
+    let mut stages = Vec::new();
+    if let Some(wait) = wait {
+        let client = shutdown
+            .run(wait_for_session(&builder, &wait, &mut stages))
+            .await
+            .ok_or_else(|| anyhow!(Message::Interrupted.to_string()))??;
+        let services = shutdown
+            .run(wait_for_services(&client, &wait, &mut stages))
+            .await;
+        client.logout().await?;
+        services.ok_or_else(|| anyhow!(Message::Interrupted.to_string()))??;
+    }
+    Ok(serde_json::to_value(RebootReport {
+        rebooted: true,
+        stages,
+    })?)
+}
+
+/// Waits for the Livebox to go down, come back and accept a new session.
+async fn wait_for_session(
+    builder: &ClientBuilder,
+    wait: &WaitOptions,
+    stages: &mut Vec<StageReport>,
+) -> Result<Client> {
+    wait_for("shutdown", wait.reachable_timeout, stages, || async {
+        Ok(builder.probe(PROBE_TIMEOUT).await.is_err().then_some(()))
+    })
+    .await?;
+    wait_for("reachable", wait.reachable_timeout, stages, || async {
+        Ok(builder.probe(PROBE_TIMEOUT).await.ok())
+    })
+    .await?;
+    wait_for("login", wait.reachable_timeout, stages, || {
+        let builder = builder.clone();
+        async move { Ok(builder.build().await.ok()) }
+    })
+    .await
+}
+
+async fn wait_for_services(
+    client: &Client,
+    wait: &WaitOptions,
+    stages: &mut Vec<StageReport>,
+) -> Result<()> {
+    for service in &wait.services {
+        match service {
+            Service::Wan => {
+                wait_for("wan", wait.service_timeout, stages, || async {
+                    Ok(client.get_wan_status().await?.is_up().then_some(()))
+                })
+                .await?
+            }
+            Service::Wifi => {
+                wait_for("wifi", wait.service_timeout, stages, || async {
+                    Ok(client.get_wifi_status().await?.status.then_some(()))
+                })
+                .await?
+            }
+            Service::Voip => {
+                wait_for("voip", wait.service_timeout, stages, || async {
+                    let trunks = client.list_voip_trunks().await?;
+                    Ok(trunks
+                        .iter()
+                        .all(|trunk| trunk.is_registered())
+                        .then_some(()))
+                })
+                .await?
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    net::{Ipv4Addr, Ipv6Addr},
+    sync::LazyLock,
+};
+
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+static MAC_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[0-9A-Fa-f]{2}([:-])(?:[0-9A-Fa-f]{2}[:-]){4}[0-9A-Fa-f]{2}\b").unwrap()
+});
+/// Not within a dotted version number (ex: `SG40_sip-fr-6.62.12.1`), which is checked after the
+/// address too
+static IPV4_ADDRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(^|[^\w.-])(\d{1,3}(?:\.\d{1,3}){3})").unwrap());
+static IPV6_ADDRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[0-9A-Fa-f]{0,4}(?::[0-9A-Fa-f]{0,4}){2,7}").unwrap());
+static PHONE_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\+\d{8,15}\b|\b0[1-9](?:[ .-]?\d{2}){4}\b").unwrap());
+
+/// Masks personal data of a command output: MAC addresses, public IP addresses, SSIDs, serial
+/// numbers, phone numbers and secrets.
+///
+/// Values are pseudonymized, the same value always gets the same replacement of the same shape
+/// within a run so the output can still be followed.
+pub fn redact(value: Value) -> Value {
+    redact_value(None, value)
+}
+
+fn redact_value(key: Option<&str>, value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = redact_value(Some(&key), value);
+                    (redact_text(&key), value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| redact_value(key, value))
+                .collect(),
+        ),
+        Value::String(text) => Value::String(match key.map(str::to_ascii_lowercase) {
+            Some(key) if key.contains("passphrase") || key.contains("password") => {
+                "********".to_string()
+            }
+            Some(key) if key.contains("ssid") && !key.contains("bssid") && !text.is_empty() => {
+                format!("ssid-{:06x}", pseudonym(&text) & 0xffffff)
+            }
+            Some(key) if key.contains("serial") && !text.is_empty() => {
+                format!("serial-{:06x}", pseudonym(&text) & 0xffffff)
+            }
+            Some(key) if key.contains("phone") || key.contains("directorynumber") => {
+                redact_digits(&text)
+            }
+            _ => redact_text(&text),
+        }),
+        value => value,
+    }
+}
+
+/// Replaces the MAC addresses, public IP addresses and phone numbers found in a text
+fn redact_text(text: &str) -> String {
+    let text = MAC_ADDRESS.replace_all(text, |captures: &Captures| {
+        let separator = &captures[1];
+        let hash = pseudonym(&captures[0].to_ascii_lowercase()).to_be_bytes();
+        // locally administered unicast address
+        let mut octets = vec!["02".to_string()];
+        octets.extend(hash[..5].iter().map(|octet| format!("{octet:02x}")));
+        octets.join(separator)
+    });
+    let text = IPV4_ADDRESS.replace_all(&text, |captures: &Captures| {
+        let end = captures.get(0).map_or(0, |address| address.end());
+        let mut next = text[end..].chars();
+        let in_version = match next.next() {
+            Some('.') => next.next().is_some_and(|char| char.is_ascii_digit()),
+            Some(char) => char.is_alphanumeric() || char == '_' || char == '-',
+            None => false,
+        };
+        let address = match captures[2].parse::<Ipv4Addr>() {
+            Ok(address) if !in_version && is_public_ipv4(&address) => {
+                let hash = pseudonym(&captures[2]);
+                // benchmarking range 198.18.0.0/15, never routed on the Internet
+                Ipv4Addr::new(
+                    198,
+                    18 + (hash >> 16 & 1) as u8,
+                    (hash >> 8) as u8,
+                    hash as u8,
+                )
+                .to_string()
+            }
+            _ => captures[2].to_string(),
+        };
+        format!("{}{address}", &captures[1])
+    });
+    let text = IPV6_ADDRESS.replace_all(&text, |captures: &Captures| {
+        match captures[0].parse::<Ipv6Addr>() {
+            Ok(address) if is_public_ipv6(&address) => {
+                let hash = pseudonym(&address.to_string());
+                // documentation range 2001:db8::/32
+                Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, (hash >> 16) as u16, hash as u16)
+                    .to_string()
+            }
+            _ => captures[0].to_string(),
+        }
+    });
+    PHONE_NUMBER
+        .replace_all(&text, |captures: &Captures| redact_digits(&captures[0]))
+        .into_owned()
+}
+
+/// Replaces the digits of a phone number, keeping its prefix and separators
+fn redact_digits(text: &str) -> String {
+    let prefix = match text.starts_with('+') {
+        true => 3,
+        false => 2,
+    };
+    let mut hash = pseudonym(text);
+    text.chars()
+        .enumerate()
+        .map(|(index, char)| match char.is_ascii_digit() {
+            true if index >= prefix => {
+                let digit = (hash % 10) as u8;
+                hash /= 10;
+                char::from(b'0' + digit)
+            }
+            _ => char,
+        })
+        .collect()
+}
+
+fn is_public_ipv4(address: &Ipv4Addr) -> bool {
+    !(address.is_private()
+        || address.is_loopback()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        || address.is_documentation()
+        // netmasks look like addresses
+        || address.octets()[0] == 255
+        || address.octets()[..2] == [198, 18]
+        || address.octets()[..2] == [198, 19])
+}
+
+fn is_public_ipv6(address: &Ipv6Addr) -> bool {
+    // global unicast 2000::/3 out of the documentation range
+    address.segments()[0] & 0xe000 == 0x2000 && address.segments()[..2] != [0x2001, 0xdb8]
+}
+
+/// SipHash keyed randomly for the run, pseudonyms can't be brute-forced back to the values
+static PSEUDONYM_KEY: LazyLock<RandomState> = LazyLock::new(RandomState::new);
+
+fn pseudonym(value: &str) -> u64 {
+    PSEUDONYM_KEY.hash_one(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn masks_mac_addresses() {
+        let redacted = redact_text("device AA:BB:CC:DD:EE:FF up");
+        assert!(redacted.starts_with("device 02:"), "{redacted}");
+        assert!(!redacted.contains("AA:BB"));
+        // the case of a MAC address doesn't change its pseudonym
+        assert_eq!(redacted, redact_text("device aa:bb:cc:dd:ee:ff up"));
+        assert!(redact_text("aa-bb-cc-dd-ee-ff").starts_with("02-"));
+    }
+
+    #[test]
+    fn masks_public_ipv4_addresses_only() {
+        let redacted = redact_text("wan 55.27.2.115 lan 192.168.1.1");
+        assert!(redacted.starts_with("wan 198.1"), "{redacted}");
+        assert!(redacted.ends_with(" lan 192.168.1.1"), "{redacted}");
+        assert_eq!(redact_text("255.255.255.0"), "255.255.255.0");
+    }
+
+    #[test]
+    fn keeps_version_numbers() {
+        for version in [
+            "4.1.2.3.4",
+            "v4.1.2.3",
+            "SG40_sip-fr-6.62.12.1",
+            "4.1.2.3-beta",
+        ] {
+            assert_eq!(redact_text(version), version);
+        }
+        assert_ne!(redact_text("55.27.2.115."), "55.27.2.115.");
+    }
+
+    #[test]
+    fn masks_public_ipv6_addresses_only() {
+        let redacted = redact_text("2a01:cb00:8079:784::1");
+        assert!(redacted.starts_with("2001:db8::"), "{redacted}");
+        assert_eq!(redact_text("fe80::1"), "fe80::1");
+    }
+
+    #[test]
+    fn masks_phone_numbers() {
+        let redacted = redact_text("call 01 23 45 67 89");
+        assert!(redacted.starts_with("call 01 "), "{redacted}");
+        assert_ne!(redacted, "call 01 23 45 67 89");
+        assert_eq!(redacted.len(), "call 01 23 45 67 89".len());
+        assert!(redact_text("+33123456789").starts_with("+33"));
+    }
+
+    #[test]
+    fn masks_fields_by_name() {
+        let redacted = redact(json!({
+            "SSID": "Livebox-1234",
+            "BSSID": "Livebox-1234",
+            "SerialNumber": "AN2022012345",
+            "KeyPassPhrase": "secret",
+            "DirectoryNumber": "0123456789",
+        }));
+        assert!(redacted["SSID"].as_str().unwrap().starts_with("ssid-"));
+        assert_eq!(redacted["BSSID"], "Livebox-1234");
+        assert!(redacted["SerialNumber"]
+            .as_str()
+            .unwrap()
+            .starts_with("serial-"));
+        assert_eq!(redacted["KeyPassPhrase"], "********");
+        assert_ne!(redacted["DirectoryNumber"], "0123456789");
+    }
+
+    #[test]
+    fn pseudonyms_are_stable_within_a_run() {
+        let value = json!({"SSID": "Livebox-1234", "IPAddress": "55.27.2.115"});
+        assert_eq!(redact(value.clone()), redact(value));
+        assert_ne!(
+            redact_text("55.27.2.115"),
+            redact_text("55.27.2.116"),
+            "distinct values get distinct pseudonyms"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fs::File, path::Path, process::Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{daemon::Event, i18n::Message, mqtt};
+
+/// Reactions to the daemon events, loaded from a YAML file:
+///
+/// ```yaml
+/// rules:
+///   - when: wan-down
+///     then:
+///       webhook: https://example.com/hooks/livebox
+///   - when: "$[?@.Name == 'phone']"
+///     then:
+///       command: notify-send "phone is home"
+/// ```
+#[derive(Deserialize)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Deserialize)]
+struct Rule {
+    when: Condition,
+    #[serde(with = "serde_yaml::with::singleton_map")]
+    then: Action,
+}
+
+/// An event name (ex: `device-joined`), or a JSONPath matching the event when it selects nodes
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+enum Condition {
+    Event(String),
+    Query(JsonPath),
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(condition: String) -> Result<Self, Self::Error> {
+        match condition.starts_with('$') {
+            true => JsonPath::parse(&condition)
+                .map(Condition::Query)
+                .map_err(|err| err.to_string()),
+            false => Ok(Condition::Event(condition)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    /// Shell command, receiving the event as JSON on stdin and its name in `LIVEBOX_EVENT`
+    Command(String),
+    /// URL the event is posted to as JSON
+    Webhook(String),
+    /// Topic of an MQTT broker the event is published to as JSON
+    Mqtt(mqtt::Publication),
+}
+
+impl Rules {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| Message::UnableToOpen(path).to_string())?;
+        serde_yaml::from_reader(file)
+            .with_context(|| Message::InvalidConfiguration(path).to_string())
+    }
+
+    /// Runs the action of every rule matching the event, failures are logged.
+    pub async fn react(&self, event: &Event) {
+        let value = match serde_json::to_value(event) {
+            Ok(value) => value,
+            Err(err) => return warn!("Unable to serialize {}: {err}", event.name()),
+        };
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.when.matches(event, &value))
+        {
+            if let Err(err) = rule.then.run(event, &value).await {
+                warn!("Rule on {} failed: {err:#}", event.name());
+            }
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, event: &Event, value: &Value) -> bool {
+        match self {
+            Condition::Event(name) => name == event.name(),
+            Condition::Query(path) => !path.query(value).is_empty(),
+        }
+    }
+}
+
+impl Action {
+    async fn run(&self, event: &Event, value: &Value) -> Result<()> {
+        match self {
+            Action::Command(command) => {
+                info!("{}: running {command}", event.name());
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("LIVEBOX_EVENT", event.name())
+                    .stdin(Stdio::piped())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(value.to_string().as_bytes()).await?;
+                }
+                let status = child.wait().await?;
+                match status.success() {
+                    true => Ok(()),
+                    false => Err(anyhow!(Message::CommandFailed(command, &status).to_string())),
+                }
+            }
+            Action::Webhook(url) => {
+                info!("{}: posting to {url}", event.name());
+                reqwest::Client::new()
+                    .post(url)
+                    .json(value)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(())
+            }
+            Action::Mqtt(publication) => {
+                info!("{}: publishing to {}", event.name(), publication.topic);
+                publication.publish(value.to_string().as_bytes()).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn condition(condition: &str) -> Condition {
+        Condition::try_from(condition.to_string()).unwrap()
+    }
+
+    fn matches(condition: &Condition, event: &Event) -> bool {
+        condition.matches(event, &serde_json::to_value(event).unwrap())
+    }
+
+    fn phone_joined() -> Event {
+        Event::DeviceJoined {
+            device: json!({"Name": "phone", "Active": true}),
+        }
+    }
+
+    #[test]
+    fn matches_events_by_name() {
+        let condition = condition("device-joined");
+        assert!(matches!(condition, Condition::Event(_)));
+        assert!(matches(&condition, &phone_joined()));
+        assert!(!matches(&condition, &Event::WifiToggled { enable: false }));
+    }
+
+    #[test]
+    fn matches_events_selected_by_a_json_path() {
+        let condition = condition("$[?@.Name == 'phone']");
+        assert!(matches!(condition, Condition::Query(_)));
+        assert!(matches(&condition, &phone_joined()));
+        let laptop_joined = Event::DeviceJoined {
+            device: json!({"Name": "laptop", "Active": true}),
+        };
+        assert!(!matches(&condition, &laptop_joined));
+    }
+
+    #[test]
+    fn rejects_invalid_json_paths() {
+        assert!(Condition::try_from("$[?@.Name ==".to_string()).is_err());
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules: Rules = serde_yaml::from_str(
+            "rules:
+              - when: wan-down
+                then:
+                  webhook: https://example.com/hooks/livebox
+              - when: \"$[?@.Name == 'phone']\"
+                then:
+                  command: notify-send \"phone is home\"
+              - when: wifi-toggled
+                then:
+                  mqtt:
+                    broker: mqtt.lan
+                    topic: livebox/wifi
+            ",
+        )
+        .unwrap();
+        assert!(matches!(
+            &rules.rules[..],
+            [
+                Rule {
+                    when: Condition::Event(_),
+                    then: Action::Webhook(_),
+                },
+                Rule {
+                    when: Condition::Query(_),
+                    then: Action::Command(_),
+                },
+                Rule {
+                    when: Condition::Event(_),
+                    then: Action::Mqtt(_),
+                },
+            ]
+        ));
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    env,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::{config, i18n::Message, livebox::ClientBuilder};
+
+/// Commands run by `schedule run` at the times of cron expressions, kept in `schedule.toml` of
+/// the state directory
+pub struct Schedule {
+    path: PathBuf,
+    state: ScheduleState,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScheduleState {
+    #[serde(default)]
+    tasks: Vec<Task>,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Task {
+    id: u32,
+    /// Cron expression, in the local time zone (ex: `0 2 * * *`)
+    cron: String,
+    /// livebox-cli arguments (ex: `["nat", "disable", "game-server"]`)
+    command: Vec<String>,
+}
+
+/// Times matched by the minute, hour, day of month, month and day of week fields of a cron
+/// expression, as bit sets
+struct CronExpression {
+    fields: [u64; 5],
+    /// The day of month or of week starts with `*` (ex: `*/2`), a day must match both fields
+    /// instead of either one, as with cron
+    any_day: bool,
+}
+
+/// Bounds of the cron fields, Sunday is both 0 and 7
+const FIELD_BOUNDS: [(u32, u32); 5] = [(0, 59), (0, 23), (1, 31), (1, 12), (0, 7)];
+
+pub fn parse_cron(expression: &str) -> Result<String, String> {
+    CronExpression::from_str(expression).map(|_| expression.to_string())
+}
+
+impl FromStr for CronExpression {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = || Message::InvalidCronExpression(expression).to_string();
+        let parts: Vec<_> = expression.split_whitespace().collect();
+        if parts.len() != FIELD_BOUNDS.len() {
+            return Err(invalid());
+        }
+        let mut fields = [0; 5];
+        for ((field, part), (min, max)) in fields.iter_mut().zip(&parts).zip(FIELD_BOUNDS) {
+            for item in part.split(',') {
+                let (range, step) = match item.split_once('/') {
+                    Some((range, step)) => (range, step.parse().map_err(|_| invalid())?),
+                    None => (item, 1),
+                };
+                let (first, last) = match range.split_once('-') {
+                    _ if range == "*" => (min, max),
+                    Some((first, last)) => (
+                        first.parse().map_err(|_| invalid())?,
+                        last.parse().map_err(|_| invalid())?,
+                    ),
+                    None => {
+                        let value = range.parse().map_err(|_| invalid())?;
+                        (value, if item.contains('/') { max } else { value })
+                    }
+                };
+                if step == 0 || first < min || last > max || first > last {
+                    return Err(invalid());
+                }
+                for value in (first..=last).step_by(step) {
+                    *field |= 1 << value;
+                }
+            }
+        }
+        // Sunday
+        if fields[4] & 1 << 7 != 0 {
+            fields[4] |= 1;
+        }
+        Ok(Self {
+            fields,
+            any_day: parts[2].starts_with('*') || parts[4].starts_with('*'),
+        })
+    }
+}
+
+impl CronExpression {
+    fn matches(&self, time: &[u32; 5]) -> bool {
+        let matches = |field: usize| self.fields[field] & 1 << time[field] != 0;
+        let day = match self.any_day {
+            true => matches(2) && matches(4),
+            false => matches(2) || matches(4),
+        };
+        matches(0) && matches(1) && matches(3) && day
+    }
+}
+
+impl Schedule {
+    pub fn load() -> Result<Self> {
+        let (path, state) = config::load_state("schedule.toml")?;
+        Ok(Self { path, state })
+    }
+
+    fn save(&self) -> Result<()> {
+        config::save_state(&self.path, &self.state)
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.state.tasks
+    }
+
+    pub fn add(&mut self, cron: String, command: Vec<String>) -> Result<Task> {
+        let id = self
+            .state
+            .tasks
+            .iter()
+            .map(|task| task.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let task = Task { id, cron, command };
+        self.state.tasks.push(task.clone());
+        self.save()?;
+        Ok(task)
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<Task> {
+        let index = self
+            .state
+            .tasks
+            .iter()
+            .position(|task| task.id == id)
+            .ok_or_else(|| anyhow!(Message::NoScheduledTask(id).to_string()))?;
+        let task = self.state.tasks.remove(index);
+        self.save()?;
+        Ok(task)
+    }
+}
+
+impl Task {
+    /// Runs the command in its own session with the connection settings, confirmations are
+    /// skipped.
+    async fn run(&self, connection: &[String], password: &SecretString) -> Result<()> {
+        let status = Command::new(env::current_exe()?)
+            .args(connection)
+            .arg("--yes")
+            .args(&self.command)
+            .env("LIVEBOX_PASSWORD", password.expose_secret())
+            .status()
+            .await?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(anyhow!(Message::CommandFailed(
+                &self.command.join(" "),
+                &status
+            )
+            .to_string())),
+        }
+    }
+}
+
+/// Runs the due tasks at the start of every minute until interrupted, the schedule is reloaded
+/// each time so changes apply without restarting. Failed tasks are logged. The password is read
+/// from the builder source for each task.
+pub async fn run(connection: &[String], builder: &ClientBuilder) -> Result<Value> {
+    loop {
+        let now = now();
+        let minute = now / 60 * 60 + 60;
+        tokio::time::sleep(Duration::from_secs(minute - now)).await;
+        let schedule = match Schedule::load() {
+            Ok(schedule) => schedule,
+            Err(err) => {
+                warn!("Unable to load the schedule: {err:#}");
+                continue;
+            }
+        };
+        let time = local_time(minute);
+        for task in schedule.tasks() {
+            match task.cron.parse::<CronExpression>() {
+                Ok(cron) if cron.matches(&time) => {}
+                Ok(_) => continue,
+                Err(err) => {
+                    warn!("Task {}: {err}", task.id);
+                    continue;
+                }
+            }
+            info!("Running the task {}: {}", task.id, task.command.join(" "));
+            let ran = match builder.password() {
+                Ok(password) => task.run(connection, &password).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = ran {
+                warn!("Task {} failed: {err:#}", task.id);
+            }
+        }
+    }
+}
+
+/// Minute, hour, day of month, month and day of week (0 is Sunday) in the local time zone
+#[cfg(unix)]
+fn local_time(timestamp: u64) -> [u32; 5] {
+    let timestamp = timestamp as libc::time_t;
+    // SAFETY: localtime_r only writes to the given struct, zeroed integers are a valid `tm`
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&timestamp, &mut tm);
+        tm
+    };
+    [tm.tm_min, tm.tm_hour, tm.tm_mday, tm.tm_mon + 1, tm.tm_wday].map(|value| value as u32)
+}
+
+/// Minute, hour, day of month, month and day of week (0 is Sunday) in UTC, the local time zone
+/// is only read on unix
+#[cfg(not(unix))]
+fn local_time(timestamp: u64) -> [u32; 5] {
+    utc_time(timestamp)
+}
+
+/// Minute, hour, day of month, month and day of week (0 is Sunday) in UTC, from the civil
+/// calendar algorithm of Howard Hinnant
+#[cfg(any(not(unix), test))]
+fn utc_time(timestamp: u64) -> [u32; 5] {
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+    // days since 0000-03-01, years starting in March put the leap day last
+    let days_since_march = days + 719_468;
+    let day_of_era = days_since_march % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = match month_from_march {
+        0..=9 => month_from_march + 3,
+        _ => month_from_march - 9,
+    };
+    // 1970-01-01 is a Thursday
+    let day_of_week = (days + 4) % 7;
+    [seconds / 60 % 60, seconds / 3600, day, month, day_of_week].map(|value| value as u32)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cron(expression: &str) -> CronExpression {
+        expression.parse().unwrap()
+    }
+
+    /// Minute, hour, day of month, month and day of week of the times matched in 2024, a leap
+    /// year starting on Monday
+    fn matched(expression: &str) -> Vec<[u32; 5]> {
+        let cron = cron(expression);
+        let days = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        let mut matched = Vec::new();
+        let mut day_of_week = 1;
+        for (month, days) in (1..=12).zip(days) {
+            for day in 1..=days {
+                for hour in 0..24 {
+                    for minute in 0..60 {
+                        let time = [minute, hour, day, month, day_of_week];
+                        if cron.matches(&time) {
+                            matched.push(time);
+                        }
+                    }
+                }
+                day_of_week = (day_of_week + 1) % 7;
+            }
+        }
+        matched
+    }
+
+    #[test]
+    fn matches_values_ranges_steps_and_lists() {
+        assert_eq!(
+            cron("* * * * *").fields,
+            [
+                (1 << 60) - 1,
+                (1 << 24) - 1,
+                (1 << 32) - 2,
+                (1 << 13) - 2,
+                (1 << 8) - 1,
+            ]
+        );
+        let cases: [(&str, &[u32]); 7] = [
+            ("5", &[5]),
+            ("10-13", &[10, 11, 12, 13]),
+            ("*/15", &[0, 15, 30, 45]),
+            ("10-30/10", &[10, 20, 30]),
+            ("50/4", &[50, 54, 58]),
+            ("1,5-6,*/20", &[0, 1, 5, 6, 20, 40]),
+            ("59", &[59]),
+        ];
+        for (minutes, expected) in cases {
+            let expression = format!("{minutes} * * * *");
+            let matched: Vec<_> = (0..60)
+                .filter(|&minute| cron(&expression).matches(&[minute, 12, 15, 6, 6]))
+                .collect();
+            assert_eq!(matched, expected, "{expression}");
+        }
+    }
+
+    #[test]
+    fn matches_sunday_as_0_or_7() {
+        assert_eq!(cron("0 0 * * 0").fields[4], 1);
+        assert_eq!(cron("0 0 * * 7").fields[4], 1 | 1 << 7);
+        assert_eq!(cron("0 0 * * 5-7").fields[4], 1 | 1 << 5 | 1 << 6 | 1 << 7);
+        let sundays = matched("0 0 * * 7");
+        assert_eq!(sundays.len(), 52);
+        assert_eq!(sundays[0], [0, 0, 7, 1, 0]);
+    }
+
+    #[test]
+    fn matches_either_day_when_both_are_restricted() {
+        // the 13th or any Friday
+        let days = matched("0 12 13 * 5");
+        assert_eq!(days.len(), 52 + 12 - 2);
+        assert!(days.contains(&[0, 12, 13, 2, 2]));
+        assert!(days.contains(&[0, 12, 13, 9, 5]));
+        assert!(days.contains(&[0, 12, 5, 1, 5]));
+    }
+
+    #[test]
+    fn matches_both_days_when_one_is_unrestricted() {
+        assert_eq!(matched("0 12 13 * *").len(), 12);
+        assert_eq!(matched("0 12 * * 5").len(), 52);
+        // Mondays on odd days, as the steps start from the first day
+        let days = matched("0 12 */2 * 1");
+        assert!(!days.is_empty());
+        assert!(days.iter().all(|time| time[2] % 2 == 1 && time[4] == 1));
+        assert!(days.contains(&[0, 12, 1, 1, 1]));
+        assert!(!days.contains(&[0, 12, 8, 1, 1]));
+    }
+
+    #[test]
+    fn matches_months() {
+        let matched = matched("30 2 1 1,7 *");
+        assert_eq!(matched, [[30, 2, 1, 1, 1], [30, 2, 1, 7, 1]]);
+    }
+
+    #[test]
+    fn computes_utc_times() {
+        assert_eq!(utc_time(0), [0, 0, 1, 1, 4]);
+        assert_eq!(utc_time(951_782_400), [0, 0, 29, 2, 2]);
+        assert_eq!(utc_time(1_709_210_040), [34, 12, 29, 2, 4]);
+        assert_eq!(utc_time(1_735_689_599), [59, 23, 31, 12, 2]);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 0 *",
+            "* * * 13 *",
+            "* * * * 8",
+            "30-10 * * * *",
+            "*/0 * * * *",
+            "*/x * * * *",
+            "1- * * * *",
+            "-1 * * * *",
+            "*-5 * * * *",
+            "1,,2 * * * *",
+            "mon * * * *",
+        ] {
+            assert_eq!(
+                expression.parse::<CronExpression>().err(),
+                Some(Message::InvalidCronExpression(expression).to_string()),
+                "{expression:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    i18n::Message,
+    livebox::{AccessPointView, Client, ClientBuilder},
+    prompt,
+};
+
+/// Change applied by the setup wizard
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum SetupChange {
+    ConfigureAccessPoint {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ssid: Option<String>,
+        #[serde(skip_serializing)]
+        #[schemars(skip)]
+        passphrase: Option<SecretString>,
+        passphrase_changed: bool,
+    },
+    SetDhcpRange {
+        min_address: String,
+        max_address: String,
+    },
+    DisableWps {
+        access_point: String,
+    },
+    ChangeAdminPassword {
+        #[serde(skip_serializing)]
+        #[schemars(skip)]
+        new_password: SecretString,
+    },
+}
+
+impl SetupChange {
+    /// Order of application: the WiFi changes may drop the connection of the user running the
+    /// wizard, they come last but for the password change
+    fn stage(&self) -> u8 {
+        match self {
+            SetupChange::SetDhcpRange { .. } => 0,
+            SetupChange::DisableWps { .. } => 1,
+            SetupChange::ConfigureAccessPoint { .. } => 2,
+            SetupChange::ChangeAdminPassword { .. } => 3,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SetupReport {
+    changes: Vec<SetupChange>,
+}
+
+/// Walks through the first-boot settings, then applies the answers.
+pub async fn setup(client: &Client, builder: &ClientBuilder) -> Result<Value> {
+    let new_password = match prompt::confirm(Message::SetupAdminPassword.to_string(), true).await? {
+        true => Some(prompt::new_password().await?),
+        false => None,
+    };
+
+    let mut access_points: Vec<_> = client.list_access_points().await?.into_iter().collect();
+    access_points.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut changes = Vec::new();
+    for (name, actual) in &access_points {
+        let ssid = prompt::ask(Message::SetupSsid(name).to_string(), actual.ssid.clone()).await?;
+        let passphrase = prompt::password(Message::SetupPassphrase(name).to_string()).await?;
+        let passphrase = Some(passphrase).filter(|passphrase| {
+            !passphrase.expose_secret().is_empty()
+                && passphrase.expose_secret() != actual.security.key_pass_phrase
+        });
+        let ssid = Some(ssid).filter(|ssid| *ssid != actual.ssid);
+        if ssid.is_some() || passphrase.is_some() {
+            changes.push(SetupChange::ConfigureAccessPoint {
+                name: name.clone(),
+                ssid,
+                passphrase_changed: passphrase.is_some(),
+                passphrase,
+            });
+        }
+    }
+
+    let lan_ip = client.get_lan_ip().await?;
+    let min_address = prompt::ask(
+        Message::SetupDhcpMin.to_string(),
+        lan_ip.dhcp_min_address.clone(),
+    )
+    .await?;
+    let max_address = prompt::ask(
+        Message::SetupDhcpMax.to_string(),
+        lan_ip.dhcp_max_address.clone(),
+    )
+    .await?;
+    if min_address != lan_ip.dhcp_min_address || max_address != lan_ip.dhcp_max_address {
+        changes.push(SetupChange::SetDhcpRange {
+            min_address,
+            max_address,
+        });
+    }
+
+    let wps_enabled = wps_enabled(&access_points);
+    if !wps_enabled.is_empty()
+        && prompt::confirm(Message::SetupDisableWps.to_string(), true).await?
+    {
+        changes.extend(wps_enabled);
+    }
+    if let Some(new_password) = new_password {
+        changes.push(SetupChange::ChangeAdminPassword { new_password });
+    }
+
+    apply(client, builder, changes).await
+}
+
+/// WPS changes of the access points with WPS enabled, or not reporting its state
+fn wps_enabled(access_points: &[(String, AccessPointView)]) -> Vec<SetupChange> {
+    access_points
+        .iter()
+        .filter(|(_, actual)| actual.wps.as_ref().is_none_or(|wps| wps.enable))
+        .map(|(name, _)| SetupChange::DisableWps {
+            access_point: name.clone(),
+        })
+        .collect()
+}
+
+/// Applies the changes by stage, then logs in again when the administration password changed.
+///
+/// The administration password is changed last so that a failure doesn't leave the other
+/// settings unapplied with an unknown password.
+async fn apply(
+    client: &Client,
+    builder: &ClientBuilder,
+    mut changes: Vec<SetupChange>,
+) -> Result<Value> {
+    let username = builder
+        .username()
+        .ok_or_else(|| anyhow!(Message::MissingCredentials.to_string()))?;
+    changes.sort_by_key(SetupChange::stage);
+    for change in &changes {
+        match change {
+            SetupChange::ConfigureAccessPoint {
+                name,
+                ssid,
+                passphrase,
+                ..
+            } => {
+                client
+                    .configure_access_point(
+                        name.clone(),
+                        ssid.clone(),
+                        passphrase
+                            .as_ref()
+                            .map(|passphrase| passphrase.expose_secret().to_string()),
+                    )
+                    .await?
+            }
+            SetupChange::SetDhcpRange {
+                min_address,
+                max_address,
+            } => {
+                client
+                    .set_dhcp_range(min_address.clone(), max_address.clone())
+                    .await?
+            }
+            SetupChange::DisableWps { access_point } => {
+                client.set_wps_enable(access_point.clone(), false).await?
+            }
+            SetupChange::ChangeAdminPassword { new_password } => {
+                client
+                    .change_password(
+                        username.to_string(),
+                        builder.password()?,
+                        new_password.clone(),
+                    )
+                    .await?
+            }
+        };
+    }
+    if let Some(SetupChange::ChangeAdminPassword { new_password }) = changes.last() {
+        let verification = builder
+            .clone()
+            .with_credentials(username.to_string(), new_password.clone())
+            .build()
+            .await
+            .context(Message::PasswordNotVerified.to_string())?;
+        verification.logout().await?;
+    }
+    Ok(serde_json::to_value(SetupReport { changes })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use livebox_cli::livebox::test_support::{
+        MockLiveboxBuilder, DEFAULT_PASSWORD, DEFAULT_USERNAME,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn skips_access_points_without_wps() {
+        let access_point = |wps: Value| {
+            let mut mib = json!({"SSID": "Livebox-1234", "Security": {"KeyPassPhrase": "secret"}});
+            if !wps.is_null() {
+                mib["WPS"] = wps;
+            }
+            serde_json::from_value(mib).unwrap()
+        };
+        let access_points = [
+            ("wl0".to_string(), access_point(json!({"Enable": true}))),
+            ("wl1".to_string(), access_point(json!({"Enable": false}))),
+            ("wl2".to_string(), access_point(Value::Null)),
+        ];
+        let changes = serde_json::to_value(wps_enabled(&access_points)).unwrap();
+        assert_eq!(
+            changes,
+            json!([
+                {"action": "disable_wps", "access_point": "wl0"},
+                {"action": "disable_wps", "access_point": "wl2"},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn changes_the_wifi_then_the_password_and_logs_in_again() {
+        let lan_ip = json!({
+            "Address": "192.168.1.1",
+            "Netmask": "255.255.255.0",
+            "DHCPEnable": true,
+            "DHCPMinAddress": "192.168.1.10",
+            "DHCPMaxAddress": "192.168.1.150",
+        });
+        let livebox = MockLiveboxBuilder::default()
+            .with_response("NMC", "getLANIP", json!({"status": true, "data": lan_ip}))
+            .start()
+            .unwrap();
+        let builder = livebox
+            .client_builder()
+            .with_password_source(DEFAULT_USERNAME.to_string(), || {
+                Ok(DEFAULT_PASSWORD.to_string().into())
+            });
+        let client = builder.clone().build().await.unwrap();
+        let changes = vec![
+            SetupChange::ConfigureAccessPoint {
+                name: "wl0".to_string(),
+                ssid: Some("Home".to_string()),
+                passphrase: None,
+                passphrase_changed: false,
+            },
+            SetupChange::ChangeAdminPassword {
+                new_password: "new-secret".to_string().into(),
+            },
+            SetupChange::DisableWps {
+                access_point: "wl0".to_string(),
+            },
+            SetupChange::SetDhcpRange {
+                min_address: "192.168.1.10".to_string(),
+                max_address: "192.168.1.99".to_string(),
+            },
+        ];
+
+        apply(&client, &builder, changes).await.unwrap();
+        client.logout().await.unwrap();
+
+        let changes: Vec<_> = livebox
+            .requests()
+            .into_iter()
+            .filter(|request| request["method"] != "getLANIP")
+            .map(|request| request["parameters"].clone())
+            .collect();
+        assert_eq!(changes.len(), 8, "{changes:#?}");
+        // DHCP range, then WPS, then the access point, then the password
+        assert_eq!(changes[1]["DHCPMinAddress"], "192.168.1.10");
+        assert_eq!(changes[2]["mibs"]["wlanvap"]["wl0"]["WPS"]["Enable"], false);
+        assert_eq!(changes[3]["mibs"]["wlanvap"]["wl0"]["SSID"], "Home");
+        assert_eq!(changes[4]["name"], DEFAULT_USERNAME);
+        // logged in again with the new password
+        assert_eq!(changes[5]["username"], DEFAULT_USERNAME);
+        assert_eq!(livebox.open_contexts(), 0);
+        builder
+            .with_credentials(
+                DEFAULT_USERNAME.to_string(),
+                "new-secret".to_string().into(),
+            )
+            .build()
+            .await
+            .unwrap()
+            .logout()
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use std::future::Future;
+
+use log::debug;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+/// Shared by long-running subsystems to stop on SIGINT/SIGTERM (Ctrl-C on other platforms),
+/// leaving room to release the session.
+#[derive(Clone)]
+pub struct Shutdown {
+    requested: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Traps SIGINT and SIGTERM, must be called from within the tokio runtime.
+    pub fn install() -> std::io::Result<Self> {
+        let (sender, requested) = watch::channel(false);
+        let received = signals()?;
+        tokio::spawn(async move {
+            received.await;
+            sender.send_replace(true);
+        });
+        Ok(Self { requested })
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// Waits until a shutdown is requested.
+    pub async fn requested(&self) {
+        let mut requested = self.requested.clone();
+        // the sender is never dropped before sending
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+
+    /// Runs the future until completion, or cancels it when a shutdown is requested.
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        if self.is_requested() {
+            return None;
+        }
+        tokio::select! {
+            output = future => Some(output),
+            _ = self.requested() => None,
+        }
+    }
+}
+
+/// Completes on the first SIGINT or SIGTERM
+#[cfg(unix)]
+fn signals() -> std::io::Result<impl Future<Output = ()>> {
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+    Ok(async move {
+        tokio::select! {
+            _ = sigint.recv() => debug!("SIGINT received"),
+            _ = sigterm.recv() => debug!("SIGTERM received"),
+        }
+    })
+}
+
+/// Completes on the first Ctrl-C
+#[cfg(not(unix))]
+fn signals() -> std::io::Result<impl Future<Output = ()>> {
+    Ok(async {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => debug!("Ctrl-C received"),
+            Err(err) => {
+                debug!("Unable to listen for Ctrl-C: {err}");
+                std::future::pending().await
+            }
+        }
+    })
+}
@@ -0,0 +1,147 @@
+use std::{env, fmt::Write, path::Path, time::Duration};
+#[cfg(target_os = "linux")]
+use std::{
+    net::TcpListener,
+    os::{
+        fd::FromRawFd,
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    process,
+};
+
+use anyhow::Result;
+#[cfg(target_os = "linux")]
+use log::debug;
+
+use crate::{ApiFlavor, CliArgs};
+
+/// Sends a state (ex: `READY=1`) to the service manager, when started by systemd with
+/// `Type=notify`.
+#[cfg(target_os = "linux")]
+pub fn notify(state: &str) {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let path = path.to_string_lossy();
+    let result = (|| {
+        let address = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(path.as_ref())?,
+        };
+        UnixDatagram::unbound()?.send_to_addr(state.as_bytes(), &address)
+    })();
+    if let Err(err) = result {
+        debug!("Unable to notify {state} to {path}: {err}");
+    }
+}
+
+/// systemd only runs on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn notify(_state: &str) {}
+
+/// Interval between two watchdog keep-alives, half of the `WatchdogSec=` of the unit
+#[cfg(target_os = "linux")]
+pub fn watchdog_interval() -> Option<Duration> {
+    let pid = env::var("WATCHDOG_PID").ok();
+    if pid.is_some_and(|pid| pid != process::id().to_string()) {
+        return None;
+    }
+    env::var("WATCHDOG_USEC")
+        .ok()?
+        .parse()
+        .ok()
+        .map(|usec| Duration::from_micros(usec) / 2)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watchdog_interval() -> Option<Duration> {
+    None
+}
+
+/// First file descriptor passed by socket activation
+#[cfg(target_os = "linux")]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Listening socket passed by systemd socket activation (`LISTEN_FDS`), when started by a
+/// `.socket` unit. Only the first socket is used.
+#[cfg(target_os = "linux")]
+pub fn activated_listener() -> Option<TcpListener> {
+    if env::var("LISTEN_PID").ok()? != process::id().to_string() {
+        return None;
+    }
+    let count: u32 = env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    if count == 0 {
+        return None;
+    }
+    // SAFETY: systemd passes the sockets to this process from LISTEN_FDS_START, they aren't
+    // owned by anything else
+    Some(unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START) })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn activated_listener() -> Option<std::net::TcpListener> {
+    None
+}
+
+/// Unit running the daemon with the current binary, connection settings and rules.
+///
+/// The password is read from `LIVEBOX_PASSWORD` in the `livebox-cli/env` file of the
+/// configuration directory.
+pub fn unit(args: &CliArgs, rules: Option<&Path>, interval: u64) -> Result<String> {
+    let mut command = vec![env::current_exe()?.display().to_string()];
+    command.extend(connection_args(args)?);
+    command.push("daemon".to_string());
+    if let Some(rules) = rules {
+        command.push(format!("--rules={}", rules.canonicalize()?.display()));
+    }
+    command.push(format!("--interval={interval}"));
+
+    let mut unit = String::new();
+    writeln!(unit, "[Unit]")?;
+    writeln!(unit, "Description=livebox-cli daemon")?;
+    writeln!(unit, "Wants=network-online.target")?;
+    writeln!(unit, "After=network-online.target")?;
+    writeln!(unit)?;
+    writeln!(unit, "[Service]")?;
+    writeln!(unit, "Type=notify")?;
+    writeln!(unit, "# LIVEBOX_PASSWORD=...")?;
+    writeln!(unit, "EnvironmentFile=%E/livebox-cli/env")?;
+    let command: Vec<_> = command.iter().map(|arg| quote(arg)).collect();
+    writeln!(unit, "ExecStart={}", command.join(" "))?;
+    writeln!(unit, "WatchdogSec={}", interval.max(20) * 3)?;
+    writeln!(unit, "Restart=on-failure")?;
+    writeln!(unit)?;
+    writeln!(unit, "[Install]")?;
+    writeln!(unit, "WantedBy=default.target")?;
+    Ok(unit)
+}
+
+/// Arguments giving the connection settings to another livebox-cli process, but the password
+pub fn connection_args(args: &CliArgs) -> Result<Vec<String>> {
+    let mut connection = vec![
+        format!("--base-url={}", args.livebox_api_baseurl),
+        format!("--username={}", args.username),
+    ];
+    match args.api_flavor {
+        ApiFlavor::Ws => connection.push("--api-flavor=ws".to_string()),
+        ApiFlavor::Legacy => connection.push("--api-flavor=legacy".to_string()),
+        ApiFlavor::Auto => {}
+    }
+    if let Some(profile) = &args.profile {
+        connection.push(format!("--profile={profile}"));
+    }
+    if let Some(config) = &args.config {
+        connection.push(format!("--config={}", config.canonicalize()?.display()));
+    }
+    Ok(connection)
+}
+
+/// Escapes an `ExecStart=` argument
+fn quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    match arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        true => format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")),
+        false => arg,
+    }
+}
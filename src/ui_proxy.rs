@@ -0,0 +1,320 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use reqwest::{
+    header::{HeaderName, HeaderValue, CONTENT_LENGTH, COOKIE, ORIGIN, REFERER, SET_COOKIE},
+    redirect::Policy,
+    Method,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{i18n::Message, livebox::Client, systemd};
+
+/// Largest request head accepted from the browser
+const MAX_HEAD_LENGTH: usize = 64 * 1024;
+/// Largest request body accepted from the browser, the web UI posts JSON calls and configuration
+/// backups
+const MAX_BODY_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Headers describing the connection to the proxy, not forwarded
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// The Livebox web UI and the session shared with the browser
+struct Upstream {
+    http_client: reqwest::Client,
+    base_url: String,
+    cookie: Option<String>,
+    context_id: String,
+}
+
+/// Request refused before reaching the Livebox
+struct Rejection {
+    status: &'static str,
+    message: String,
+}
+
+struct ProxiedRequest {
+    method: Method,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Serves the Livebox web UI on a local address until interrupted, every request carrying the
+/// session cookies and context so the UI needs no login. The socket passed by systemd socket
+/// activation takes precedence over the address.
+pub async fn serve(client: &Client, base_url: &str, listen: SocketAddr) -> Result<()> {
+    let upstream = Arc::new(Upstream::new(client, base_url)?);
+    let listener = match systemd::activated_listener() {
+        Some(listener) => {
+            listener.set_nonblocking(true)?;
+            TcpListener::from_std(listener)?
+        }
+        None => TcpListener::bind(listen)
+            .await
+            .with_context(|| Message::UnableToListen(listen).to_string())?,
+    };
+    info!(
+        "Serving the Livebox web UI on http://{}/",
+        listener.local_addr()?
+    );
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let upstream = upstream.clone();
+        tokio::spawn(async move {
+            if let Err(err) = proxy(stream, &upstream).await {
+                warn!("Unable to proxy a request from {peer}: {err:#}");
+            }
+        });
+    }
+}
+
+impl Upstream {
+    fn new(client: &Client, base_url: &str) -> Result<Self> {
+        Ok(Self {
+            http_client: reqwest::Client::builder()
+                .redirect(Policy::none())
+                .build()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            cookie: client.cookie_header(),
+            context_id: client.context_id().to_string(),
+        })
+    }
+}
+
+/// Forwards a single request, the connection is closed once answered.
+async fn proxy(stream: TcpStream, upstream: &Upstream) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let response = match read_request(&mut stream).await? {
+        Ok(request) => {
+            debug!("{} {}", request.method, request.path);
+            forward(upstream, request).await.map_err(|err| Rejection {
+                status: "502 Bad Gateway",
+                message: format!("{err:#}"),
+            })
+        }
+        Err(rejection) => Err(rejection),
+    };
+    let stream = stream.get_mut();
+    match response {
+        Ok(response) => stream.write_all(&response).await?,
+        Err(Rejection { status, message }) => {
+            let head = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                message.len()
+            );
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(message.as_bytes()).await?;
+        }
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Reads the request of the browser, requests that can't be forwarded are rejected.
+async fn read_request(
+    stream: &mut BufReader<TcpStream>,
+) -> Result<Result<ProxiedRequest, Rejection>> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read_until(b'\n', &mut head).await? == 0 || head.len() > MAX_HEAD_LENGTH {
+            return Err(anyhow!(Message::IncompleteRequestHead.to_string()));
+        }
+    }
+    let head = String::from_utf8(head)?;
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(anyhow!(
+            Message::InvalidRequestLine(request_line).to_string()
+        ));
+    };
+    let headers: Vec<_> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    };
+    if header("transfer-encoding").is_some() {
+        return Err(anyhow!(Message::ChunkedBodyUnsupported.to_string()));
+    }
+    let length: usize = header("content-length").unwrap_or("0").parse()?;
+    if length > MAX_BODY_LENGTH {
+        return Ok(Err(Rejection {
+            status: "413 Payload Too Large",
+            message: Message::RequestBodyTooLarge(MAX_BODY_LENGTH).to_string(),
+        }));
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await?;
+    Ok(Ok(ProxiedRequest {
+        method: Method::from_bytes(method.as_bytes())?,
+        path: path.to_string(),
+        headers,
+        body,
+    }))
+}
+
+/// Sends the request to the Livebox within the session, returns the raw HTTP response.
+async fn forward(upstream: &Upstream, request: ProxiedRequest) -> Result<Vec<u8>> {
+    let url = format!("{}{}", upstream.base_url, request.path);
+    let mut builder = upstream.http_client.request(request.method, &url);
+    let mut cookies = upstream.cookie.iter().cloned().collect::<Vec<_>>();
+    for (name, value) in request.headers {
+        match name.as_str() {
+            name if HOP_BY_HOP_HEADERS.contains(&name) => {}
+            "x-context" => {}
+            "cookie" => cookies.push(value),
+            // the Livebox rejects calls from other origins
+            "origin" => builder = builder.header(ORIGIN, &upstream.base_url),
+            "referer" => builder = builder.header(REFERER, format!("{}/", upstream.base_url)),
+            name => {
+                builder = builder.header(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(&value)?,
+                )
+            }
+        }
+    }
+    if !cookies.is_empty() {
+        builder = builder.header(COOKIE, cookies.join("; "));
+    }
+    let response = builder
+        .header("x-context", &upstream.context_id)
+        .body(request.body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    for (name, value) in response.headers() {
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        let value = value.to_str().unwrap_or_default();
+        let value = match name == SET_COOKIE {
+            // cookies are set for the proxy host
+            true => value
+                .split(';')
+                .filter(|attribute| !attribute.trim().to_ascii_lowercase().starts_with("domain="))
+                .collect::<Vec<_>>()
+                .join(";"),
+            false => value.to_string(),
+        };
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    let body = response.bytes().await?;
+    head.push_str(&format!(
+        "{CONTENT_LENGTH}: {}\r\nconnection: close\r\n\r\n",
+        body.len()
+    ));
+    let mut raw = head.into_bytes();
+    raw.extend_from_slice(&body);
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use livebox_cli::livebox::test_support::{
+        MockLiveboxBuilder, DEFAULT_PASSWORD, DEFAULT_USERNAME,
+    };
+
+    use super::*;
+
+    /// Sends a request through the proxy and returns the raw response.
+    async fn round_trip(upstream: Upstream, request: &[u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            proxy(stream, &upstream).await.unwrap();
+        });
+        let mut browser = TcpStream::connect(address).await.unwrap();
+        browser.write_all(request).await.unwrap();
+        let mut response = String::new();
+        browser.read_to_string(&mut response).await.unwrap();
+        server.await.unwrap();
+        response
+    }
+
+    fn post(body: &str, headers: &str) -> Vec<u8> {
+        format!(
+            "POST /ws HTTP/1.1\r\nHost: localhost\r\n{headers}Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn forwards_requests_within_the_session() {
+        let livebox = MockLiveboxBuilder::default()
+            .with_cookie_domain("127.0.0.1")
+            .start()
+            .unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+        let session_cookie = client.cookie_header().unwrap();
+        let upstream = || Upstream::new(&client, &livebox.base_url()).unwrap();
+
+        let get_users = r#"{"service":"UserManagement","method":"getUsers","parameters":{}}"#;
+        let response = round_trip(upstream(), &post(get_users, "Cookie: lang=fr\r\n")).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#""name":"admin""#));
+        assert_eq!(
+            livebox.cookies().last().unwrap(),
+            &format!("{session_cookie}; lang=fr")
+        );
+
+        let login = format!(
+            r#"{{"service":"sah.Device.Information","method":"createContext","parameters":{{"username":"{DEFAULT_USERNAME}","password":"{DEFAULT_PASSWORD}"}}}}"#
+        );
+        let response = round_trip(upstream(), &post(&login, "")).await;
+        let set_cookie = response
+            .lines()
+            .find(|line| line.starts_with("set-cookie:"))
+            .unwrap();
+        assert!(set_cookie.contains("sessid="));
+        assert!(!set_cookie.to_ascii_lowercase().contains("domain="));
+        client.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_large_bodies() {
+        let livebox = MockLiveboxBuilder::default().start().unwrap();
+        let client = livebox.client_builder().build().await.unwrap();
+        let upstream = Upstream::new(&client, &livebox.base_url()).unwrap();
+        let request = format!(
+            "POST /ws HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_LENGTH + 1
+        );
+        let requests = livebox.requests().len();
+
+        let response = round_trip(upstream, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+        assert_eq!(livebox.requests().len(), requests);
+        client.logout().await.unwrap();
+    }
+}
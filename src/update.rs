@@ -0,0 +1,242 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    env::{self, consts},
+    fs,
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(windows)]
+use log::debug;
+use log::info;
+use reqwest::Client;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::Message;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/jeremiehuchet/livebox-cli/releases/latest";
+/// Overrides the URL of the latest release (ex: a mirror of the GitHub API)
+const RELEASE_URL_VARIABLE: &str = "LIVEBOX_CLI_RELEASE_URL";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+/// Detached Ed25519 signature of the checksums, base64 encoded
+const SIGNATURE_ASSET: &str = "SHA256SUMS.sig";
+/// Base64 encoded Ed25519 public key signing the releases, given when building release binaries
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("LIVEBOX_CLI_RELEASE_PUBLIC_KEY");
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| anyhow!(Message::MissingReleaseAsset(name).to_string()))
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UpdateReport {
+    current_version: String,
+    latest_version: String,
+    updated: bool,
+}
+
+/// Replaces the running binary with the latest GitHub release, once its SHA-256 checksum is
+/// verified against the `SHA256SUMS` release asset, itself signed by the release key embedded in
+/// the binary.
+pub async fn self_update(check_only: bool) -> Result<Value> {
+    let http_client = Client::builder()
+        .user_agent(concat!("livebox-cli/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release_url =
+        env::var(RELEASE_URL_VARIABLE).unwrap_or_else(|_| LATEST_RELEASE_URL.to_string());
+    let release: Release = http_client
+        .get(release_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let current_version = env!("CARGO_PKG_VERSION");
+    let latest_version = release.tag_name.trim_start_matches('v');
+    let mut report = UpdateReport {
+        current_version: current_version.to_string(),
+        latest_version: latest_version.to_string(),
+        updated: false,
+    };
+    if check_only || !is_newer(latest_version, current_version) {
+        return Ok(serde_json::to_value(report)?);
+    }
+
+    let binary_name = format!(
+        "livebox-cli-{}-{}{}",
+        consts::ARCH,
+        consts::OS,
+        consts::EXE_SUFFIX
+    );
+    let binary_asset = release.asset(&binary_name)?;
+    let public_key =
+        RELEASE_PUBLIC_KEY.ok_or_else(|| anyhow!(Message::MissingReleaseKey.to_string()))?;
+    let checksums = download(&http_client, release.asset(CHECKSUMS_ASSET)?).await?;
+    let signature = download(&http_client, release.asset(SIGNATURE_ASSET)?).await?;
+    verify_signature(public_key, &checksums, &signature)?;
+    let expected_checksum = String::from_utf8_lossy(&checksums)
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == binary_name)
+        .map(|(checksum, _)| checksum.to_ascii_lowercase())
+        .ok_or_else(|| anyhow!(Message::MissingChecksum(&binary_name).to_string()))?;
+
+    let binary = download(&http_client, binary_asset).await?;
+    let actual_checksum: String = Sha256::digest(&binary)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if actual_checksum != expected_checksum {
+        return Err(anyhow!(Message::ChecksumMismatch(&binary_name).to_string()));
+    }
+
+    // written next to the running binary so the final rename stays on the same filesystem
+    let current_exe = env::current_exe()?;
+    let new_exe = current_exe.with_extension("new");
+    fs::write(&new_exe, &binary)?;
+    #[cfg(unix)]
+    fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))?;
+    replace(&current_exe, &new_exe)?;
+    info!("{} replaced by {}", current_exe.display(), release.tag_name);
+
+    report.updated = true;
+    Ok(serde_json::to_value(report)?)
+}
+
+/// Replaces the running binary, atomically on unix
+#[cfg(not(windows))]
+fn replace(current_exe: &Path, new_exe: &Path) -> Result<()> {
+    Ok(fs::rename(new_exe, current_exe)?)
+}
+
+/// Replaces the running binary, which Windows doesn't allow to overwrite but allows to move
+/// aside. The previous binary is removed by the next update.
+#[cfg(windows)]
+fn replace(current_exe: &Path, new_exe: &Path) -> Result<()> {
+    let old_exe = current_exe.with_extension("old");
+    if let Err(err) = fs::remove_file(&old_exe) {
+        debug!("{} not removed: {err}", old_exe.display());
+    }
+    fs::rename(current_exe, &old_exe)?;
+    fs::rename(new_exe, current_exe).or_else(|err| {
+        fs::rename(&old_exe, current_exe)?;
+        Err(err)
+    })?;
+    Ok(())
+}
+
+/// Checks the Ed25519 signature of the checksums against the release public key.
+fn verify_signature(public_key: &str, checksums: &[u8], signature: &[u8]) -> Result<()> {
+    let invalid = || anyhow!(Message::InvalidSignature(CHECKSUMS_ASSET).to_string());
+    let public_key = STANDARD.decode(public_key.trim()).map_err(|_| invalid())?;
+    let signature = STANDARD
+        .decode(String::from_utf8_lossy(signature).trim())
+        .map_err(|_| invalid())?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(checksums, &signature)
+        .map_err(|_| invalid())
+}
+
+async fn download(http_client: &Client, asset: &Asset) -> Result<Vec<u8>> {
+    info!("Downloading {}", asset.browser_download_url);
+    let bytes = http_client
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+/// Compares dotted numeric versions, pre-release suffixes are ignored.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+
+    use super::*;
+
+    const CHECKSUMS: &[u8] = b"0123abcd  livebox-cli-x86_64-linux\n";
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn accepts_checksums_signed_by_the_release_key() {
+        let signer = key_pair();
+        let public_key = STANDARD.encode(signer.public_key());
+        let signature = STANDARD.encode(signer.sign(CHECKSUMS));
+        assert!(
+            verify_signature(&public_key, CHECKSUMS, format!("{signature}\n").as_bytes()).is_ok()
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_checksums_and_other_keys() {
+        let signer = key_pair();
+        let public_key = STANDARD.encode(signer.public_key());
+        let signature = STANDARD.encode(signer.sign(CHECKSUMS));
+        let tampered = b"ffffffff  livebox-cli-x86_64-linux\n";
+        assert!(verify_signature(&public_key, tampered, signature.as_bytes()).is_err());
+
+        let other_key = STANDARD.encode(key_pair().public_key());
+        assert!(verify_signature(&other_key, CHECKSUMS, signature.as_bytes()).is_err());
+        assert!(verify_signature(&public_key, CHECKSUMS, b"not base64").is_err());
+    }
+
+    #[test]
+    fn accepts_checksums_signed_by_openssl() {
+        // signed as by the release workflow:
+        // openssl pkeyutl -sign -inkey release-key.pem -rawin -in SHA256SUMS | base64 -w0
+        let public_key = "YX+dlYb1GMFcR03T4hw31/ElvKIY59VJfHTeUFvemWg=";
+        let signature = concat!(
+            "+tfsobQFvnF8Xi1qzfHkcg9ydPE4C+JXCN4BR9qf3+WyaLvAzxmBuTR01iaN",
+            "UBoT3l+n1zbA7yCDxUvRB6F/Bw==",
+        );
+        assert!(verify_signature(public_key, CHECKSUMS, signature.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert!(is_newer("1.10.0", "1.9.3"));
+        assert!(!is_newer("1.2.0-rc1", "1.2.0"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use livebox_cli::livebox::{
+    test_support::{MockLivebox, MockLiveboxBuilder, DEFAULT_PASSWORD, DEFAULT_USERNAME},
+    Protocol, SetPortFowardingParams,
+};
+use serde_json::Value;
+
+/// Runs livebox-cli against the mock Livebox, with its own configuration and state directories.
+fn livebox_cli(livebox: &MockLivebox, name: &str, args: &[&str]) -> Output {
+    livebox_cli_as(livebox, name, (DEFAULT_USERNAME, DEFAULT_PASSWORD), args)
+}
+
+fn livebox_cli_as(
+    livebox: &MockLivebox,
+    name: &str,
+    (username, password): (&str, &str),
+    args: &[&str],
+) -> Output {
+    let home: PathBuf = env::temp_dir().join(format!("livebox-cli-{name}-{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_livebox-cli"))
+        .args(["--base-url", &livebox.base_url(), "-u", username])
+        .args(args)
+        .env("LIVEBOX_PASSWORD", password)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_STATE_HOME", home.join("state"))
+        .output()
+        .unwrap();
+    fs::remove_dir_all(home).ok();
+    output
+}
+
+fn mock_livebox() -> MockLivebox {
+    MockLiveboxBuilder::default()
+        .with_user("guest", "secret", &["http"])
+        .with_nat_rule(SetPortFowardingParams::new(
+            "ssh".to_string(),
+            "SSH".to_string(),
+            Protocol::Tcp,
+            "22".to_string(),
+            "22".to_string(),
+            "192.168.1.10".to_string(),
+        ))
+        .start()
+        .unwrap()
+}
+
+#[test]
+fn lists_nat_rules() {
+    let livebox = mock_livebox();
+    let output = livebox_cli(&livebox, "list", &["nat", "list"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let rules: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(rules["status"]["webui_ssh"]["ExternalPort"], "22");
+    assert_eq!(livebox.open_contexts(), 0);
+}
+
+#[test]
+fn disables_a_nat_rule() {
+    let livebox = mock_livebox();
+    let output = livebox_cli(&livebox, "disable", &["nat", "disable", "webui_ssh"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!livebox.nat_rules()[0].enable);
+}
+
+#[test]
+fn refuses_destructive_commands_without_a_terminal() {
+    let livebox = mock_livebox();
+    let output = livebox_cli(&livebox, "remove", &["nat", "remove", "webui_ssh"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Aborted") && stderr.contains("--yes"),
+        "{stderr}"
+    );
+    assert_eq!(livebox.nat_rules().len(), 1);
+
+    let args = ["--yes", "nat", "remove", "webui_ssh"];
+    let output = livebox_cli(&livebox, "remove-yes", &args);
+    assert!(output.status.success(), "{output:?}");
+    assert!(livebox.nat_rules().is_empty());
+}
+
+#[test]
+fn restricted_accounts_fail_before_changes_are_sent() {
+    let livebox = mock_livebox();
+    let guest = ("guest", "secret");
+    let output = livebox_cli_as(&livebox, "guest", guest, &["nat", "disable", "webui_ssh"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("requires the admin group"), "{stderr}");
+    assert!(livebox.nat_rules()[0].enable);
+    assert!(!livebox
+        .requests()
+        .iter()
+        .any(|request| request["method"] == "setPortForwarding"));
+
+    // nothing is sent when only checking
+    let args = ["--check", "nat", "disable", "webui_ssh"];
+    let output = livebox_cli_as(&livebox, "guest-check", guest, &args);
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[cfg(feature = "self-update")]
+#[test]
+fn self_update_only_reports_the_latest_version_with_check() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let release_url = format!("http://{}/releases/latest", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        let body = r#"{"tag_name": "v99.0.0", "assets": []}"#;
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close";
+        write!(
+            reader.get_mut(),
+            "{head}\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+    });
+
+    let home = env::temp_dir().join(format!("livebox-cli-self-update-{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_livebox-cli"))
+        .args(["self-update", "--check"])
+        .env("LIVEBOX_CLI_RELEASE_URL", release_url)
+        .env_remove("LIVEBOX_PASSWORD")
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_STATE_HOME", home.join("state"))
+        .output()
+        .unwrap();
+    fs::remove_dir_all(home).ok();
+    server.join().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["latest_version"], "99.0.0");
+    assert_eq!(report["updated"], false);
+}
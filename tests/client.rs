@@ -0,0 +1,243 @@
+use std::{collections::HashMap, env, fs};
+
+use livebox_cli::livebox::{
+    test_support::{MockLivebox, MockLiveboxBuilder},
+    AuditLog, InvalidCredentials, PermissionDenied, Protocol, SetPortFowardingParams,
+};
+use serde_json::json;
+
+fn ssh_rule() -> SetPortFowardingParams {
+    SetPortFowardingParams::new(
+        "ssh".to_string(),
+        "SSH".to_string(),
+        Protocol::Tcp,
+        "22".to_string(),
+        "22".to_string(),
+        "192.168.1.10".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn releases_the_context_on_logout() {
+    let livebox = MockLivebox::start().unwrap();
+    let client = livebox.client_builder().build().await.unwrap();
+    assert_eq!(client.groups(), ["http", "admin"]);
+    assert_eq!(livebox.open_contexts(), 1);
+
+    client.logout().await.unwrap();
+    assert_eq!(livebox.open_contexts(), 0);
+}
+
+#[tokio::test]
+async fn rejects_invalid_credentials() {
+    let livebox = MockLivebox::start().unwrap();
+    let err = livebox
+        .client_builder()
+        .with_credentials("admin".to_string(), "wrong".to_string().into())
+        .build()
+        .await
+        .err()
+        .unwrap();
+    assert!(err.is::<InvalidCredentials>());
+}
+
+#[tokio::test]
+async fn answers_calls_with_fixtures() {
+    let wan_status = json!({"status": true, "data": {"WanState": "up"}});
+    let livebox = MockLiveboxBuilder::default()
+        .with_response("NMC", "getWANStatus", wan_status.clone())
+        .start()
+        .unwrap();
+    let client = livebox.client_builder().build().await.unwrap();
+
+    let response = client
+        .execute_with_parameters("NMC", "getWANStatus", HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(response, wan_status);
+    client.logout().await.unwrap();
+
+    let requests = livebox.requests();
+    assert_eq!(requests[0]["parameters"]["password"], "********");
+    assert_eq!(requests[1]["method"], "getWANStatus");
+}
+
+#[tokio::test]
+async fn edits_nat_rules() {
+    let livebox = MockLiveboxBuilder::default()
+        .with_nat_rule(ssh_rule())
+        .start()
+        .unwrap();
+    let client = livebox.client_builder().build().await.unwrap();
+
+    client
+        .disable_nat_rule("webui_ssh".to_string())
+        .await
+        .unwrap();
+    let rules = livebox.nat_rules();
+    assert_eq!(rules.len(), 1);
+    assert!(!rules[0].enable);
+
+    client
+        .remove_nat_rule("webui_ssh".to_string())
+        .await
+        .unwrap();
+    assert!(client.structured_nat_rules().await.unwrap().is_empty());
+    client.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn denies_nat_changes_to_restricted_users() {
+    let livebox = MockLiveboxBuilder::default()
+        .with_user("guest", "secret", &["http"])
+        .start()
+        .unwrap();
+    let client = livebox
+        .client_builder()
+        .with_credentials("guest".to_string(), "secret".to_string().into())
+        .build()
+        .await
+        .unwrap();
+
+    let err = client.add_nat_rule(ssh_rule()).await.unwrap_err();
+    assert!(err.is::<PermissionDenied>());
+    assert!(livebox.nat_rules().is_empty());
+    client.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn forgets_the_given_password_once_logged_in() {
+    let livebox = MockLivebox::start().unwrap();
+    let builder = livebox.client_builder();
+    let client = builder.clone().build().await.unwrap();
+    assert!(builder.password().is_err());
+    client.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn reads_the_password_source_at_each_login() {
+    let livebox = MockLivebox::start().unwrap();
+    let builder = livebox
+        .client_builder()
+        .with_password_source("admin".to_string(), || Ok("admin".to_string().into()));
+    for _ in 0..2 {
+        let client = builder.clone().build().await.unwrap();
+        client.logout().await.unwrap();
+    }
+    assert_eq!(livebox.open_contexts(), 0);
+}
+
+fn sent(livebox: &MockLivebox, method: &str) -> bool {
+    livebox
+        .requests()
+        .iter()
+        .any(|request| request["method"] == method)
+}
+
+#[tokio::test]
+async fn restricted_accounts_fail_before_changes_are_sent() {
+    let livebox = MockLiveboxBuilder::default()
+        .with_user("guest", "secret", &["http"])
+        .start()
+        .unwrap();
+    let client = livebox
+        .client_builder()
+        .with_credentials("guest".to_string(), "secret".to_string().into())
+        .with_change_group("admin")
+        .build()
+        .await
+        .unwrap();
+
+    let err = client.add_nat_rule(ssh_rule()).await.unwrap_err();
+    assert!(err.is::<PermissionDenied>());
+    assert!(!sent(&livebox, "setPortForwarding"));
+    let requests = livebox.requests().len();
+    let err = client
+        .raw(
+            "/sysbus/Firewall:setPortForwarding",
+            &json!({"parameters": {}}),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.is::<PermissionDenied>());
+    assert_eq!(livebox.requests().len(), requests);
+    // reads are still allowed
+    client.structured_nat_rules().await.unwrap();
+    client
+        .raw("/sysbus/NMC:getWANStatus", &json!({"parameters": {}}))
+        .await
+        .unwrap();
+    client.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn lists_the_groups_missing_from_the_login_response() {
+    let livebox = MockLiveboxBuilder::default()
+        .with_user("guest", "secret", &["http"])
+        .without_login_groups()
+        .start()
+        .unwrap();
+    let guest = livebox
+        .client_builder()
+        .with_credentials("guest".to_string(), "secret".to_string().into())
+        .with_change_group("admin")
+        .build()
+        .await
+        .unwrap();
+    assert!(guest.groups().is_empty());
+    assert_eq!(guest.known_groups().await, ["http"]);
+    let err = guest.add_nat_rule(ssh_rule()).await.unwrap_err();
+    assert!(err.is::<PermissionDenied>());
+    assert!(!sent(&livebox, "setPortForwarding"));
+    guest.logout().await.unwrap();
+
+    let admin = livebox
+        .client_builder()
+        .with_change_group("admin")
+        .build()
+        .await
+        .unwrap();
+    admin.add_nat_rule(ssh_rule()).await.unwrap();
+    assert!(sent(&livebox, "setPortForwarding"));
+    admin.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn audits_raw_calls_by_their_sysbus_path() {
+    let path = env::temp_dir().join(format!(
+        "livebox-cli-raw-audit-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    let livebox = MockLivebox::start().unwrap();
+    let client = livebox
+        .client_builder()
+        .with_audit_log(AuditLog::new(path.clone(), "raw".to_string()))
+        .build()
+        .await
+        .unwrap();
+
+    let parameters = json!({"parameters": {}});
+    client
+        .raw("/sysbus/NMC:getWANStatus", &parameters)
+        .await
+        .unwrap();
+    let reply = client
+        .raw("/sysbus/NeMo/Intf/lan:setFirstParameter", &parameters)
+        .await
+        .unwrap();
+    assert!(reply["errors"].is_array());
+    client.logout().await.unwrap();
+
+    let entries = AuditLog::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].service, "NeMo.Intf.lan");
+    assert_eq!(entries[0].method, "setFirstParameter");
+    assert_eq!(entries[0].status, "failed");
+    assert!(entries[0]
+        .error
+        .as_ref()
+        .unwrap()
+        .contains("\"error\":196618"));
+}